@@ -0,0 +1,41 @@
+use game_engine_3d::*;
+use map_element::*;
+use map_generator::MapGenerator;
+
+fn main() {
+    let seed = std::env::args()
+        .nth(1)
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or(2021);
+    let resolution = Size {
+        width: 1280.0,
+        height: 720.0,
+    };
+    let number_of_rays = 10000;
+    let vertical_angle_value = Radians::new(std::f64::consts::PI * 0.375);
+    let wall_height = 5.0;
+    let render_threads_amount = 3;
+
+    let generated_map = MapGenerator::new(seed, 8, None).dungeon(60, 40, 10);
+    let map = generated_map.wall_map.get_map();
+    let player = Player::new(
+        Angle {
+            start: Radians::new(std::f64::consts::PI * 3.0 / 4.0),
+            end: Radians::new(std::f64::consts::PI * 5.0 / 4.0),
+        },
+        generated_map.spawn.clone(),
+        number_of_rays,
+    );
+    let map_elements = generated_map.into_map_elements(DoorVelocity::Fast);
+
+    let mut engine = Engine::new(
+        resolution,
+        vertical_angle_value,
+        wall_height,
+        map,
+        player,
+        map_elements,
+        render_threads_amount,
+    );
+    engine.start();
+}
@@ -0,0 +1,487 @@
+use game_engine_3d::level::{Level, LevelDoor};
+use game_engine_3d::map_element::{
+    Color, DoorArea, DoorType, DoorVelocity, MapElement, Point, Rectangle, WallMap,
+};
+use game_engine_3d::validator::MapValidator;
+use game_engine_3d::{Angle, Coordinate, Engine, Radians, Size};
+use glutin_window::GlutinWindow;
+use graphics::Transformed;
+use opengl_graphics::{CreateTexture, Filter, Format, GlGraphics, OpenGL, Texture};
+use opengl_graphics::{TextureSettings, UpdateTexture};
+use piston::event_loop::{EventSettings, Events};
+use piston::input::{Button, Key, MouseButton, PressEvent, ReleaseEvent, RenderEvent};
+use piston::window::WindowSettings;
+use piston::{AdvancedWindow, MouseCursorEvent};
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "usage: level_editor <level> [width height]";
+const WINDOW_SIZE: [f64; 2] = [1024.0, 768.0];
+const OPENGL_VERSION: OpenGL = OpenGL::V3_2;
+const BACKGROUND_COLOR: [f32; 4] = [0.2, 0.2, 0.2, 1.0];
+const SELECTED_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const OPEN_AREA_COLOR: [f32; 4] = [0.0, 0.8, 0.8, 1.0];
+const SPAWN_COLOR: [f32; 4] = [0.9, 0.1, 0.1, 1.0];
+const NUMBER_OF_RAYS: usize = 10000;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Mode {
+    Paint,
+    Door,
+    OpenArea,
+    Spawn,
+}
+
+enum Drag {
+    Paint(bool),
+    NewDoor(Point),
+    // second point of the door is moved, first point stays in place
+    ResizeDoor(usize),
+    OpenArea(Point),
+    Spawn,
+}
+
+struct Editor {
+    level: Level,
+    path: PathBuf,
+    mode: Mode,
+    selected_door: Option<usize>,
+    drag: Option<Drag>,
+    cursor: Point,
+    scale: f64,
+    is_image_changed: bool,
+}
+
+fn new_level(width: u32, height: u32) -> Level {
+    let mut image = image::RgbaImage::from_pixel(width, height, image::Rgba([255, 255, 255, 255]));
+    for x in 0..width {
+        image.put_pixel(x, 0, image::Rgba([0, 0, 0, 255]));
+        image.put_pixel(x, height - 1, image::Rgba([0, 0, 0, 255]));
+    }
+    for y in 0..height {
+        image.put_pixel(0, y, image::Rgba([0, 0, 0, 255]));
+        image.put_pixel(width - 1, y, image::Rgba([0, 0, 0, 255]));
+    }
+    Level::new(
+        WallMap::from_image(image, None),
+        Coordinate {
+            x: width as f64 / 2.0,
+            y: height as f64 / 2.0,
+        },
+        Angle {
+            start: Radians::new(PI * 3.0 / 4.0),
+            end: Radians::new(PI * 5.0 / 4.0),
+        },
+    )
+}
+
+fn rectangle(point_a: &Point, point_b: &Point) -> Rectangle {
+    Rectangle {
+        point_a: point_a.clone(),
+        point_b: point_b.clone(),
+    }
+}
+
+// returns [x, y, width, height] of the inclusive rectangle
+fn rectangle_bounds(rectangle: &Rectangle) -> [f64; 4] {
+    let min_x = rectangle.point_a.x.min(rectangle.point_b.x);
+    let min_y = rectangle.point_a.y.min(rectangle.point_b.y);
+    let max_x = rectangle.point_a.x.max(rectangle.point_b.x);
+    let max_y = rectangle.point_a.y.max(rectangle.point_b.y);
+    [
+        min_x as f64,
+        min_y as f64,
+        (max_x - min_x + 1) as f64,
+        (max_y - min_y + 1) as f64,
+    ]
+}
+
+fn is_point_in_rectangle(point: &Point, rectangle: &Rectangle) -> bool {
+    let [x, y, width, height] = rectangle_bounds(rectangle);
+    let (px, py) = (point.x as f64, point.y as f64);
+    px >= x && py >= y && px < x + width && py < y + height
+}
+
+fn next_velocity(velocity: &DoorVelocity) -> DoorVelocity {
+    match velocity {
+        DoorVelocity::VerySlow => DoorVelocity::Slow,
+        DoorVelocity::Slow => DoorVelocity::Fast,
+        DoorVelocity::Fast => DoorVelocity::VeryFast,
+        DoorVelocity::VeryFast => DoorVelocity::VerySlow,
+    }
+}
+
+impl Editor {
+    fn title(&self) -> String {
+        let door = match self.selected_door {
+            Some(index) => format!(
+                " | door {}: {:?} {:?}",
+                index,
+                self.level.doors[index].door_area.door_type,
+                self.level.doors[index].velocity
+            ),
+            None => String::new(),
+        };
+        format!(
+            "{} | {:?} mode | ({}, {}){}",
+            self.path.display(),
+            self.mode,
+            self.cursor.x,
+            self.cursor.y,
+            door
+        )
+    }
+
+    fn set_wall(&mut self, is_wall: bool) {
+        let cursor = self.cursor.clone();
+        if self.level.wall_map.set_wall(&cursor, is_wall) {
+            self.is_image_changed = true;
+        }
+    }
+
+    fn set_spawn_direction(&mut self) {
+        let direction = (self.cursor.y as f64 + 0.5 - self.level.spawn.y)
+            .atan2(self.cursor.x as f64 + 0.5 - self.level.spawn.x);
+        let half_angle = (self.level.angle.end - self.level.angle.start).to_f64() / 2.0;
+        self.level.angle = Angle {
+            start: Radians::new(direction - half_angle),
+            end: Radians::new(direction + half_angle),
+        };
+    }
+
+    fn on_press(&mut self, button: MouseButton) {
+        let cursor = self.cursor.clone();
+        match (self.mode, button) {
+            (Mode::Paint, MouseButton::Left) | (Mode::Paint, MouseButton::Right) => {
+                let is_wall = button == MouseButton::Left;
+                self.set_wall(is_wall);
+                self.drag = Some(Drag::Paint(is_wall));
+            }
+            (Mode::Door, MouseButton::Left) => {
+                self.selected_door = self
+                    .level
+                    .doors
+                    .iter()
+                    .position(|door| is_point_in_rectangle(&cursor, &door.door_area.area));
+                self.drag = match self.selected_door {
+                    Some(index) => Some(Drag::ResizeDoor(index)),
+                    None => Some(Drag::NewDoor(cursor)),
+                };
+            }
+            (Mode::OpenArea, MouseButton::Left) if self.selected_door.is_some() => {
+                self.drag = Some(Drag::OpenArea(cursor));
+            }
+            (Mode::Spawn, MouseButton::Left) => {
+                self.level.spawn = Coordinate {
+                    x: cursor.x as f64 + 0.5,
+                    y: cursor.y as f64 + 0.5,
+                };
+                self.drag = Some(Drag::Spawn);
+            }
+            _ => {}
+        }
+    }
+
+    fn on_move(&mut self) {
+        match self.drag {
+            Some(Drag::Paint(is_wall)) => self.set_wall(is_wall),
+            Some(Drag::ResizeDoor(index)) => {
+                let area = &mut self.level.doors[index].door_area.area;
+                // the corner nearest to the cursor follows it
+                if (area.point_a.x - self.cursor.x).abs() + (area.point_a.y - self.cursor.y).abs()
+                    < (area.point_b.x - self.cursor.x).abs()
+                        + (area.point_b.y - self.cursor.y).abs()
+                {
+                    std::mem::swap(&mut area.point_a, &mut area.point_b);
+                }
+                area.point_b = self.cursor.clone();
+            }
+            Some(Drag::Spawn) => self.set_spawn_direction(),
+            _ => {}
+        }
+    }
+
+    fn on_release(&mut self) {
+        match self.drag.take() {
+            Some(Drag::NewDoor(start)) => {
+                let area = rectangle(&start, &self.cursor);
+                let [_, _, width, height] = rectangle_bounds(&area);
+                self.level.doors.push(LevelDoor {
+                    door_area: DoorArea {
+                        area,
+                        door_type: if width >= height {
+                            DoorType::Horizontal
+                        } else {
+                            DoorType::Vertical
+                        },
+                        open_area: None,
+                    },
+                    velocity: DoorVelocity::Fast,
+                    color: None,
+                });
+                self.selected_door = Some(self.level.doors.len() - 1);
+            }
+            Some(Drag::OpenArea(start)) => {
+                if let Some(index) = self.selected_door {
+                    self.level.doors[index].door_area.open_area =
+                        Some(rectangle(&start, &self.cursor));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn save(&self) {
+        match self.level.save(&self.path) {
+            Ok(()) => println!("saved {}", self.path.display()),
+            Err(error) => {
+                eprintln!("cannot save {}: {}", self.path.display(), error);
+                return;
+            }
+        }
+        let doors = self
+            .level
+            .doors
+            .iter()
+            .map(|door| door.door_area.clone())
+            .collect::<Vec<_>>();
+        let report = MapValidator::new(1).validate(&self.level.wall_map, &doors, &self.level.spawn);
+        for error in &report.errors {
+            println!("warning: {:?}", error);
+        }
+    }
+
+    // returns true when the 3D preview was requested
+    fn on_key(&mut self, key: Key) -> bool {
+        match key {
+            Key::D1 => self.mode = Mode::Paint,
+            Key::D2 => self.mode = Mode::Door,
+            Key::D3 => self.mode = Mode::OpenArea,
+            Key::D4 => self.mode = Mode::Spawn,
+            Key::S => self.save(),
+            Key::Tab => return true,
+            _ => {}
+        }
+        if let Some(index) = self.selected_door {
+            match key {
+                Key::Delete | Key::Backspace => {
+                    self.level.doors.remove(index);
+                    self.selected_door = None;
+                }
+                Key::T => {
+                    let door_area = &mut self.level.doors[index].door_area;
+                    door_area.door_type = if door_area.door_type == DoorType::Vertical {
+                        DoorType::Horizontal
+                    } else {
+                        DoorType::Vertical
+                    };
+                }
+                Key::V => {
+                    let door = &mut self.level.doors[index];
+                    door.velocity = next_velocity(&door.velocity);
+                }
+                Key::O => self.level.doors[index].door_area.open_area = None,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    fn wall_map_pixels(&self) -> Vec<u8> {
+        let map = self.level.wall_map.get_map();
+        let mut pixels = Vec::with_capacity((map.width * map.height * 4) as usize);
+        for y in 0..map.height as u32 {
+            for x in 0..map.width as u32 {
+                if self.level.wall_map.is_wall_pixel(x, y) {
+                    pixels.extend_from_slice(&[0, 0, 0, 255]);
+                } else {
+                    pixels.extend_from_slice(&[255, 255, 255, 255]);
+                }
+            }
+        }
+        pixels
+    }
+
+    fn draw(&self, context: graphics::Context, g: &mut GlGraphics, texture: &Texture) {
+        let transform = context.transform.scale(self.scale, self.scale);
+        graphics::clear(BACKGROUND_COLOR, g);
+        graphics::image(texture, transform, g);
+
+        let line_width = 1.0 / self.scale;
+        for (index, door) in self.level.doors.iter().enumerate() {
+            let mut color: [f32; 4] = door.color.clone().unwrap_or(Color::Blue).into();
+            color[3] = 0.7;
+            graphics::rectangle(color, rectangle_bounds(&door.door_area.area), transform, g);
+            if self.selected_door == Some(index) {
+                graphics::Rectangle::new_border(SELECTED_COLOR, line_width).draw(
+                    rectangle_bounds(&door.door_area.area),
+                    &context.draw_state,
+                    transform,
+                    g,
+                );
+                graphics::Rectangle::new_border(OPEN_AREA_COLOR, line_width).draw(
+                    rectangle_bounds(&door.door_area.open_door_area()),
+                    &context.draw_state,
+                    transform,
+                    g,
+                );
+            }
+        }
+        if let Some(Drag::NewDoor(start)) | Some(Drag::OpenArea(start)) = &self.drag {
+            graphics::Rectangle::new_border(SELECTED_COLOR, line_width).draw(
+                rectangle_bounds(&rectangle(start, &self.cursor)),
+                &context.draw_state,
+                transform,
+                g,
+            );
+        }
+
+        let spawn = &self.level.spawn;
+        graphics::ellipse(
+            SPAWN_COLOR,
+            graphics::ellipse::circle(spawn.x, spawn.y, 1.0),
+            transform,
+            g,
+        );
+        for radians in [self.level.angle.start, self.level.angle.end].iter() {
+            graphics::line(
+                SPAWN_COLOR,
+                line_width / 2.0,
+                [
+                    spawn.x,
+                    spawn.y,
+                    spawn.x + radians.to_f64().cos() * 10.0,
+                    spawn.y + radians.to_f64().sin() * 10.0,
+                ],
+                transform,
+                g,
+            );
+        }
+    }
+
+    // returns true when the 3D preview was requested, false when the window was closed
+    fn run(&mut self) -> bool {
+        let mut window: GlutinWindow =
+            WindowSettings::new("level editor", [WINDOW_SIZE[0], WINDOW_SIZE[1]])
+                .graphics_api(OPENGL_VERSION)
+                .exit_on_esc(true)
+                .resizable(false)
+                .build()
+                .unwrap();
+        let mut gl = GlGraphics::new(OPENGL_VERSION);
+        let map = self.level.wall_map.get_map();
+        let size = [map.width as u32, map.height as u32];
+        let texture_settings = TextureSettings::new().filter(Filter::Nearest);
+        let mut texture: Texture = CreateTexture::create(
+            &mut (),
+            Format::Rgba8,
+            &self.wall_map_pixels(),
+            size,
+            &texture_settings,
+        )
+        .unwrap();
+        self.scale = (WINDOW_SIZE[0] / map.width as f64).min(WINDOW_SIZE[1] / map.height as f64);
+
+        let mut events = Events::new(EventSettings::new());
+        while let Some(event) = events.next(&mut window) {
+            if let Some(position) = event.mouse_cursor_args() {
+                self.cursor = Point {
+                    x: (position[0] / self.scale) as i64,
+                    y: (position[1] / self.scale) as i64,
+                };
+                self.on_move();
+                window.set_title(self.title());
+            }
+            if let Some(button) = event.press_args() {
+                match button {
+                    Button::Mouse(mouse_button) => self.on_press(mouse_button),
+                    Button::Keyboard(key) => {
+                        if self.on_key(key) {
+                            return true;
+                        }
+                        window.set_title(self.title());
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(Button::Mouse(_)) = event.release_args() {
+                self.on_release();
+                window.set_title(self.title());
+            }
+            if let Some(args) = event.render_args() {
+                if self.is_image_changed {
+                    UpdateTexture::update(
+                        &mut texture,
+                        &mut (),
+                        Format::Rgba8,
+                        &self.wall_map_pixels(),
+                        [0, 0],
+                        size,
+                    )
+                    .unwrap();
+                    self.is_image_changed = false;
+                }
+                gl.draw(args.viewport(), |context, g| {
+                    self.draw(context, g, &texture)
+                });
+            }
+        }
+        false
+    }
+
+    fn preview(&self) {
+        let mut engine = Engine::new(
+            Size {
+                width: 1280.0,
+                height: 720.0,
+            },
+            Radians::new(PI * 0.375),
+            5.0,
+            self.level.wall_map.get_map(),
+            self.level.player(NUMBER_OF_RAYS),
+            self.level.map_elements(),
+            3,
+        );
+        engine.start();
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let path = match args.first() {
+        Some(path) => PathBuf::from(path),
+        None => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+    let level = if Path::new(&path).exists() {
+        Level::load(&path).unwrap_or_else(|error| {
+            eprintln!("cannot load {}: {}", path.display(), error);
+            std::process::exit(2);
+        })
+    } else {
+        let size = |index: usize| {
+            args.get(index)
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(128)
+                .max(3)
+        };
+        new_level(size(1), size(2))
+    };
+
+    let mut editor = Editor {
+        level,
+        path,
+        mode: Mode::Paint,
+        selected_door: None,
+        drag: None,
+        cursor: Point::default(),
+        scale: 1.0,
+        is_image_changed: false,
+    };
+    // editor window is closed during the 3D preview, engine creates its own window
+    while editor.run() {
+        editor.preview();
+    }
+}
@@ -0,0 +1,112 @@
+use game_engine_3d::map_element::{DoorArea, DoorType, Point, Rectangle, WallMap};
+use game_engine_3d::validator::MapValidator;
+use game_engine_3d::Coordinate;
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "usage: validate_level <image> <spawn_x> <spawn_y> [--cell-size N] \
+[--door x1 y1 x2 y2 vertical|horizontal [ox1 oy1 ox2 oy2]]...";
+
+fn parse<T: std::str::FromStr>(value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| String::from("missing argument"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid argument: {}", value))
+}
+
+fn parse_rectangle(args: &[String]) -> Result<Rectangle, String> {
+    Ok(Rectangle {
+        point_a: Point {
+            x: parse(args.first())?,
+            y: parse(args.get(1))?,
+        },
+        point_b: Point {
+            x: parse(args.get(2))?,
+            y: parse(args.get(3))?,
+        },
+    })
+}
+
+fn parse_args(args: &[String]) -> Result<(String, Coordinate, u32, Vec<DoorArea>), String> {
+    let image = args.first().ok_or_else(|| String::from(USAGE))?.clone();
+    let spawn = Coordinate {
+        x: parse(args.get(1))?,
+        y: parse(args.get(2))?,
+    };
+    let mut cell_size = 1;
+    let mut doors = vec![];
+    let mut index = 3;
+    while index < args.len() {
+        match args[index].as_str() {
+            "--cell-size" => {
+                cell_size = parse(args.get(index + 1))?;
+                index += 2;
+            }
+            "--door" => {
+                let area = parse_rectangle(args.get(index + 1..).unwrap_or(&[]))?;
+                let door_type = match args.get(index + 5).map(String::as_str) {
+                    Some("vertical") => DoorType::Vertical,
+                    Some("horizontal") => DoorType::Horizontal,
+                    _ => return Err(String::from("door type has to be vertical or horizontal")),
+                };
+                index += 6;
+                let open_area = match args.get(index) {
+                    Some(arg) if !arg.starts_with("--") => {
+                        index += 4;
+                        Some(parse_rectangle(&args[index - 4..])?)
+                    }
+                    _ => None,
+                };
+                doors.push(DoorArea {
+                    area,
+                    door_type,
+                    open_area,
+                });
+            }
+            arg => return Err(format!("unknown argument: {}\n{}", arg, USAGE)),
+        }
+    }
+    Ok((image, spawn, cell_size, doors))
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (image, spawn, cell_size, doors) = parse_args(&args).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(2);
+    });
+    let wall_map = WallMap::new(Path::new(&image), None).unwrap_or_else(|error| {
+        eprintln!("cannot load {}: {}", image, error);
+        process::exit(2);
+    });
+
+    let report = MapValidator::new(cell_size).validate(&wall_map, &doors, &spawn);
+    for error in &report.errors {
+        println!("error: {:?}", error);
+    }
+    println!("reachable area: {}", report.reachable_area);
+    println!("dead ends: {}", report.dead_ends.len());
+    for dead_end in &report.dead_ends {
+        println!("  ({}, {})", dead_end.x, dead_end.y);
+    }
+    println!(
+        "disconnected regions: {}",
+        report.disconnected_regions.len()
+    );
+    for region in &report.disconnected_regions {
+        println!(
+            "  ({}, {}) size {}{}",
+            region.point.x,
+            region.point.y,
+            region.size,
+            if region.touches_border {
+                " touches border"
+            } else {
+                ""
+            }
+        );
+    }
+    if !report.is_valid() {
+        process::exit(1);
+    }
+}
@@ -1,9 +1,12 @@
-use crate::map_element::MapElement;
-use crate::player_utils::Radians;
+use crate::map_element::{MapElements, MapElementsHandle};
+use crate::player_utils::{Axis, PlayerHandle};
+use crate::render_thread::RenderMode;
 use graphics::types::Color;
 use graphics::Transformed;
 use mockall_double::double;
-use piston::input::{ButtonEvent, MouseRelativeEvent, RenderEvent, UpdateEvent};
+use piston::input::{
+    ButtonEvent, ControllerAxisEvent, MouseRelativeEvent, RenderEvent, UpdateEvent,
+};
 use std::sync::mpsc::{channel, Sender};
 use std::sync::{Arc, RwLock};
 use std::thread::JoinHandle;
@@ -22,11 +25,11 @@ cfg_if::cfg_if! {
         use crate::wrapper::test_utils::Window as GlutinWindow;
         use crate::wrapper::test_utils::GlGraphics;
     } else {
-        use crate::graph::Walls;
         use crate::map::Map;
+        use crate::player_utils::Radians;
         use crate::generator::PolygonGenerator;
         use crate::generator::PointGenerator;
-        use crate::render_thread::RenderThread;
+        use crate::render_thread::{InSight, RenderThread};
         use glutin_window::GlutinWindow;
         use opengl_graphics::GlGraphics;
         use piston::AdvancedWindow;
@@ -43,7 +46,8 @@ pub struct Engine {
     graphics: GlGraphics,
     events: Events,
     player: Arc<RwLock<Player>>,
-    map_elements: Arc<RwLock<Vec<Box<dyn MapElement>>>>,
+    map_elements: Arc<RwLock<MapElements>>,
+    render_mode: Arc<RwLock<RenderMode>>,
     start_render_notifiers: Vec<Sender<bool>>,
     render_threads: Vec<JoinHandle<()>>,
 }
@@ -59,21 +63,29 @@ impl Engine {
         wall_height: f64,
         map: Map,
         player: Player,
-        map_elements: Vec<Box<dyn MapElement>>,
+        map_elements: impl Into<MapElements>,
         render_threads_amount: i64,
     ) -> Engine {
         let polygon_generator = PolygonGenerator {
-            point_generator: PointGenerator::new(resolution, vertical_angle_value, wall_height),
+            point_generator: PointGenerator::new(
+                resolution,
+                vertical_angle_value,
+                player.get_angle_value(),
+                wall_height,
+            ),
         };
         let map = Arc::new(map);
         let rays = Arc::new(player.get_all_rays());
         let player = Arc::new(RwLock::new(player));
+        let mut map_elements: MapElements = map_elements.into();
+        map_elements.update_index();
         let map_elements = Arc::new(RwLock::new(map_elements));
+        let render_mode = Arc::new(RwLock::new(RenderMode::default()));
         let mut start_render_notifiers = vec![];
         let render_threads_amount = Self::limit_threads_amount(render_threads_amount);
         let mut render_threads = Vec::with_capacity(render_threads_amount);
 
-        let (sender_walls, receiver_walls) = channel::<(Walls, usize)>();
+        let (sender_walls, receiver_walls) = channel::<(InSight, usize)>();
 
         for thread_index in 0..render_threads_amount {
             let (start_render_notifier, start_render_receiver) = channel::<bool>();
@@ -86,6 +98,9 @@ impl Engine {
                 sender_walls: sender_walls.clone(),
                 thread_index,
                 threads_amount: render_threads_amount,
+                render_mode: Arc::clone(&render_mode),
+                columns: resolution.width as usize,
+                wall_height,
             };
             render_threads.push(RenderThread::start_thread(render_thread));
             start_render_notifiers.push(start_render_notifier);
@@ -102,6 +117,7 @@ impl Engine {
             events: Events::new(),
             player,
             map_elements,
+            render_mode,
             start_render_notifiers,
             render_threads,
         }
@@ -131,18 +147,44 @@ impl Engine {
         return render_threads_amount as usize;
     }
 
+    // render threads use the new mode from the next frame
+    pub fn with_render_mode(self, render_mode: RenderMode) -> Self {
+        *self.render_mode.write().unwrap() = render_mode;
+        self
+    }
+
+    pub fn map_elements_handle(&self) -> MapElementsHandle {
+        MapElementsHandle::new(Arc::clone(&self.map_elements))
+    }
+
+    pub fn player_handle(&self) -> PlayerHandle {
+        PlayerHandle::new(Arc::clone(&self.player))
+    }
+
     pub fn start(&mut self) {
         while let Some(e) = self.events.next_event(&mut self.window) {
             if let Some(args) = e.render_args() {
                 for start_render_notifier in &self.start_render_notifiers {
                     start_render_notifier.send(true).unwrap();
                 }
-                let polygons = self.generator.generate_polygons(&self.player);
-                self.graphics.draw(args.viewport(), |c, g| {
+                // floors and ceilings are drawn under the walls
+                let surfaces = self.map_elements.read().unwrap().surfaces();
+                let mut polygons = self.generator.generate_surfaces(&self.player, surfaces);
+                polygons.extend(self.generator.generate_polygons(&self.player));
+                let horizon_offset = self.generator.horizon_offset(&self.player);
+                let roll = self.player.read().unwrap().view_roll();
+                self.graphics.draw(args.viewport(), move |c, g| {
+                    let [width, height] = c.viewport.unwrap().draw_size;
+                    let (center_x, center_y) = (width as f64 / 2.0, height as f64 / 2.0);
+                    // view is rolled around the center of the screen, horizon
+                    // is moved by the pitch of the player (y-shearing)
                     let transform = c
                         .transform
+                        .trans(center_x, center_y)
+                        .rot_rad(-roll)
+                        .trans(-center_x, -center_y)
                         .flip_v()
-                        .trans(0.0, -(c.viewport.unwrap().draw_size[1] as f64 / 2.0));
+                        .trans(0.0, -center_y - horizon_offset);
                     Graphics::clear(g, BACKGROUND_COLOR);
                     for polygon in polygons {
                         Graphics::draw_polygon(
@@ -157,11 +199,15 @@ impl Engine {
             }
 
             if let Some(args) = e.mouse_relative_args() {
-                let mut player = self.player.write().unwrap();
-                if args[0] > 0.0 {
-                    player.rotate_left(Radians::new(args[0] / 1000.0));
-                } else {
-                    player.rotate_right(Radians::new((args[0] / 1000.0).abs()));
+                self.player.write().unwrap().mouse_look(args[0], args[1]);
+            }
+
+            if let Some(args) = e.controller_axis_args() {
+                if let Some((axis, sign)) = controller_axis(args.axis) {
+                    self.player
+                        .write()
+                        .unwrap()
+                        .set_axis(axis, sign * args.position);
                 }
             }
 
@@ -181,6 +227,24 @@ impl Engine {
                         piston::input::Key::D => {
                             player.move_right(into_bool(args.state));
                         }
+                        piston::input::Key::Space if into_bool(args.state) => {
+                            player.jump();
+                        }
+                        piston::input::Key::LCtrl => {
+                            player.crouch(into_bool(args.state));
+                        }
+                        piston::input::Key::LShift => {
+                            player.run(into_bool(args.state));
+                        }
+                        piston::input::Key::LAlt => {
+                            player.walk_slowly(into_bool(args.state));
+                        }
+                        piston::input::Key::Left => {
+                            player.turn_left(into_bool(args.state));
+                        }
+                        piston::input::Key::Right => {
+                            player.turn_right(into_bool(args.state));
+                        }
                         _ => {}
                     }
                 }
@@ -189,14 +253,16 @@ impl Engine {
             if let Some(args) = e.update_args() {
                 let mut map_elements = self.map_elements.write().unwrap();
                 let mut player = self.player.write().unwrap();
-                if player.update() {
-                    for map_element in &mut *map_elements {
+                player.update_height(args.dt);
+                if player.update(&map_elements, args.dt) {
+                    for map_element in map_elements.iter_mut() {
                         map_element.as_mut().on_position_update(player.position());
                     }
                 }
-                for map_element in &mut *map_elements {
+                for map_element in map_elements.iter_mut() {
                     map_element.as_mut().update(args.dt);
                 }
+                map_elements.update_index();
             }
         }
 
@@ -210,6 +276,19 @@ impl Engine {
     }
 }
 
+// Axis of the player driven by the gamepad axis and the sign of the value,
+// the left stick moves and the right one turns and looks. Stick moved down
+// gives positive value.
+fn controller_axis(axis: u8) -> Option<(Axis, f64)> {
+    match axis {
+        0 => Some((Axis::MoveRight, 1.0)),
+        1 => Some((Axis::MoveForward, -1.0)),
+        2 => Some((Axis::Turn, 1.0)),
+        3 => Some((Axis::LookUp, -1.0)),
+        _ => None,
+    }
+}
+
 fn into_bool(state: piston::input::ButtonState) -> bool {
     if state == piston::input::ButtonState::Press {
         return true;
@@ -222,9 +301,9 @@ mod tests {
     #![allow(non_upper_case_globals)]
     use super::*;
     use crate::generator::{MockObjectGenerator, Polygon};
-    use crate::graph::Coordinate;
-    use crate::map_element::{Color, MockMapElement};
-    use crate::player_utils::{MockPlayer, Radians};
+    use crate::graph::{Coordinate, Surface};
+    use crate::map_element::{unbounded_map_element, Color, MapElement};
+    use crate::player_utils::MockPlayer;
     use crate::wrapper::test_utils::{GlGraphics, Window};
     use crate::wrapper::{MockEvents, MockGraphics};
     use mockall::*;
@@ -315,6 +394,71 @@ mod tests {
         assert_eq!(Engine::limit_threads_amount(0), 1);
     }
 
+    #[test]
+    fn map_elements_handle() {
+        let engine = Engine {
+            generator: MockObjectGenerator::new(),
+            window: Window {},
+            graphics: GlGraphics {},
+            events: MockEvents::default(),
+            player: Arc::new(RwLock::new(MockPlayer::default())),
+            map_elements: Arc::new(RwLock::new(MapElements::new())),
+            render_mode: Arc::new(RwLock::new(RenderMode::default())),
+            start_render_notifiers: vec![],
+            render_threads: vec![],
+        };
+
+        let handle = engine.map_elements_handle();
+        let id = handle.insert(unbounded_map_element());
+        assert_eq!(engine.map_elements.read().unwrap().ids(), &[id]);
+        assert!(handle.remove(id).is_some());
+        assert!(engine.map_elements.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn player_handle() {
+        let mut player = MockPlayer::default();
+        player
+            .expect_set_zoom()
+            .times(1)
+            .withf(|zoom| *zoom == 2.0)
+            .return_const(());
+        let engine = Engine {
+            generator: MockObjectGenerator::new(),
+            window: Window {},
+            graphics: GlGraphics {},
+            events: MockEvents::default(),
+            player: Arc::new(RwLock::new(player)),
+            map_elements: Arc::new(RwLock::new(MapElements::new())),
+            render_mode: Arc::new(RwLock::new(RenderMode::default())),
+            start_render_notifiers: vec![],
+            render_threads: vec![],
+        };
+
+        engine.player_handle().set_zoom(2.0);
+        engine.player.write().unwrap().checkpoint();
+    }
+
+    #[test]
+    fn with_render_mode() {
+        let engine = Engine {
+            generator: MockObjectGenerator::new(),
+            window: Window {},
+            graphics: GlGraphics {},
+            events: MockEvents::default(),
+            player: Arc::new(RwLock::new(MockPlayer::default())),
+            map_elements: Arc::new(RwLock::new(MapElements::new())),
+            render_mode: Arc::new(RwLock::new(RenderMode::default())),
+            start_render_notifiers: vec![],
+            render_threads: vec![],
+        };
+        let render_mode = Arc::clone(&engine.render_mode);
+        assert_eq!(*render_mode.read().unwrap(), RenderMode::Walls);
+
+        let _engine = engine.with_render_mode(RenderMode::Columns);
+        assert_eq!(*render_mode.read().unwrap(), RenderMode::Columns);
+    }
+
     #[test]
     fn start_render_event() {
         let mut seq = Sequence::new();
@@ -324,8 +468,20 @@ mod tests {
         let graphics = GlGraphics {};
         let mut events = MockEvents::default();
         let player = Arc::new(RwLock::new(MockPlayer::default()));
-        let map_elements: Arc<RwLock<Vec<Box<dyn MapElement>>>> =
-            Arc::new(RwLock::new(vec![Box::new(MockMapElement::new())]));
+        let surface = Surface {
+            corners: vec![Coordinate::default(); 4],
+            height: 0.5,
+            color: Color::Green,
+            is_floor: true,
+        };
+        let mut map_element = unbounded_map_element();
+        map_element
+            .expect_surfaces()
+            .times(1)
+            .return_const(vec![surface.clone()]);
+        let map_elements = Arc::new(RwLock::new(MapElements::from(vec![
+            map_element as Box<dyn MapElement>,
+        ])));
 
         let mut start_render_notifiers = vec![];
         let mut start_render_receivers = vec![];
@@ -339,6 +495,10 @@ mod tests {
         let clear_ctx = MockGraphics::clear_context();
         let draw_polygon_ctx = MockGraphics::draw_polygon_context();
 
+        let surface_polygon = Polygon {
+            area: [[0.0, 0.0], [1.0, 0.0], [1.0, -1.0], [0.0, -1.0]],
+            color: Color::Green,
+        };
         let polygons = vec![
             Polygon {
                 area: [[0.0, 1.0], [2.0, 3.0], [4.0, 5.0], [6.0, 7.0]],
@@ -361,11 +521,29 @@ mod tests {
             .return_const(Some(event))
             .in_sequence(&mut seq);
 
+        generator
+            .expect_generate_surfaces()
+            .times(1)
+            .withf(move |_, surfaces| *surfaces == vec![surface.clone()])
+            .return_const(vec![surface_polygon.clone()])
+            .in_sequence(&mut seq);
         generator
             .expect_generate_polygons()
             .times(1)
             .return_const(polygons.clone())
             .in_sequence(&mut seq);
+        generator
+            .expect_horizon_offset()
+            .times(1)
+            .return_const(0.0)
+            .in_sequence(&mut seq);
+        player
+            .write()
+            .unwrap()
+            .expect_view_roll()
+            .times(1)
+            .return_const(0.0)
+            .in_sequence(&mut seq);
 
         clear_ctx
             .expect()
@@ -374,7 +552,8 @@ mod tests {
             .return_const(())
             .in_sequence(&mut seq);
 
-        for polygon in polygons.into_iter() {
+        // surfaces are drawn under the walls
+        for polygon in std::iter::once(surface_polygon).chain(polygons) {
             draw_polygon_ctx
                 .expect()
                 .times(1)
@@ -395,6 +574,7 @@ mod tests {
             events,
             player,
             map_elements,
+            render_mode: Arc::new(RwLock::new(RenderMode::default())),
             start_render_notifiers,
             render_threads: vec![],
         };
@@ -415,8 +595,9 @@ mod tests {
         let graphics = GlGraphics {};
         let mut events = MockEvents::default();
         let player = Arc::new(RwLock::new(MockPlayer::default()));
-        let map_elements: Arc<RwLock<Vec<Box<dyn MapElement>>>> =
-            Arc::new(RwLock::new(vec![Box::new(MockMapElement::new())]));
+        let map_elements = Arc::new(RwLock::new(MapElements::from(vec![
+            unbounded_map_element() as Box<dyn MapElement>,
+        ])));
 
         static motion_left: [f64; 2] = [3.0, 5.0];
         static motion_right: [f64; 2] = [-7.0, 9.0];
@@ -426,17 +607,33 @@ mod tests {
 
             call_move_event(&mut events, &mut seq, motion_left);
             player_write
-                .expect_rotate_left()
+                .expect_mouse_look()
                 .times(1)
-                .withf(|radians| *radians == Radians::new(motion_left[0] / 1000.0))
+                .withf(|dx, dy| *dx == motion_left[0] && *dy == motion_left[1])
                 .return_const(())
                 .in_sequence(&mut seq);
 
             call_move_event(&mut events, &mut seq, motion_right);
             player_write
-                .expect_rotate_right()
+                .expect_mouse_look()
                 .times(1)
-                .withf(|radians| *radians == Radians::new(motion_right[0].abs() / 1000.0))
+                .withf(|dx, dy| *dx == motion_right[0] && *dy == motion_right[1])
+                .return_const(())
+                .in_sequence(&mut seq);
+
+            // left stick moved up moves the player forward
+            events
+                .expect_next_event()
+                .times(1)
+                .return_const(Some(piston::Event::Input(
+                    Input::Move(Motion::ControllerAxis(ControllerAxisArgs::new(0, 1, -0.5))),
+                    None,
+                )))
+                .in_sequence(&mut seq);
+            player_write
+                .expect_set_axis()
+                .times(1)
+                .withf(|axis, value| *axis == Axis::MoveForward && *value == 0.5)
                 .return_const(())
                 .in_sequence(&mut seq);
 
@@ -450,6 +647,7 @@ mod tests {
             events,
             player,
             map_elements,
+            render_mode: Arc::new(RwLock::new(RenderMode::default())),
             start_render_notifiers: vec![],
             render_threads: vec![],
         };
@@ -466,8 +664,9 @@ mod tests {
         let window = Window {};
         let mut events = MockEvents::default();
         let graphics = GlGraphics {};
-        let map_elements: Arc<RwLock<Vec<Box<dyn MapElement>>>> =
-            Arc::new(RwLock::new(vec![Box::new(MockMapElement::new())]));
+        let map_elements = Arc::new(RwLock::new(MapElements::from(vec![
+            unbounded_map_element() as Box<dyn MapElement>,
+        ])));
         {
             let mut player_write = player.write().unwrap();
 
@@ -495,6 +694,69 @@ mod tests {
             call_key_event(&mut events, &mut seq, input::Key::D, ButtonState::Release);
             expect_move_right(&mut player_write, &mut seq, false);
 
+            call_key_event(&mut events, &mut seq, input::Key::Space, ButtonState::Press);
+            player_write
+                .expect_jump()
+                .times(1)
+                .return_const(())
+                .in_sequence(&mut seq);
+            call_key_event(
+                &mut events,
+                &mut seq,
+                input::Key::Space,
+                ButtonState::Release,
+            );
+
+            call_key_event(&mut events, &mut seq, input::Key::LCtrl, ButtonState::Press);
+            player_write
+                .expect_crouch()
+                .times(1)
+                .withf(|is_crouching| *is_crouching)
+                .return_const(())
+                .in_sequence(&mut seq);
+
+            call_key_event(
+                &mut events,
+                &mut seq,
+                input::Key::LShift,
+                ButtonState::Press,
+            );
+            player_write
+                .expect_run()
+                .times(1)
+                .withf(|is_running| *is_running)
+                .return_const(())
+                .in_sequence(&mut seq);
+
+            call_key_event(&mut events, &mut seq, input::Key::LAlt, ButtonState::Press);
+            player_write
+                .expect_walk_slowly()
+                .times(1)
+                .withf(|is_walking_slowly| *is_walking_slowly)
+                .return_const(())
+                .in_sequence(&mut seq);
+
+            call_key_event(&mut events, &mut seq, input::Key::Left, ButtonState::Press);
+            player_write
+                .expect_turn_left()
+                .times(1)
+                .withf(|is_turn| *is_turn)
+                .return_const(())
+                .in_sequence(&mut seq);
+
+            call_key_event(
+                &mut events,
+                &mut seq,
+                input::Key::Right,
+                ButtonState::Release,
+            );
+            player_write
+                .expect_turn_right()
+                .times(1)
+                .withf(|is_turn| !*is_turn)
+                .return_const(())
+                .in_sequence(&mut seq);
+
             call_none_event(&mut events, &mut seq);
         }
 
@@ -505,6 +767,7 @@ mod tests {
             events,
             player,
             map_elements,
+            render_mode: Arc::new(RwLock::new(RenderMode::default())),
             start_render_notifiers: vec![],
             render_threads: vec![],
         };
@@ -522,7 +785,7 @@ mod tests {
         let mut events = MockEvents::default();
         let player = Arc::new(RwLock::new(MockPlayer::default()));
 
-        let mut map_element = Box::new(MockMapElement::new());
+        let mut map_element = unbounded_map_element();
 
         let delta_time = 2.0;
 
@@ -537,9 +800,16 @@ mod tests {
                     },
                 ))))
                 .in_sequence(&mut seq);
+            player_write
+                .expect_update_height()
+                .times(1)
+                .withf(move |dt| *dt == delta_time)
+                .return_const(())
+                .in_sequence(&mut seq);
             player_write
                 .expect_update()
                 .times(1)
+                .withf(move |_, dt| *dt == delta_time)
                 .return_const(false)
                 .in_sequence(&mut seq);
             map_element
@@ -552,8 +822,9 @@ mod tests {
             call_none_event(&mut events, &mut seq);
         }
 
-        let map_elements: Arc<RwLock<Vec<Box<dyn MapElement>>>> =
-            Arc::new(RwLock::new(vec![map_element]));
+        let map_elements = Arc::new(RwLock::new(MapElements::from(vec![
+            map_element as Box<dyn MapElement>,
+        ])));
         let mut engine = Engine {
             generator,
             window,
@@ -561,6 +832,7 @@ mod tests {
             events,
             player,
             map_elements,
+            render_mode: Arc::new(RwLock::new(RenderMode::default())),
             start_render_notifiers: vec![],
             render_threads: vec![],
         };
@@ -578,7 +850,7 @@ mod tests {
         let mut events = MockEvents::default();
         let player = Arc::new(RwLock::new(MockPlayer::default()));
 
-        let mut map_element = Box::new(MockMapElement::new());
+        let mut map_element = unbounded_map_element();
 
         let delta_time = 2.0;
         let position = Coordinate { x: 10.0, y: 20.0 };
@@ -594,9 +866,16 @@ mod tests {
                     },
                 ))))
                 .in_sequence(&mut seq);
+            player_write
+                .expect_update_height()
+                .times(1)
+                .withf(move |dt| *dt == delta_time)
+                .return_const(())
+                .in_sequence(&mut seq);
             player_write
                 .expect_update()
                 .times(1)
+                .withf(move |_, dt| *dt == delta_time)
                 .return_const(true)
                 .in_sequence(&mut seq);
             player_write
@@ -620,8 +899,9 @@ mod tests {
 
         call_none_event(&mut events, &mut seq);
 
-        let map_elements: Arc<RwLock<Vec<Box<dyn MapElement>>>> =
-            Arc::new(RwLock::new(vec![map_element]));
+        let map_elements = Arc::new(RwLock::new(MapElements::from(vec![
+            map_element as Box<dyn MapElement>,
+        ])));
 
         let mut engine = Engine {
             generator,
@@ -630,6 +910,7 @@ mod tests {
             events,
             player,
             map_elements,
+            render_mode: Arc::new(RwLock::new(RenderMode::default())),
             start_render_notifiers: vec![],
             render_threads: vec![],
         };
@@ -1,7 +1,7 @@
 use crate::generator::Polygon;
 use crate::graph;
-use crate::graph::Walls;
 use crate::player_utils;
+use crate::render_thread::InSight;
 use mockall_double::double;
 use std::collections::HashMap;
 use std::sync::mpsc::Receiver;
@@ -17,14 +17,14 @@ use crate::player_utils::Player;
 
 pub struct ObjectGenerator {
     pub polygon_generator: PolygonGenerator,
-    pub receiver_walls: Receiver<(Walls, usize)>,
+    pub receiver_walls: Receiver<(InSight, usize)>,
     pub render_threads_amount: usize,
 }
 
 #[cfg_attr(test, automock)]
 impl ObjectGenerator {
-    fn receive_and_merge_walls(&self) -> Option<graph::Walls> {
-        let mut walls_map: HashMap<usize, Walls> =
+    fn receive_and_merge_walls(&self) -> Option<InSight> {
+        let mut walls_map: HashMap<usize, InSight> =
             HashMap::with_capacity(self.render_threads_amount);
         for _ in 0..self.render_threads_amount {
             let (walls, index) = self.receiver_walls.recv().unwrap();
@@ -47,6 +47,7 @@ impl ObjectGenerator {
         walls_in_sight: &Vec<graph::Wall>,
         position: &graph::Coordinate,
         angle: &player_utils::Angle,
+        eye_offset: f64,
         polygons: &mut Vec<Polygon>,
     ) -> usize {
         if index < walls_in_sight.len() - 1
@@ -58,19 +59,22 @@ impl ObjectGenerator {
                 walls_in_sight,
                 position,
                 angle,
+                eye_offset,
                 polygons,
             );
-            polygons.push(self.polygon_generator.generate_polygon(
+            polygons.extend(self.polygon_generator.generate_polygon(
                 &walls_in_sight[index],
                 position,
                 angle,
+                eye_offset,
             ));
             return ret_index;
         }
-        polygons.push(self.polygon_generator.generate_polygon(
+        polygons.extend(self.polygon_generator.generate_polygon(
             &walls_in_sight[index],
             position,
             angle,
+            eye_offset,
         ));
         return index;
     }
@@ -80,6 +84,7 @@ impl ObjectGenerator {
         walls_in_sight: graph::Walls,
         position: &graph::Coordinate,
         angle: &player_utils::Angle,
+        eye_offset: f64,
     ) -> Vec<Polygon> {
         let mut polygons: Vec<Polygon> = Vec::new();
         let mut index = 0;
@@ -90,10 +95,11 @@ impl ObjectGenerator {
             if walls_in_sight.0[index].point_distance_end(&position)
                 >= walls_in_sight.0[index + 1].point_distance_start(&position)
             {
-                polygons.push(self.polygon_generator.generate_polygon(
+                polygons.extend(self.polygon_generator.generate_polygon(
                     &walls_in_sight.0[index],
                     &position,
                     &angle,
+                    eye_offset,
                 ));
             } else {
                 index = self.generate_farther_polygons(
@@ -101,29 +107,90 @@ impl ObjectGenerator {
                     &walls_in_sight.0,
                     &position,
                     &angle,
+                    eye_offset,
                     &mut polygons,
                 );
             }
             index += 1;
         }
         if index == walls_in_sight.0.len() - 1 {
-            polygons.push(self.polygon_generator.generate_polygon(
+            polygons.extend(self.polygon_generator.generate_polygon(
                 &walls_in_sight.0[index],
                 &position,
                 &angle,
+                eye_offset,
             ));
         }
         return polygons;
     }
 
+    // Floors are drawn from the lowest and ceilings from the highest, so the
+    // surfaces closer to the eye height cover the farther ones.
+    pub fn generate_surfaces(
+        &self,
+        player: &Arc<RwLock<Player>>,
+        mut surfaces: Vec<graph::Surface>,
+    ) -> Vec<Polygon> {
+        surfaces.sort_by(|first, second| {
+            let order = |surface: &graph::Surface| {
+                if surface.is_floor {
+                    (0, surface.height)
+                } else {
+                    (1, -surface.height)
+                }
+            };
+            let (first, second) = (order(first), order(second));
+            first.0.cmp(&second.0).then(first.1.total_cmp(&second.1))
+        });
+        let player_read = player.read().unwrap();
+        surfaces
+            .iter()
+            .flat_map(|surface| {
+                self.polygon_generator.generate_surface(
+                    surface,
+                    player_read.position(),
+                    player_read.angle(),
+                    player_read.view_eye_offset(),
+                )
+            })
+            .collect()
+    }
+
+    pub fn horizon_offset(&self, player: &Arc<RwLock<Player>>) -> f64 {
+        let player_read = player.read().unwrap();
+        self.polygon_generator
+            .horizon_offset(player_read.angle(), player_read.view_pitch())
+    }
+
     pub fn generate_polygons(&self, player: &Arc<RwLock<Player>>) -> Vec<Polygon> {
-        if let Some(merged_walls) = self.receive_and_merge_walls() {
+        if let Some(merged) = self.receive_and_merge_walls() {
             let player_read = player.read().unwrap();
-            return self.generate_polygons_(
-                merged_walls,
-                player_read.position(),
-                player_read.angle(),
-            );
+            return match merged {
+                // layers are drawn from the highest, so lower walls cover
+                // the taller walls behind them
+                InSight::Walls(layers) => layers
+                    .into_iter()
+                    .flat_map(|walls| {
+                        self.generate_polygons_(
+                            walls,
+                            player_read.position(),
+                            player_read.angle(),
+                            player_read.view_eye_offset(),
+                        )
+                    })
+                    .collect(),
+                InSight::Columns(columns) => columns
+                    .iter()
+                    .map(|column| {
+                        self.polygon_generator.generate_column(
+                            column,
+                            player_read.position(),
+                            player_read.angle(),
+                            player_read.view_eye_offset(),
+                        )
+                    })
+                    .collect(),
+            };
         }
         return vec![];
     }
@@ -135,7 +202,9 @@ mod tests {
     use super::*;
     use crate::generator::MockPolygonGenerator;
     use crate::generator::Polygon;
-    use crate::map_element::{Color, Point};
+    use crate::graph::Coordinate;
+    use crate::graph::Walls;
+    use crate::map_element::Color;
     use crate::player_utils::Angle;
     use crate::player_utils::MockPlayer;
     use crate::player_utils::Radians;
@@ -151,7 +220,7 @@ mod tests {
 
         let mut polygon_generator = MockPolygonGenerator::new();
         let player = Arc::new(RwLock::new(MockPlayer::default()));
-        let (sender_walls, receiver_walls) = mpsc::channel::<(Walls, usize)>();
+        let (sender_walls, receiver_walls) = mpsc::channel::<(InSight, usize)>();
 
         {
             let mut player_write = player.write().unwrap();
@@ -168,46 +237,63 @@ mod tests {
                     end: Radians::new(1.0),
                 })
                 .in_sequence(&mut seq);
+            player_write
+                .expect_view_eye_offset()
+                .times(1)
+                .return_const(0.5)
+                .in_sequence(&mut seq);
         }
 
         let walls_in_sight = graph::Walls(vec![
             graph::Wall {
-                start_point: Point { x: 1, y: 4 },
-                end_point: Point { x: 2, y: 4 },
+                start_point: Coordinate { x: 1.0, y: 4.0 },
+                end_point: Coordinate { x: 2.0, y: 4.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 2, y: 4 },
-                end_point: Point { x: 2, y: 3 },
+                start_point: Coordinate { x: 2.0, y: 4.0 },
+                end_point: Coordinate { x: 2.0, y: 3.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 2, y: 3 },
-                end_point: Point { x: 3, y: 3 },
+                start_point: Coordinate { x: 2.0, y: 3.0 },
+                end_point: Coordinate { x: 3.0, y: 3.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 3, y: 4 },
-                end_point: Point { x: 4, y: 4 },
+                start_point: Coordinate { x: 3.0, y: 4.0 },
+                end_point: Coordinate { x: 4.0, y: 4.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 4, y: 5 },
-                end_point: Point { x: 5, y: 5 },
+                start_point: Coordinate { x: 4.0, y: 5.0 },
+                end_point: Coordinate { x: 5.0, y: 5.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 5, y: 6 },
-                end_point: Point { x: 7, y: 6 },
+                start_point: Coordinate { x: 5.0, y: 6.0 },
+                end_point: Coordinate { x: 7.0, y: 6.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
         ]);
 
         sender_walls
-            .send((Walls(walls_in_sight.0[0..3].to_vec()), 0))
+            .send((
+                InSight::Walls(vec![Walls(walls_in_sight.0[0..3].to_vec())]),
+                0,
+            ))
             .unwrap();
         sender_walls
-            .send((Walls(walls_in_sight.0[3..6].to_vec()), 1))
+            .send((
+                InSight::Walls(vec![Walls(walls_in_sight.0[3..6].to_vec())]),
+                1,
+            ))
             .unwrap();
 
         let expected_generate_polygons = vec![
@@ -253,13 +339,15 @@ mod tests {
                 .withf(
                     move |wall: &graph::Wall,
                           position: &graph::Coordinate,
-                          angle: &player_utils::Angle| {
+                          angle: &player_utils::Angle,
+                          eye_offset: &f64| {
                         *wall == cloned_wall
                             && *position == cloned_position
                             && *angle == cloned_angle
+                            && *eye_offset == 0.5
                     },
                 )
-                .return_const(polygon)
+                .return_const(Some(polygon))
                 .in_sequence(&mut seq);
         }
 
@@ -302,7 +390,7 @@ mod tests {
 
         let mut polygon_generator = MockPolygonGenerator::new();
         let player = Arc::new(RwLock::new(MockPlayer::default()));
-        let (sender_walls, receiver_walls) = mpsc::channel::<(Walls, usize)>();
+        let (sender_walls, receiver_walls) = mpsc::channel::<(InSight, usize)>();
         lazy_static! {
             static ref angle: Angle = Angle {
                 start: Radians::new(0.0),
@@ -323,20 +411,32 @@ mod tests {
                 .times(1)
                 .return_const(angle.clone())
                 .in_sequence(&mut seq);
+            player_write
+                .expect_view_eye_offset()
+                .times(1)
+                .return_const(0.0)
+                .in_sequence(&mut seq);
         }
 
         sender_walls
-            .send((Walls(vec![walls_in_sight.0[0].clone()]), 0))
+            .send((
+                InSight::Walls(vec![Walls(vec![walls_in_sight.0[0].clone()])]),
+                0,
+            ))
             .unwrap();
         sender_walls
             .send((
-                Walls(walls_in_sight.0[1..walls_in_sight.0.len() - 1].to_vec()),
+                InSight::Walls(vec![Walls(
+                    walls_in_sight.0[1..walls_in_sight.0.len() - 1].to_vec(),
+                )]),
                 1,
             ))
             .unwrap();
         sender_walls
             .send((
-                Walls(vec![walls_in_sight.0[walls_in_sight.0.len() - 1].clone()]),
+                InSight::Walls(vec![Walls(vec![walls_in_sight.0
+                    [walls_in_sight.0.len() - 1]
+                    .clone()])]),
                 2,
             ))
             .unwrap();
@@ -354,11 +454,12 @@ mod tests {
                 .withf(
                     move |wall_: &graph::Wall,
                           position_: &graph::Coordinate,
-                          angle_: &player_utils::Angle| {
+                          angle_: &player_utils::Angle,
+                          _: &f64| {
                         *wall_ == wall && *position_ == *position && *angle_ == *angle
                     },
                 )
-                .return_const(polygon)
+                .return_const(Some(polygon))
                 .in_sequence(&mut seq);
         }
 
@@ -377,105 +478,123 @@ mod tests {
     fn generate_polygons_merge_walls() {
         let walls_in_sight_1 = graph::Walls(vec![
             graph::Wall {
-                start_point: Point { x: 2, y: 3 },
-                end_point: Point { x: 4, y: 3 },
+                start_point: Coordinate { x: 2.0, y: 3.0 },
+                end_point: Coordinate { x: 4.0, y: 3.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 3, y: 3 },
-                end_point: Point { x: 5, y: 3 },
+                start_point: Coordinate { x: 3.0, y: 3.0 },
+                end_point: Coordinate { x: 5.0, y: 3.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 5, y: 3 },
-                end_point: Point { x: 5, y: 5 },
+                start_point: Coordinate { x: 5.0, y: 3.0 },
+                end_point: Coordinate { x: 5.0, y: 5.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 5, y: 4 },
-                end_point: Point { x: 5, y: 6 },
+                start_point: Coordinate { x: 5.0, y: 4.0 },
+                end_point: Coordinate { x: 5.0, y: 6.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
         ]);
         let merged_walls_1 = graph::Walls(vec![
             graph::Wall {
-                start_point: Point { x: 2, y: 3 },
-                end_point: Point { x: 5, y: 3 },
+                start_point: Coordinate { x: 2.0, y: 3.0 },
+                end_point: Coordinate { x: 5.0, y: 3.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 5, y: 3 },
-                end_point: Point { x: 5, y: 6 },
+                start_point: Coordinate { x: 5.0, y: 3.0 },
+                end_point: Coordinate { x: 5.0, y: 6.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
         ]);
 
         let walls_in_sight_2 = graph::Walls(vec![
             graph::Wall {
-                start_point: Point { x: 5, y: 6 },
-                end_point: Point { x: 5, y: 4 },
+                start_point: Coordinate { x: 5.0, y: 6.0 },
+                end_point: Coordinate { x: 5.0, y: 4.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 5, y: 5 },
-                end_point: Point { x: 5, y: 3 },
+                start_point: Coordinate { x: 5.0, y: 5.0 },
+                end_point: Coordinate { x: 5.0, y: 3.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 5, y: 3 },
-                end_point: Point { x: 3, y: 3 },
+                start_point: Coordinate { x: 5.0, y: 3.0 },
+                end_point: Coordinate { x: 3.0, y: 3.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 3, y: 3 },
-                end_point: Point { x: 2, y: 3 },
+                start_point: Coordinate { x: 3.0, y: 3.0 },
+                end_point: Coordinate { x: 2.0, y: 3.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
         ]);
         let merged_walls_2 = graph::Walls(vec![
             graph::Wall {
-                start_point: Point { x: 5, y: 6 },
-                end_point: Point { x: 5, y: 3 },
+                start_point: Coordinate { x: 5.0, y: 6.0 },
+                end_point: Coordinate { x: 5.0, y: 3.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 5, y: 3 },
-                end_point: Point { x: 2, y: 3 },
+                start_point: Coordinate { x: 5.0, y: 3.0 },
+                end_point: Coordinate { x: 2.0, y: 3.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
         ]);
         let walls_in_sight_3 = graph::Walls(vec![
             graph::Wall {
-                start_point: Point { x: 5, y: 6 },
-                end_point: Point { x: 5, y: 5 },
+                start_point: Coordinate { x: 5.0, y: 6.0 },
+                end_point: Coordinate { x: 5.0, y: 5.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 5, y: 5 },
-                end_point: Point { x: 5, y: 4 },
+                start_point: Coordinate { x: 5.0, y: 5.0 },
+                end_point: Coordinate { x: 5.0, y: 4.0 },
                 primary_object_color: Color::Green,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 5, y: 4 },
-                end_point: Point { x: 5, y: 3 },
+                start_point: Coordinate { x: 5.0, y: 4.0 },
+                end_point: Coordinate { x: 5.0, y: 3.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
         ]);
         let merged_walls_3 = graph::Walls(vec![
             graph::Wall {
-                start_point: Point { x: 5, y: 6 },
-                end_point: Point { x: 5, y: 5 },
+                start_point: Coordinate { x: 5.0, y: 6.0 },
+                end_point: Coordinate { x: 5.0, y: 5.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 5, y: 5 },
-                end_point: Point { x: 5, y: 4 },
+                start_point: Coordinate { x: 5.0, y: 5.0 },
+                end_point: Coordinate { x: 5.0, y: 4.0 },
                 primary_object_color: Color::Green,
+                height: Default::default(),
             },
             graph::Wall {
-                start_point: Point { x: 5, y: 4 },
-                end_point: Point { x: 5, y: 3 },
+                start_point: Coordinate { x: 5.0, y: 4.0 },
+                end_point: Coordinate { x: 5.0, y: 3.0 },
                 primary_object_color: Color::Red,
+                height: Default::default(),
             },
         ]);
 
@@ -506,11 +625,20 @@ mod tests {
                 .times(1)
                 .return_const(Default::default())
                 .in_sequence(&mut seq);
+            player_write
+                .expect_view_eye_offset()
+                .times(1)
+                .return_const(0.0)
+                .in_sequence(&mut seq);
         }
 
-        let (sender_walls, receiver_walls) = mpsc::channel::<(Walls, usize)>();
-        sender_walls.send((Walls(vec![]), 0)).unwrap();
-        sender_walls.send((Walls(vec![]), 1)).unwrap();
+        let (sender_walls, receiver_walls) = mpsc::channel::<(InSight, usize)>();
+        sender_walls
+            .send((InSight::Walls(vec![Walls(vec![])]), 0))
+            .unwrap();
+        sender_walls
+            .send((InSight::Walls(vec![Walls(vec![])]), 1))
+            .unwrap();
 
         let object_generator = ObjectGenerator {
             polygon_generator,
@@ -530,7 +658,7 @@ mod tests {
         let polygon_generator = MockPolygonGenerator::new();
         let player = Arc::new(RwLock::new(MockPlayer::default()));
 
-        let (_, receiver_walls) = mpsc::channel::<(Walls, usize)>();
+        let (_, receiver_walls) = mpsc::channel::<(InSight, usize)>();
 
         let object_generator = ObjectGenerator {
             polygon_generator,
@@ -542,4 +670,129 @@ mod tests {
             Vec::<Polygon>::new()
         );
     }
+
+    #[test]
+    fn generate_polygons_columns() {
+        let render_threads_amount = 2;
+
+        let mut polygon_generator = MockPolygonGenerator::new();
+        let player = Arc::new(RwLock::new(MockPlayer::default()));
+        {
+            let mut player_write = player.write().unwrap();
+            player_write
+                .expect_position()
+                .return_const(Coordinate { x: 2.0, y: 1.0 });
+            player_write.expect_angle().return_const(Angle {
+                start: Radians::new(0.0),
+                end: Radians::new(1.0),
+            });
+            player_write.expect_view_eye_offset().return_const(0.0);
+        }
+
+        let column = |index| graph::Column {
+            index,
+            hit_point: Coordinate { x: 5.0, y: 1.0 },
+            color: Color::Red,
+            height: Default::default(),
+        };
+        let polygon = |index: usize| Polygon {
+            area: [[index as f64, 0.0]; 4],
+            color: Color::Red,
+        };
+
+        let (sender_walls, receiver_walls) = mpsc::channel::<(InSight, usize)>();
+        sender_walls
+            .send((InSight::Columns(vec![column(1), column(3)]), 1))
+            .unwrap();
+        sender_walls
+            .send((InSight::Columns(vec![column(0)]), 0))
+            .unwrap();
+
+        polygon_generator
+            .expect_generate_column()
+            .times(3)
+            .returning(move |column, _, _, _| polygon(column.index));
+
+        let object_generator = ObjectGenerator {
+            polygon_generator,
+            receiver_walls,
+            render_threads_amount,
+        };
+        assert_eq!(
+            object_generator.generate_polygons(&player),
+            vec![polygon(0), polygon(1), polygon(3)]
+        );
+    }
+
+    #[test]
+    fn generate_surfaces() {
+        let mut seq = Sequence::new();
+        let mut polygon_generator = MockPolygonGenerator::new();
+        let player = Arc::new(RwLock::new(MockPlayer::default()));
+        {
+            let mut player_write = player.write().unwrap();
+            player_write
+                .expect_position()
+                .return_const(Coordinate { x: 2.0, y: 1.0 });
+            player_write.expect_angle().return_const(Angle::default());
+            player_write.expect_view_eye_offset().return_const(0.0);
+        }
+        let surface = |height: f64, is_floor: bool| graph::Surface {
+            corners: vec![],
+            height,
+            color: Color::Red,
+            is_floor,
+        };
+        // floors from the lowest, then ceilings from the highest
+        for (height, is_floor) in [(-1.0, true), (1.0, true), (4.0, false), (3.0, false)] {
+            polygon_generator
+                .expect_generate_surface()
+                .times(1)
+                .withf(move |surface, _, _, _| {
+                    surface.height == height && surface.is_floor == is_floor
+                })
+                .in_sequence(&mut seq)
+                .return_const(vec![]);
+        }
+
+        let (_, receiver_walls) = mpsc::channel::<(InSight, usize)>();
+        let object_generator = ObjectGenerator {
+            polygon_generator,
+            receiver_walls,
+            render_threads_amount: 1,
+        };
+        object_generator.generate_surfaces(
+            &player,
+            vec![
+                surface(3.0, false),
+                surface(1.0, true),
+                surface(4.0, false),
+                surface(-1.0, true),
+            ],
+        );
+    }
+
+    #[test]
+    fn horizon_offset() {
+        let mut polygon_generator = MockPolygonGenerator::new();
+        let player = Arc::new(RwLock::new(MockPlayer::default()));
+        {
+            let mut player_write = player.write().unwrap();
+            player_write.expect_angle().return_const(Angle::default());
+            player_write.expect_view_pitch().times(1).return_const(0.3);
+        }
+        polygon_generator
+            .expect_horizon_offset()
+            .times(1)
+            .withf(|_, pitch| *pitch == 0.3)
+            .return_const(12.0);
+
+        let (_, receiver_walls) = mpsc::channel::<(InSight, usize)>();
+        let object_generator = ObjectGenerator {
+            polygon_generator,
+            receiver_walls,
+            render_threads_amount: 1,
+        };
+        assert_eq!(object_generator.horizon_offset(&player), 12.0);
+    }
 }
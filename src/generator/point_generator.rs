@@ -1,149 +1,210 @@
-use crate::graph::{Coordinate, LinearGraph};
-use crate::map_element::Point;
+use crate::graph::{Coordinate, LinearGraph, Wall, WallHeight};
 use crate::player_utils;
 use piston::window::Size;
 
 #[cfg(test)]
 use mockall::{automock, predicate::*};
 
+// walls closer than this to the player are clipped
+const NEAR_PLANE_DISTANCE: f64 = 0.01;
+
 pub struct PointGenerator {
     resolution: Size,
-    vertical_tangens: f64,
-    half_wall_height: f64,
+    // tangens of the half of the vertical angle divided by the horizontal one
+    aspect_ratio: f64,
+    wall_height: f64,
 }
 
+// Projects the map coordinates on the screen plane placed at distance 1 in
+// front of the player.
 #[cfg_attr(test, automock)]
 impl PointGenerator {
+    // Vertical angle is given for the horizontal angle value, it changes
+    // together with the horizontal angle of the player.
     pub fn new(
         resolution: Size,
         vertical_angle_value: player_utils::Radians,
+        horizontal_angle_value: player_utils::Radians,
         wall_height: f64,
     ) -> PointGenerator {
         PointGenerator {
             resolution,
-            vertical_tangens: LinearGraph::from_radians(vertical_angle_value / 2.0).tangens,
-            half_wall_height: wall_height / 2.0,
+            aspect_ratio: (vertical_angle_value / 2.0).tan() / (horizontal_angle_value / 2.0).tan(),
+            wall_height,
         }
     }
 
+    // Returns the position of the point on the screen, points beside the field
+    // of view are placed outside of the [0, width] range.
     pub fn point_width(
         &self,
         angle: &player_utils::Angle,
         start_position: &Coordinate,
-        end_position: &Point,
+        end_position: &Coordinate,
     ) -> f64 {
-        let point_radians = start_position.into_radians_point(end_position);
-        if angle.is_inside(point_radians) {
-            return self.point_width_inside_field_of_view(angle, &point_radians);
-        }
-        return self.point_width_outside_field_of_view(angle, &point_radians);
+        let half_width = Self::half_width(angle);
+        let (_, plane_x) = Self::project(angle, start_position, end_position);
+        return (plane_x + half_width) / (half_width * 2.0) * self.resolution.width as f64;
     }
 
-    fn compute_graphs(
+    // Clips the wall to the part which lies in front of the near plane and
+    // between the edges of the field of view. Returns None if nothing is left.
+    pub fn clip_wall(
+        &self,
         angle: &player_utils::Angle,
-        point_radians: &player_utils::Radians,
-    ) -> (LinearGraph, LinearGraph, f64, Coordinate) {
-        let direction = LinearGraph::from_radians(angle.get_direction() - angle.start);
-
-        let perpendicular_direction = LinearGraph::from_radians(
-            angle.get_direction() + player_utils::Radians::new(std::f64::consts::PI / 2.0)
-                - angle.start,
-        );
-
-        let cross_point_middle_x = (1.0 / (direction.tangens.powi(2) + 1.0)).sqrt();
-        let cross_point_middle_y = direction.tangens * cross_point_middle_x;
-
-        let perpendicular_direction_b =
-            cross_point_middle_y - perpendicular_direction.tangens * cross_point_middle_x;
-
-        let graph_point_radians = LinearGraph::from_radians(point_radians - angle.start);
-        return (
-            graph_point_radians,
-            perpendicular_direction,
-            perpendicular_direction_b,
-            Coordinate {
-                x: cross_point_middle_x,
-                y: cross_point_middle_y,
-            },
-        );
+        position: &Coordinate,
+        wall: &Wall,
+    ) -> Option<(Coordinate, Coordinate)> {
+        let half_width = Self::half_width(angle);
+        let (start_forward, start_side) = Self::view_coordinate(angle, position, &wall.start_point);
+        let (end_forward, end_side) = Self::view_coordinate(angle, position, &wall.end_point);
+        // every plane is described by the value which is not negative in front of it
+        let planes = [
+            (
+                start_forward - NEAR_PLANE_DISTANCE,
+                end_forward - NEAR_PLANE_DISTANCE,
+            ),
+            (
+                start_forward * half_width + start_side,
+                end_forward * half_width + end_side,
+            ),
+            (
+                start_forward * half_width - start_side,
+                end_forward * half_width - end_side,
+            ),
+        ];
+        let mut start_part = 0.0_f64;
+        let mut end_part = 1.0_f64;
+        for (start_value, end_value) in planes.iter() {
+            if *start_value < 0.0 && *end_value < 0.0 {
+                return None;
+            }
+            if *start_value < 0.0 {
+                start_part = start_part.max(start_value / (start_value - end_value));
+            } else if *end_value < 0.0 {
+                end_part = end_part.min(start_value / (start_value - end_value));
+            }
+        }
+        if start_part > end_part {
+            return None;
+        }
+        let point_at = |part: f64| {
+            if part == 0.0 {
+                wall.start_point.clone()
+            } else if part == 1.0 {
+                wall.end_point.clone()
+            } else {
+                Coordinate {
+                    x: wall.start_point.x + (wall.end_point.x - wall.start_point.x) * part,
+                    y: wall.start_point.y + (wall.end_point.y - wall.start_point.y) * part,
+                }
+            }
+        };
+        Some((point_at(start_part), point_at(end_part)))
     }
 
-    fn compute_distances_for_width(
+    // Clips the polygon to the part which lies in front of the near plane and
+    // between the edges of the field of view, the polygon has to be convex.
+    pub fn clip_surface(
+        &self,
         angle: &player_utils::Angle,
-        point_radians: &player_utils::Radians,
-    ) -> (f64, f64) {
-        let (
-            graph_point_radians,
-            perpendicular_direction,
-            perpendicular_direction_b,
-            cross_point_middle,
-        ) = Self::compute_graphs(angle, point_radians);
-
-        let cross_point_y_0 = Coordinate {
-            x: -perpendicular_direction_b / perpendicular_direction.tangens,
-            y: 0.0,
+        position: &Coordinate,
+        corners: &[Coordinate],
+    ) -> Vec<Coordinate> {
+        let half_width = Self::half_width(angle);
+        // plane values are not negative in front of the planes, as in clip_wall
+        let plane_values = |corner: &Coordinate| {
+            let (forward, side) = Self::view_coordinate(angle, position, corner);
+            [
+                forward - NEAR_PLANE_DISTANCE,
+                forward * half_width + side,
+                forward * half_width - side,
+            ]
         };
+        let mut polygon = corners.to_vec();
+        for plane in 0..3 {
+            let mut clipped = vec![];
+            for (index, corner) in polygon.iter().enumerate() {
+                let next_corner = &polygon[(index + 1) % polygon.len()];
+                let value = plane_values(corner)[plane];
+                let next_value = plane_values(next_corner)[plane];
+                if value >= 0.0 {
+                    clipped.push(corner.clone());
+                }
+                if (value >= 0.0) != (next_value >= 0.0) {
+                    let part = value / (value - next_value);
+                    clipped.push(Coordinate {
+                        x: corner.x + (next_corner.x - corner.x) * part,
+                        y: corner.y + (next_corner.y - corner.y) * part,
+                    });
+                }
+            }
+            polygon = clipped;
+        }
+        polygon
+    }
 
-        let whole_distance = cross_point_y_0.distance(&cross_point_middle) * 2.0;
-
-        let cross_point_x = -perpendicular_direction_b
-            / (perpendicular_direction.tangens - graph_point_radians.tangens);
-        let cross_point_y = cross_point_x * graph_point_radians.tangens;
-
-        let short_distance = cross_point_y_0.distance(&Coordinate {
-            x: cross_point_x,
-            y: cross_point_y,
-        });
-        return (short_distance, whole_distance);
+    fn half_width(angle: &player_utils::Angle) -> f64 {
+        (angle.get_direction() - angle.start).tan()
     }
 
-    fn point_width_inside_field_of_view(
-        &self,
+    // Returns the distance of the point along the view direction and the
+    // distance to the side (positive towards the end of the angle).
+    fn view_coordinate(
         angle: &player_utils::Angle,
-        point_radians: &player_utils::Radians,
-    ) -> f64 {
-        let (short_distance, whole_distance) =
-            Self::compute_distances_for_width(angle, point_radians);
-
-        return short_distance / whole_distance * self.resolution.width as f64;
+        start_position: &Coordinate,
+        end_position: &Coordinate,
+    ) -> (f64, f64) {
+        let direction = LinearGraph::from_radians(angle.get_direction()).direction;
+        let point = Coordinate {
+            x: end_position.x - start_position.x,
+            y: end_position.y - start_position.y,
+        };
+        (
+            point.x * direction.x + point.y * direction.y,
+            point.y * direction.x - point.x * direction.y,
+        )
     }
 
-    fn point_width_outside_field_of_view(
-        &self,
+    // Returns the distance of the point along the view direction and the
+    // coordinate of the point projected on the screen plane, where the plane
+    // center is 0 and the left edge of the field of view is -half_width.
+    fn project(
         angle: &player_utils::Angle,
-        point_radians: &player_utils::Radians,
-    ) -> f64 {
-        let (short_distance, whole_distance) =
-            Self::compute_distances_for_width(angle, point_radians);
-
-        if short_distance < whole_distance {
-            return -short_distance / whole_distance * self.resolution.width as f64;
-        }
-        return short_distance / whole_distance * self.resolution.width as f64;
+        start_position: &Coordinate,
+        end_position: &Coordinate,
+    ) -> (f64, f64) {
+        let (forward, side) = Self::view_coordinate(angle, start_position, end_position);
+        (forward, side / forward)
     }
 
-    // returns 1/2 of point height
+    // Returns the height of the wall top above the horizon and of the wall
+    // bottom below it. Eye is in the middle of the engine wall height, eye
+    // offset moves it up.
     pub fn point_height(
         &self,
         angle: &player_utils::Angle,
         start_position: &Coordinate,
-        end_position: &Point,
-    ) -> f64 {
-        let point_radians = start_position.into_radians_point(end_position);
-        let (graph_point_radians, perpendicular_direction, perpendicular_direction_b, _) =
-            Self::compute_graphs(angle, &point_radians);
-
-        let cross_point_x = -perpendicular_direction_b
-            / (perpendicular_direction.tangens - graph_point_radians.tangens);
-        let cross_point_y = cross_point_x * graph_point_radians.tangens;
-
-        let short_distance = Coordinate::ZERO.distance(&Coordinate {
-            x: cross_point_x,
-            y: cross_point_y,
-        });
-        let whole_distance = end_position.distance_coor(&start_position);
-        return (short_distance / whole_distance * self.half_wall_height) / self.vertical_tangens
-            * self.resolution.height;
+        end_position: &Coordinate,
+        height: &WallHeight,
+        eye_offset: f64,
+    ) -> (f64, f64) {
+        let (forward, _) = Self::project(angle, start_position, end_position);
+        let scale = self.resolution.height / forward.abs() / self.vertical_tangens(angle);
+        let eye_height = self.wall_height / 2.0 + eye_offset;
+        return (
+            (height.top(self.wall_height) - eye_height) * scale,
+            (eye_height - height.elevation) * scale,
+        );
+    }
+
+    // returns how much the horizon is moved down when the player looks up
+    pub fn horizon_offset(&self, angle: &player_utils::Angle, pitch: f64) -> f64 {
+        pitch.tan() / self.vertical_tangens(angle) * self.resolution.height
+    }
+
+    fn vertical_tangens(&self, angle: &player_utils::Angle) -> f64 {
+        Self::half_width(angle) * self.aspect_ratio
     }
 }
 
@@ -162,6 +223,7 @@ mod tests {
             },
             Default::default(),
             Default::default(),
+            Default::default(),
         );
 
         let angle = player_utils::Angle {
@@ -169,7 +231,7 @@ mod tests {
             end: player_utils::Radians::new(std::f64::consts::PI * 2.0 / 3.0),
         };
         let start_position = Coordinate { x: 0.0, y: 0.0 };
-        let end_position = Point { x: 0, y: 10 };
+        let end_position = Coordinate { x: 0.0, y: 10.0 };
 
         let short_distance = 3.0_f64.sqrt() * 4.0 / 3.0;
         let whole_distance = 3.0_f64.sqrt() * 2.0;
@@ -192,6 +254,7 @@ mod tests {
             },
             Default::default(),
             Default::default(),
+            Default::default(),
         );
 
         let angle = player_utils::Angle {
@@ -199,8 +262,8 @@ mod tests {
             end: player_utils::Radians::new(std::f64::consts::PI * 3.0 / 4.0),
         };
         let start_position = Coordinate { x: 8.0, y: 1.0 };
-        let end_position_1 = Point { x: 14, y: 4 };
-        let end_position_2 = Point { x: 2, y: 4 };
+        let end_position_1 = Coordinate { x: 14.0, y: 4.0 };
+        let end_position_2 = Coordinate { x: 2.0, y: 4.0 };
 
         let short_distance_1 = -1.0;
         let short_distance_2 = 3.0;
@@ -231,6 +294,7 @@ mod tests {
                 height: resolution_height,
             },
             player_utils::Radians::new(std::f64::consts::PI / 2.0),
+            player_utils::Radians::new(std::f64::consts::PI / 2.0),
             wall_height,
         );
         let half_wall_height = wall_height / 2.0;
@@ -241,21 +305,231 @@ mod tests {
         };
 
         let start_position = Coordinate { x: 8.0, y: 1.0 };
-        let end_position_1 = Point { x: 8, y: 4 };
-        let end_position_2 = Point { x: 4, y: 5 };
+        let end_position_1 = Coordinate { x: 8.0, y: 4.0 };
+        let end_position_2 = Coordinate { x: 4.0, y: 5.0 };
 
-        assert!(approx_eq!(
-            f64,
-            point_generator.point_height(&angle, &start_position, &end_position_1),
+        let assert_height = |height: (f64, f64), top: f64, bottom: f64| {
+            assert!(approx_eq!(f64, height.0, top, epsilon = 1e-9));
+            assert!(approx_eq!(f64, height.1, bottom, epsilon = 1e-9));
+        };
+
+        assert_height(
+            point_generator.point_height(
+                &angle,
+                &start_position,
+                &end_position_1,
+                &WallHeight::default(),
+                0.0,
+            ),
             half_wall_height / 3.0 * resolution_height,
-            ulps = 3
-        ));
+            half_wall_height / 3.0 * resolution_height,
+        );
+        assert_height(
+            point_generator.point_height(
+                &angle,
+                &start_position,
+                &end_position_2,
+                &WallHeight::default(),
+                0.0,
+            ),
+            half_wall_height / 4.0 * resolution_height,
+            half_wall_height / 4.0 * resolution_height,
+        );
+
+        // raised eye sees less of the wall above the horizon
+        assert_height(
+            point_generator.point_height(
+                &angle,
+                &start_position,
+                &end_position_1,
+                &WallHeight::default(),
+                1.5,
+            ),
+            (half_wall_height - 1.5) / 3.0 * resolution_height,
+            (half_wall_height + 1.5) / 3.0 * resolution_height,
+        );
+
+        // zoomed angle keeps the aspect ratio, point is twice as high
+        let half_value = 0.5_f64.atan();
+        let zoomed_angle = player_utils::Angle {
+            start: player_utils::Radians::new(std::f64::consts::PI / 2.0 - half_value),
+            end: player_utils::Radians::new(std::f64::consts::PI / 2.0 + half_value),
+        };
+        assert_height(
+            point_generator.point_height(
+                &zoomed_angle,
+                &start_position,
+                &end_position_1,
+                &WallHeight::default(),
+                0.0,
+            ),
+            half_wall_height / 3.0 * resolution_height * 2.0,
+            half_wall_height / 3.0 * resolution_height * 2.0,
+        );
+
+        // low raised wall is below the eye
+        assert_height(
+            point_generator.point_height(
+                &angle,
+                &start_position,
+                &end_position_1,
+                &WallHeight::new(1.0, 0.5),
+                0.0,
+            ),
+            -0.5 / 3.0 * resolution_height,
+            1.5 / 3.0 * resolution_height,
+        );
+    }
 
+    #[test]
+    fn clip_wall() {
+        let point_generator = PointGenerator::new(
+            Size {
+                width: Default::default(),
+                height: Default::default(),
+            },
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        let angle = player_utils::Angle {
+            start: player_utils::Radians::new(std::f64::consts::PI / 4.0),
+            end: player_utils::Radians::new(std::f64::consts::PI * 3.0 / 4.0),
+        };
+        let position = Coordinate { x: 8.0, y: 1.0 };
+        let wall = |start: (f64, f64), end: (f64, f64)| Wall {
+            start_point: Coordinate {
+                x: start.0,
+                y: start.1,
+            },
+            end_point: Coordinate { x: end.0, y: end.1 },
+            primary_object_color: Default::default(),
+            height: Default::default(),
+        };
+        let assert_clipped =
+            |clipped: Option<(Coordinate, Coordinate)>, start: (f64, f64), end: (f64, f64)| {
+                let (clipped_start, clipped_end) = clipped.unwrap();
+                for (coordinate, expected) in [(clipped_start, start), (clipped_end, end)].iter() {
+                    assert!(
+                        approx_eq!(f64, coordinate.x, expected.0, epsilon = 1e-9)
+                            && approx_eq!(f64, coordinate.y, expected.1, epsilon = 1e-9),
+                        "{:?} != {:?}",
+                        coordinate,
+                        expected
+                    );
+                }
+            };
+
+        // wall inside of the field of view is not changed
+        assert_eq!(
+            point_generator.clip_wall(&angle, &position, &wall((6.0, 5.0), (10.0, 5.0))),
+            Some((
+                Coordinate { x: 6.0, y: 5.0 },
+                Coordinate { x: 10.0, y: 5.0 }
+            ))
+        );
+        // wall which goes behind the player is clipped by the near plane
+        assert_clipped(
+            point_generator.clip_wall(&angle, &position, &wall((8.0, 5.0), (8.0, -3.0))),
+            (8.0, 5.0),
+            (8.0, 1.0 + NEAR_PLANE_DISTANCE),
+        );
+        // wall wider than the field of view is clipped by both edges
+        assert_clipped(
+            point_generator.clip_wall(&angle, &position, &wall((2.0, 5.0), (14.0, 5.0))),
+            (4.0, 5.0),
+            (12.0, 5.0),
+        );
+        // wall behind the player
+        assert_eq!(
+            point_generator.clip_wall(&angle, &position, &wall((6.0, 0.0), (10.0, 0.0))),
+            None
+        );
+        // wall beside the field of view
+        assert_eq!(
+            point_generator.clip_wall(&angle, &position, &wall((14.0, 3.0), (20.0, 3.0))),
+            None
+        );
+    }
+
+    #[test]
+    fn clip_surface() {
+        let point_generator = PointGenerator::new(
+            Size {
+                width: Default::default(),
+                height: Default::default(),
+            },
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        let angle = player_utils::Angle {
+            start: player_utils::Radians::new(std::f64::consts::PI / 4.0),
+            end: player_utils::Radians::new(std::f64::consts::PI * 3.0 / 4.0),
+        };
+        let position = Coordinate { x: 8.0, y: 1.0 };
+        let square = |x: f64, y: f64, size: f64| {
+            vec![
+                Coordinate { x, y },
+                Coordinate { x: x + size, y },
+                Coordinate {
+                    x: x + size,
+                    y: y + size,
+                },
+                Coordinate { x, y: y + size },
+            ]
+        };
+
+        // surface inside of the field of view is not changed
+        assert_eq!(
+            point_generator.clip_surface(&angle, &position, &square(7.0, 3.0, 1.0)),
+            square(7.0, 3.0, 1.0)
+        );
+        // surface behind the player
+        assert_eq!(
+            point_generator.clip_surface(&angle, &position, &square(7.0, -3.0, 2.0)),
+            vec![]
+        );
+        // surface under the player is clipped by the near plane and both edges
+        let clipped = point_generator.clip_surface(&angle, &position, &square(6.0, 0.0, 4.0));
+        assert_eq!(clipped.len(), 6);
+        for corner in clipped.iter() {
+            let forward = corner.y - position.y;
+            let side = corner.x - position.x;
+            assert!(
+                forward >= NEAR_PLANE_DISTANCE - 1e-9 && side.abs() <= forward + 1e-9,
+                "{:?}",
+                corner
+            );
+        }
+        assert!(clipped.contains(&Coordinate { x: 6.0, y: 4.0 }));
+        assert!(clipped.contains(&Coordinate { x: 10.0, y: 4.0 }));
+    }
+
+    #[test]
+    fn horizon_offset() {
+        let resolution_height = 600.0;
+        let point_generator = PointGenerator::new(
+            Size {
+                width: Default::default(),
+                height: resolution_height,
+            },
+            player_utils::Radians::new(std::f64::consts::PI / 2.0),
+            player_utils::Radians::new(std::f64::consts::PI / 2.0),
+            Default::default(),
+        );
+        let angle = player_utils::Angle {
+            start: player_utils::Radians::new(std::f64::consts::PI / 4.0),
+            end: player_utils::Radians::new(std::f64::consts::PI * 3.0 / 4.0),
+        };
+
+        assert_eq!(point_generator.horizon_offset(&angle, 0.0), 0.0);
         assert!(approx_eq!(
             f64,
-            point_generator.point_height(&angle, &start_position, &end_position_2),
-            half_wall_height / 4.0 * resolution_height,
-            ulps = 3
+            point_generator.horizon_offset(&angle, std::f64::consts::PI / 4.0),
+            resolution_height,
+            epsilon = 1e-9
         ));
+        assert!(point_generator.horizon_offset(&angle, -0.1) < 0.0);
     }
 }
@@ -15,33 +15,113 @@ pub struct PolygonGenerator {
 
 #[cfg_attr(test, automock)]
 impl PolygonGenerator {
+    // returns None if the wall is not visible
     pub fn generate_polygon(
         &self,
         wall: &graph::Wall,
         position: &graph::Coordinate,
         angle: &player_utils::Angle,
-    ) -> Polygon {
-        let start_point_width =
-            self.point_generator
-                .point_width(angle, position, &wall.start_point);
-        let start_point_height =
-            self.point_generator
-                .point_height(angle, position, &wall.start_point);
-        let end_point_width = self
+        eye_offset: f64,
+    ) -> Option<Polygon> {
+        let (start_point, end_point) = self.point_generator.clip_wall(angle, position, wall)?;
+        let start_point_width = self
             .point_generator
-            .point_width(angle, position, &wall.end_point);
-        let end_point_height = self
+            .point_width(angle, position, &start_point);
+        let (start_point_top, start_point_bottom) = self.point_generator.point_height(
+            angle,
+            position,
+            &start_point,
+            &wall.height,
+            eye_offset,
+        );
+        let end_point_width = self
             .point_generator
-            .point_height(angle, position, &wall.end_point);
-        return Polygon {
+            .point_width(angle, position, &end_point);
+        let (end_point_top, end_point_bottom) = self.point_generator.point_height(
+            angle,
+            position,
+            &end_point,
+            &wall.height,
+            eye_offset,
+        );
+        Some(Polygon {
             area: [
-                [start_point_width, start_point_height],
-                [end_point_width, end_point_height],
-                [end_point_width, -end_point_height],
-                [start_point_width, -start_point_height],
+                [start_point_width, start_point_top],
+                [end_point_width, end_point_top],
+                [end_point_width, -end_point_bottom],
+                [start_point_width, -start_point_bottom],
             ],
             color: wall.primary_object_color.clone(),
-        };
+        })
+    }
+
+    // Surface is split into quads, the last one repeats the vertex when the
+    // clipped surface has an odd amount of vertices.
+    pub fn generate_surface(
+        &self,
+        surface: &graph::Surface,
+        position: &graph::Coordinate,
+        angle: &player_utils::Angle,
+        eye_offset: f64,
+    ) -> Vec<Polygon> {
+        let height = graph::WallHeight::new(0.0, surface.height);
+        let points: Vec<[f64; 2]> = self
+            .point_generator
+            .clip_surface(angle, position, &surface.corners)
+            .iter()
+            .map(|corner| {
+                [
+                    self.point_generator.point_width(angle, position, corner),
+                    self.point_generator
+                        .point_height(angle, position, corner, &height, eye_offset)
+                        .0,
+                ]
+            })
+            .collect();
+        // floor below the eye is drawn under the horizon, ceiling above it
+        if points.len() < 3 || (points[0][1] < 0.0) != surface.is_floor {
+            return vec![];
+        }
+        (1..points.len() - 1)
+            .step_by(2)
+            .map(|index| Polygon {
+                area: [
+                    points[0],
+                    points[index],
+                    points[index + 1],
+                    points[(index + 2).min(points.len() - 1)],
+                ],
+                color: surface.color.clone(),
+            })
+            .collect()
+    }
+
+    pub fn horizon_offset(&self, angle: &player_utils::Angle, pitch: f64) -> f64 {
+        self.point_generator.horizon_offset(angle, pitch)
+    }
+
+    // column is one pixel wide, its height is computed from the distance to
+    // the screen plane, so walls are not distorted by the fisheye effect
+    pub fn generate_column(
+        &self,
+        column: &graph::Column,
+        position: &graph::Coordinate,
+        angle: &player_utils::Angle,
+        eye_offset: f64,
+    ) -> Polygon {
+        let left = column.index as f64;
+        let right = left + 1.0;
+        let (top, bottom) = self.point_generator.point_height(
+            angle,
+            position,
+            &column.hit_point,
+            &column.height,
+            eye_offset,
+        );
+        Polygon {
+            area: [[left, top], [right, top], [right, -bottom], [left, -bottom]],
+            color: column.color.clone(),
+        }
     }
 }
 
@@ -51,7 +131,6 @@ mod tests {
     use super::*;
     use crate::generator::MockPointGenerator;
     use crate::map_element::Color;
-    use crate::map_element::Point;
     use mockall::*;
 
     #[test]
@@ -63,29 +142,49 @@ mod tests {
         static position: graph::Coordinate = graph::Coordinate { x: 11.0, y: 13.0 };
         static color: Color = Color::Yellow;
         static wall: graph::Wall = graph::Wall {
-            start_point: Point { x: 1, y: 3 },
-            end_point: Point { x: 5, y: 8 },
+            start_point: graph::Coordinate { x: 1.0, y: 3.0 },
+            end_point: graph::Coordinate { x: 5.0, y: 8.0 },
             primary_object_color: Color::Yellow,
+            height: graph::WallHeight {
+                height: Some(2.0),
+                elevation: 0.5,
+            },
         };
+        // start of the wall is clipped by the edge of the field of view
+        static clipped_start_point: graph::Coordinate = graph::Coordinate { x: 2.0, y: 4.25 };
 
         let start_point_width = 15.0;
-        let start_point_height = 17.0;
+        let start_point_height = (17.0, 16.0);
         let end_point_width = 19.0;
-        let end_point_height = 21.0;
+        let end_point_height = (21.0, 20.0);
+        static eye_offset: f64 = 0.5;
 
         let mut point_generator = MockPointGenerator::default();
         let mut seq = Sequence::new();
 
+        point_generator
+            .expect_clip_wall()
+            .times(1)
+            .withf(
+                |angle_: &player_utils::Angle,
+                 position_: &graph::Coordinate,
+                 wall_: &graph::Wall| {
+                    *angle_ == angle && *position_ == position && *wall_ == wall
+                },
+            )
+            .return_const(Some((clipped_start_point.clone(), wall.end_point.clone())))
+            .in_sequence(&mut seq);
+
         point_generator
             .expect_point_width()
             .times(1)
             .withf(
                 |angle_: &player_utils::Angle,
                  start_position: &graph::Coordinate,
-                 end_position: &Point| {
+                 end_position: &graph::Coordinate| {
                     *angle_ == angle
                         && *start_position == position
-                        && *end_position == wall.start_point
+                        && *end_position == clipped_start_point
                 },
             )
             .return_const(start_point_width)
@@ -97,10 +196,14 @@ mod tests {
             .withf(
                 |angle_: &player_utils::Angle,
                  start_position: &graph::Coordinate,
-                 end_position: &Point| {
+                 end_position: &graph::Coordinate,
+                 height: &graph::WallHeight,
+                 eye_offset_: &f64| {
                     *angle_ == angle
                         && *start_position == position
-                        && *end_position == wall.start_point
+                        && *end_position == clipped_start_point
+                        && *height == wall.height
+                        && *eye_offset_ == eye_offset
                 },
             )
             .return_const(start_point_height)
@@ -112,7 +215,7 @@ mod tests {
             .withf(
                 |angle_: &player_utils::Angle,
                  start_position: &graph::Coordinate,
-                 end_position: &Point| {
+                 end_position: &graph::Coordinate| {
                     *angle_ == angle
                         && *start_position == position
                         && *end_position == wall.end_point
@@ -127,26 +230,170 @@ mod tests {
             .withf(
                 |angle_: &player_utils::Angle,
                  start_position: &graph::Coordinate,
-                 end_position: &Point| {
+                 end_position: &graph::Coordinate,
+                 height: &graph::WallHeight,
+                 eye_offset_: &f64| {
                     *angle_ == angle
                         && *start_position == position
                         && *end_position == wall.end_point
+                        && *height == wall.height
+                        && *eye_offset_ == eye_offset
                 },
             )
             .return_const(end_point_height)
             .in_sequence(&mut seq);
 
         assert_eq!(
-            PolygonGenerator { point_generator }.generate_polygon(&wall, &position, &angle),
-            Polygon {
+            PolygonGenerator { point_generator }
+                .generate_polygon(&wall, &position, &angle, eye_offset),
+            Some(Polygon {
                 area: [
-                    [start_point_width, start_point_height],
-                    [end_point_width, end_point_height],
-                    [end_point_width, -end_point_height],
-                    [start_point_width, -start_point_height]
+                    [start_point_width, start_point_height.0],
+                    [end_point_width, end_point_height.0],
+                    [end_point_width, -end_point_height.1],
+                    [start_point_width, -start_point_height.1]
                 ],
                 color: color.clone()
+            })
+        );
+    }
+
+    #[test]
+    fn generate_polygon_not_visible() {
+        let mut point_generator = MockPointGenerator::default();
+        point_generator
+            .expect_clip_wall()
+            .times(1)
+            .return_const(None);
+        point_generator.expect_point_width().never();
+        point_generator.expect_point_height().never();
+
+        assert_eq!(
+            PolygonGenerator { point_generator }.generate_polygon(
+                &Default::default(),
+                &Default::default(),
+                &Default::default(),
+                0.0
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn generate_column() {
+        static angle: player_utils::Angle = player_utils::Angle {
+            start: player_utils::Radians::PI,
+            end: player_utils::Radians::PI_2,
+        };
+        static position: graph::Coordinate = graph::Coordinate { x: 11.0, y: 13.0 };
+        static column: graph::Column = graph::Column {
+            index: 7,
+            hit_point: graph::Coordinate { x: 1.0, y: 3.0 },
+            color: Color::Blue,
+            height: graph::WallHeight {
+                height: Some(0.5),
+                elevation: 0.0,
+            },
+        };
+
+        let mut point_generator = MockPointGenerator::default();
+        point_generator
+            .expect_point_height()
+            .times(1)
+            .withf(
+                |angle_: &player_utils::Angle,
+                 start_position: &graph::Coordinate,
+                 end_position: &graph::Coordinate,
+                 height: &graph::WallHeight,
+                 eye_offset: &f64| {
+                    *angle_ == angle
+                        && *start_position == position
+                        && *end_position == column.hit_point
+                        && *height == column.height
+                        && *eye_offset == -1.0
+                },
+            )
+            .return_const((20.0, 30.0));
+
+        assert_eq!(
+            PolygonGenerator { point_generator }.generate_column(&column, &position, &angle, -1.0),
+            Polygon {
+                area: [[7.0, 20.0], [8.0, 20.0], [8.0, -30.0], [7.0, -30.0]],
+                color: Color::Blue
+            }
+        );
+    }
+
+    #[test]
+    fn generate_surface() {
+        let surface = |is_floor: bool| graph::Surface {
+            corners: vec![],
+            height: 0.5,
+            color: Color::Green,
+            is_floor,
+        };
+        let point_generator = || {
+            let mut point_generator = MockPointGenerator::default();
+            point_generator.expect_clip_surface().return_const(
+                (0..5)
+                    .map(|index| graph::Coordinate {
+                        x: index as f64,
+                        y: 0.0,
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            point_generator
+                .expect_point_width()
+                .returning(|_, _, corner| corner.x * 10.0);
+            point_generator
+                .expect_point_height()
+                .withf(|_, _, _, height, eye_offset| {
+                    *height == graph::WallHeight::new(0.0, 0.5) && *eye_offset == 0.25
+                })
+                .returning(|_, _, corner, _, _| (-corner.x - 1.0, 0.0));
+            point_generator
+        };
+        let position = graph::Coordinate { x: 1.0, y: 2.0 };
+
+        // five corners are drawn as two quads with the first corner in common
+        assert_eq!(
+            PolygonGenerator {
+                point_generator: point_generator()
+            }
+            .generate_surface(&surface(true), &position, &Default::default(), 0.25),
+            vec![
+                Polygon {
+                    area: [[0.0, -1.0], [10.0, -2.0], [20.0, -3.0], [30.0, -4.0]],
+                    color: Color::Green
+                },
+                Polygon {
+                    area: [[0.0, -1.0], [30.0, -4.0], [40.0, -5.0], [40.0, -5.0]],
+                    color: Color::Green
+                },
+            ]
+        );
+        // ceiling below the eye is not visible
+        assert_eq!(
+            PolygonGenerator {
+                point_generator: point_generator()
             }
+            .generate_surface(&surface(false), &position, &Default::default(), 0.25),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn horizon_offset() {
+        let mut point_generator = MockPointGenerator::default();
+        point_generator
+            .expect_horizon_offset()
+            .times(1)
+            .withf(|_, pitch| *pitch == 0.25)
+            .return_const(30.0);
+
+        assert_eq!(
+            PolygonGenerator { point_generator }.horizon_offset(&Default::default(), 0.25),
+            30.0
         );
     }
 }
@@ -0,0 +1,11 @@
+use super::{Coordinate, WallHeight};
+use crate::map_element::Color;
+
+// Wall hit by the ray cast for one screen column.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Column {
+    pub index: usize,
+    pub hit_point: Coordinate,
+    pub color: Color,
+    pub height: WallHeight,
+}
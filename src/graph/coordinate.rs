@@ -67,15 +67,20 @@ impl Coordinate {
         return player_utils::Radians::new((delta_y / delta_x).atan() + std::f64::consts::PI);
     }
 
-    pub(crate) fn into_radians_point(&self, end_point: &Point) -> player_utils::Radians {
-        self.into_radians(end_point.x as f64, end_point.y as f64)
-    }
-
     pub(crate) fn into_radians_coor(&self, end_point: &Coordinate) -> player_utils::Radians {
         self.into_radians(end_point.x, end_point.y)
     }
 }
 
+impl From<Point> for Coordinate {
+    fn from(point: Point) -> Self {
+        Self {
+            x: point.x as f64,
+            y: point.y as f64,
+        }
+    }
+}
+
 impl std::ops::AddAssign<&Coordinate> for Coordinate {
     fn add_assign(&mut self, rhs: &Self) {
         self.x += rhs.x;
@@ -197,7 +202,10 @@ mod tests {
         let mut radian = player_utils::Radians::new(0.0);
 
         for end_point in end_points {
-            assert_eq!(start_coordinate.into_radians_point(&end_point), radian);
+            assert_eq!(
+                start_coordinate.into_radians(end_point.x as f64, end_point.y as f64),
+                radian
+            );
             radian += player_utils::Radians::new(std::f64::consts::PI / 4.0);
         }
     }
@@ -0,0 +1,344 @@
+use super::{Coordinate, LinearGraph};
+
+// Returns 1 or -1 in the direction of the ray and the distance along the ray
+// between the following grid lines.
+fn axis_step(direction: f64) -> (f64, f64) {
+    if direction > 0.0 {
+        (1.0, 1.0 / direction)
+    } else if direction < 0.0 {
+        (-1.0, -1.0 / direction)
+    } else {
+        (0.0, f64::INFINITY)
+    }
+}
+
+// Returns the first grid line crossed by the ray and the distance to it.
+fn first_line(position: f64, step: f64, delta: f64) -> (f64, f64) {
+    if step > 0.0 {
+        let line = position.floor() + 1.0;
+        (line, (line - position) * delta)
+    } else if step < 0.0 {
+        let line = position.ceil() - 1.0;
+        (line, (position - line) * delta)
+    } else {
+        (position, f64::INFINITY)
+    }
+}
+
+// Incremental DDA over the grid. Returns every coordinate where the ray
+// crosses a vertical (integer x) or horizontal (integer y) grid line with the
+// distance from the start position. Steps are computed once for the ray, so
+// every next coordinate costs only a few additions.
+pub struct GridTraversal {
+    start: Coordinate,
+    direction: (f64, f64),
+    step: (f64, f64),
+    delta: (f64, f64),
+    line: (f64, f64),
+    distance: (f64, f64),
+}
+
+impl GridTraversal {
+    pub fn new(ray: &LinearGraph, start: &Coordinate) -> Self {
+        let direction = (ray.direction.x, ray.direction.y);
+        let (step_x, delta_x) = axis_step(direction.0);
+        let (step_y, delta_y) = axis_step(direction.1);
+        let (line_x, distance_x) = first_line(start.x, step_x, delta_x);
+        let (line_y, distance_y) = first_line(start.y, step_y, delta_y);
+        Self {
+            start: start.clone(),
+            direction,
+            step: (step_x, step_y),
+            delta: (delta_x, delta_y),
+            line: (line_x, line_y),
+            distance: (distance_x, distance_y),
+        }
+    }
+}
+
+impl Iterator for GridTraversal {
+    type Item = (Coordinate, f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.distance.0 < self.distance.1 {
+            let distance = self.distance.0;
+            let coordinate = Coordinate {
+                x: self.line.0,
+                y: self.start.y + distance * self.direction.1,
+            };
+            self.line.0 += self.step.0;
+            self.distance.0 += self.delta.0;
+            Some((coordinate, distance))
+        } else if self.distance.1 < self.distance.0 {
+            let distance = self.distance.1;
+            let coordinate = Coordinate {
+                x: self.start.x + distance * self.direction.0,
+                y: self.line.1,
+            };
+            self.line.1 += self.step.1;
+            self.distance.1 += self.delta.1;
+            Some((coordinate, distance))
+        } else if self.distance.0 < f64::INFINITY {
+            // ray goes exactly through the corner, both lines are crossed at once
+            let distance = self.distance.0;
+            let coordinate = Coordinate {
+                x: self.line.0,
+                y: self.line.1,
+            };
+            self.line.0 += self.step.0;
+            self.line.1 += self.step.1;
+            self.distance.0 += self.delta.0;
+            self.distance.1 += self.delta.1;
+            Some((coordinate, distance))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player_utils::Radians;
+    use float_cmp::approx_eq;
+
+    fn first_coordinates(ray: &LinearGraph, start: &Coordinate, amount: usize) -> Vec<Coordinate> {
+        GridTraversal::new(ray, start)
+            .take(amount)
+            .map(|(coordinate, _)| coordinate)
+            .collect()
+    }
+
+    fn assert_coordinates(result: &[Coordinate], expected: &[(f64, f64)]) {
+        assert_eq!(result.len(), expected.len());
+        for (coordinate, (x, y)) in result.iter().zip(expected.iter()) {
+            assert!(
+                approx_eq!(f64, coordinate.x, *x, epsilon = 1e-9)
+                    && approx_eq!(f64, coordinate.y, *y, epsilon = 1e-9),
+                "{:?} != ({}, {})",
+                coordinate,
+                x,
+                y
+            );
+        }
+    }
+
+    #[test]
+    fn horizontal() {
+        let start = Coordinate { x: 2.0, y: 1.5 };
+        assert_eq!(
+            first_coordinates(&LinearGraph::from_radians(Radians::ZERO), &start, 2),
+            [Coordinate { x: 3.0, y: 1.5 }, Coordinate { x: 4.0, y: 1.5 }]
+        );
+        assert_eq!(
+            first_coordinates(&LinearGraph::from_radians(Radians::PI), &start, 2),
+            [Coordinate { x: 1.0, y: 1.5 }, Coordinate { x: 0.0, y: 1.5 }]
+        );
+    }
+
+    #[test]
+    fn vertical() {
+        let start = Coordinate { x: 1.5, y: 1.0 };
+        assert_eq!(
+            first_coordinates(&LinearGraph::from_radians(Radians::PI / 2.0), &start, 2),
+            [Coordinate { x: 1.5, y: 2.0 }, Coordinate { x: 1.5, y: 3.0 }]
+        );
+        assert_eq!(
+            first_coordinates(
+                &LinearGraph::from_radians(Radians::new(std::f64::consts::PI * 3.0 / 2.0)),
+                &start,
+                2
+            ),
+            [
+                Coordinate { x: 1.5, y: 0.0 },
+                Coordinate { x: 1.5, y: -1.0 }
+            ]
+        );
+    }
+
+    #[test]
+    fn positive_slope() {
+        let tangens = 1.5_f64;
+        let start = Coordinate { x: 1.0, y: 1.25 };
+        assert_coordinates(
+            &first_coordinates(
+                &LinearGraph::from_radians(Radians::new(tangens.atan())),
+                &start,
+                3,
+            ),
+            &[
+                (1.0 + 0.75 / tangens, 2.0),
+                (2.0, 1.25 + tangens),
+                (1.0 + 1.75 / tangens, 3.0),
+            ],
+        );
+        assert_coordinates(
+            &first_coordinates(
+                &LinearGraph::from_radians(Radians::new(tangens.atan() + std::f64::consts::PI)),
+                &start,
+                3,
+            ),
+            &[
+                (1.0 - 0.25 / tangens, 1.0),
+                (1.0 - 1.25 / tangens, 0.0),
+                (0.0, 1.25 - tangens),
+            ],
+        );
+    }
+
+    #[test]
+    fn negative_slope() {
+        let tangens = -0.25_f64;
+        let start = Coordinate { x: 1.5, y: 1.0 };
+        assert_coordinates(
+            &first_coordinates(
+                &LinearGraph::from_radians(Radians::new(tangens.atan())),
+                &start,
+                3,
+            ),
+            &[
+                (2.0, 1.0 + 0.5 * tangens),
+                (3.0, 1.0 + 1.5 * tangens),
+                (4.0, 1.0 + 2.5 * tangens),
+            ],
+        );
+        assert_coordinates(
+            &first_coordinates(
+                &LinearGraph::from_radians(Radians::new(tangens.atan() + std::f64::consts::PI)),
+                &start,
+                2,
+            ),
+            &[(1.0, 1.0 - 0.5 * tangens), (0.0, 1.0 - 1.5 * tangens)],
+        );
+    }
+
+    #[test]
+    fn distance() {
+        let ray = LinearGraph::from_radians(Radians::new(std::f64::consts::PI / 3.0));
+        let start = Coordinate { x: 0.5, y: 0.5 };
+        for (coordinate, distance) in GridTraversal::new(&ray, &start).take(10) {
+            assert!(approx_eq!(
+                f64,
+                start.distance(&coordinate),
+                distance,
+                epsilon = 1e-9
+            ));
+        }
+    }
+
+    fn next_coordinate(radians: f64, start: &Coordinate) -> Coordinate {
+        let ray = LinearGraph::from_radians(Radians::new(radians));
+        GridTraversal::new(&ray, start).next().unwrap().0
+    }
+
+    // Ray from the first coordinate crosses the grid first in the second one,
+    // ray in the opposite direction from the second coordinate goes back to the
+    // first one, also when they lie on the grid lines.
+    fn test_coordinates(
+        radians: f64,
+        first_coordinate: &Coordinate,
+        second_coordinate: &Coordinate,
+    ) {
+        assert_coordinates(
+            &[next_coordinate(radians, first_coordinate)],
+            &[(second_coordinate.x, second_coordinate.y)],
+        );
+        assert_coordinates(
+            &[next_coordinate(
+                radians + std::f64::consts::PI,
+                second_coordinate,
+            )],
+            &[(first_coordinate.x, first_coordinate.y)],
+        );
+    }
+
+    #[test]
+    fn next_coordinate_positive_slope_y_closer() {
+        let tangens = 1.5_f64;
+        let first_coordinate = Coordinate { x: 1.0, y: 1.5 };
+        let second_coordinate = Coordinate {
+            x: first_coordinate.x + 0.5 / tangens,
+            y: 2.0,
+        };
+        test_coordinates(tangens.atan(), &first_coordinate, &second_coordinate);
+    }
+
+    #[test]
+    fn next_coordinate_negative_slope_y_closer() {
+        let tangens = -1.5_f64;
+        let first_coordinate = Coordinate { x: 1.0, y: 1.5 };
+        let second_coordinate = Coordinate {
+            x: first_coordinate.x - 0.5 / tangens,
+            y: 1.0,
+        };
+        test_coordinates(tangens.atan(), &first_coordinate, &second_coordinate);
+    }
+
+    #[test]
+    fn next_coordinate_positive_slope_x_closer() {
+        let tangens = 0.25_f64;
+        let first_coordinate = Coordinate { x: 1.5, y: 1.0 };
+        let second_coordinate = Coordinate {
+            x: 2.0,
+            y: first_coordinate.y + 0.5 * tangens,
+        };
+        test_coordinates(tangens.atan(), &first_coordinate, &second_coordinate);
+    }
+
+    #[test]
+    fn next_coordinate_negative_slope_x_closer() {
+        let tangens = -0.25_f64;
+        let first_coordinate = Coordinate { x: 1.5, y: 1.0 };
+        let second_coordinate = Coordinate {
+            x: 2.0,
+            y: first_coordinate.y + 0.5 * tangens,
+        };
+        test_coordinates(tangens.atan(), &first_coordinate, &second_coordinate);
+    }
+
+    #[test]
+    fn next_coordinate_vertical() {
+        test_coordinates(
+            std::f64::consts::PI / 2.0,
+            &Coordinate { x: 1.5, y: 1.0 },
+            &Coordinate { x: 1.5, y: 2.0 },
+        );
+        // ray along the grid line doesn't cross the vertical lines
+        test_coordinates(
+            std::f64::consts::PI * 3.0 / 2.0,
+            &Coordinate { x: 2.0, y: 2.0 },
+            &Coordinate { x: 2.0, y: 1.0 },
+        );
+    }
+
+    #[test]
+    fn next_coordinate_horizontal() {
+        test_coordinates(
+            0.0,
+            &Coordinate { x: 2.0, y: 1.5 },
+            &Coordinate { x: 3.0, y: 1.5 },
+        );
+        test_coordinates(
+            std::f64::consts::PI,
+            &Coordinate { x: 3.0, y: 2.0 },
+            &Coordinate { x: 2.0, y: 2.0 },
+        );
+    }
+
+    #[test]
+    fn next_coordinate_exact_corner() {
+        // corner is crossed once and the ray goes on to the next corner
+        let ray = LinearGraph::from_direction(&Coordinate { x: 1.0, y: 1.0 });
+        let start = Coordinate { x: 1.0, y: 1.0 };
+        assert_eq!(
+            first_coordinates(&ray, &start, 2),
+            [Coordinate { x: 2.0, y: 2.0 }, Coordinate { x: 3.0, y: 3.0 }]
+        );
+        let ray = LinearGraph::from_direction(&Coordinate { x: -1.0, y: 1.0 });
+        let start = Coordinate { x: 1.5, y: 0.5 };
+        assert_eq!(
+            first_coordinates(&ray, &start, 2),
+            [Coordinate { x: 1.0, y: 1.0 }, Coordinate { x: 0.0, y: 2.0 }]
+        );
+    }
+}
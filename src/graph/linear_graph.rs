@@ -5,26 +5,17 @@ use crate::player_utils::Radians;
 #[cfg(test)]
 use mockall::automock;
 
-#[derive(Debug, PartialEq, Clone, Default)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct LinearGraph {
     pub radians: Radians,
-    pub tangens: f64,
+    // unit vector pointing in the direction of the ray
+    pub direction: Coordinate,
 }
 
-fn next_integer(value: f64) -> f64 {
-    let next_value = value.ceil();
-    if next_value == value {
-        return next_value + 1_f64;
+impl Default for LinearGraph {
+    fn default() -> Self {
+        LinearGraph::from_radians(Radians::ZERO)
     }
-    return next_value;
-}
-
-fn prev_integer(value: f64) -> f64 {
-    let prev_value = value.floor();
-    if prev_value == value {
-        return prev_value - 1_f64;
-    }
-    return prev_value;
 }
 
 fn generate_one_graph(amount: usize, index: usize) -> LinearGraph {
@@ -45,59 +36,41 @@ fn generate_one_graph(amount: usize, index: usize) -> LinearGraph {
 
 impl LinearGraph {
     pub fn from_radians(radians: Radians) -> Self {
-        if radians == Radians::ZERO || radians == Radians::PI {
-            // TODO consider to remove this check
-            return LinearGraph {
-                radians,
-                tangens: 0.0,
-            };
-        }
+        // directions along the axes are exact, so rays don't drift from the grid lines
+        let direction = if radians == Radians::ZERO {
+            Coordinate { x: 1.0, y: 0.0 }
+        } else if radians == Radians::PI / 2.0 {
+            Coordinate { x: 0.0, y: 1.0 }
+        } else if radians == Radians::PI {
+            Coordinate { x: -1.0, y: 0.0 }
+        } else if radians == Radians::new(std::f64::consts::PI * 3.0 / 2.0) {
+            Coordinate { x: 0.0, y: -1.0 }
+        } else {
+            Coordinate {
+                x: radians.to_f64().cos(),
+                y: radians.to_f64().sin(),
+            }
+        };
+        LinearGraph { radians, direction }
+    }
+
+    // direction doesn't have to be normalized
+    pub fn from_direction(direction: &Coordinate) -> Self {
+        let length = direction.x.hypot(direction.y);
         LinearGraph {
-            radians,
-            tangens: radians.tan(),
+            radians: Radians::new(direction.y.atan2(direction.x)),
+            direction: Coordinate {
+                x: direction.x / length,
+                y: direction.y / length,
+            },
         }
     }
 
     pub fn get_next_from_distance(&self, coordinate: &Coordinate, distance: f64) -> Coordinate {
-        if self.radians > Radians::new(std::f64::consts::PI * 3.0 / 2.0)
-            || (self.radians >= Radians::ZERO && self.radians < Radians::PI / 2.0)
-        {
-            let x_delta = distance / (self.tangens.powi(2) + 1.0).sqrt();
-            let y_delta = x_delta * self.tangens;
-            return Coordinate {
-                x: coordinate.x + x_delta,
-                y: coordinate.y + y_delta,
-            };
-        } else if self.radians == Radians::PI / 2.0 {
-            return Coordinate {
-                x: coordinate.x,
-                y: coordinate.y + distance,
-            };
-        } else if self.radians > Radians::PI / 2.0
-            && self.radians < Radians::new(std::f64::consts::PI * 3.0 / 2.0)
-        {
-            let mut x_delta = distance / (self.tangens.powi(2) + 1.0).sqrt();
-            if distance > 0.0 {
-                if x_delta > 0.0 {
-                    x_delta *= -1.0;
-                }
-            } else {
-                if x_delta < 0.0 {
-                    x_delta *= -1.0;
-                }
-            }
-            let y_delta = x_delta * self.tangens;
-            return Coordinate {
-                x: coordinate.x + x_delta,
-                y: coordinate.y + y_delta,
-            };
-        } else if self.radians == Radians::new(std::f64::consts::PI * 3.0 / 2.0) {
-            return Coordinate {
-                x: coordinate.x,
-                y: coordinate.y - distance,
-            };
+        Coordinate {
+            x: coordinate.x + self.direction.x * distance,
+            y: coordinate.y + self.direction.y * distance,
         }
-        panic!("radians value out of scope");
     }
 
     pub fn get_all_rays(number_of_rays: usize) -> Rays {
@@ -126,128 +99,12 @@ impl GraphMethods {
     pub fn less_than(lhs: &LinearGraph, rhs: &LinearGraph) -> bool {
         lhs < rhs
     }
-
-    pub fn get_next(linear_graph: &LinearGraph, current_coordinate: &Coordinate) -> Coordinate {
-        if linear_graph.radians >= Radians::ZERO && linear_graph.radians < Radians::PI / 2.0 {
-            let next_x = next_integer(current_coordinate.x);
-            let next_y = next_integer(current_coordinate.y);
-            let delta_x = next_x - current_coordinate.x;
-            let delta_y = next_y - current_coordinate.y;
-            if delta_x * linear_graph.tangens < delta_y {
-                return Coordinate {
-                    x: next_x,
-                    y: current_coordinate.y + delta_x * linear_graph.tangens,
-                };
-            } else {
-                return Coordinate {
-                    x: current_coordinate.x + delta_y / linear_graph.tangens,
-                    y: next_y,
-                };
-            }
-        } else if linear_graph.radians == Radians::PI / 2.0 {
-            return Coordinate {
-                x: current_coordinate.x,
-                y: next_integer(current_coordinate.y),
-            };
-        } else if linear_graph.radians > Radians::PI / 2.0 && linear_graph.radians < Radians::PI {
-            let prev_x = prev_integer(current_coordinate.x);
-            let next_y = next_integer(current_coordinate.y);
-            let delta_x = prev_x - current_coordinate.x; // negative
-            let delta_y = next_y - current_coordinate.y;
-            if -delta_x * -linear_graph.tangens < delta_y {
-                return Coordinate {
-                    x: prev_x,
-                    y: current_coordinate.y + delta_x * linear_graph.tangens,
-                };
-            } else {
-                return Coordinate {
-                    x: current_coordinate.x + delta_y / linear_graph.tangens,
-                    y: next_y,
-                };
-            }
-        } else if linear_graph.radians >= Radians::PI
-            && linear_graph.radians < Radians::new(std::f64::consts::PI * 3.0 / 2.0)
-        {
-            let prev_x = prev_integer(current_coordinate.x);
-            let prev_y = prev_integer(current_coordinate.y);
-            let delta_x = prev_x - current_coordinate.x; // negative
-            let delta_y = prev_y - current_coordinate.y; // negative
-            if -delta_x * linear_graph.tangens < -delta_y {
-                return Coordinate {
-                    x: prev_x,
-                    y: current_coordinate.y + delta_x * linear_graph.tangens,
-                };
-            } else {
-                return Coordinate {
-                    x: current_coordinate.x + delta_y / linear_graph.tangens,
-                    y: prev_y,
-                };
-            }
-        } else if linear_graph.radians == Radians::new(std::f64::consts::PI * 3.0 / 2.0) {
-            return Coordinate {
-                x: current_coordinate.x,
-                y: prev_integer(current_coordinate.y),
-            };
-        } else if linear_graph.radians > Radians::new(std::f64::consts::PI * 3.0 / 2.0)
-            && linear_graph.radians < Radians::PI_2
-        {
-            let next_x = next_integer(current_coordinate.x);
-            let prev_y = prev_integer(current_coordinate.y);
-            let delta_x = next_x - current_coordinate.x;
-            let delta_y = prev_y - current_coordinate.y; // negative
-            if delta_x * -linear_graph.tangens < -delta_y {
-                return Coordinate {
-                    x: next_x,
-                    y: current_coordinate.y + delta_x * linear_graph.tangens,
-                };
-            } else {
-                return Coordinate {
-                    x: current_coordinate.x + delta_y / linear_graph.tangens,
-                    y: prev_y,
-                };
-            }
-        }
-        panic!("radians value out of scope");
-    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    const FLOAT_1: f64 = 1.6;
-    const FLOAT_2: f64 = 2.0;
-    const FLOAT_3: f64 = 2.4;
-
-    #[test]
-    fn test_next_integer() {
-        const NEXT_INTEGER_1: f64 = 2.0;
-        const NEXT_INTEGER_2: f64 = 3.0;
-        const NEXT_INTEGER_3: f64 = 3.0;
-
-        assert_eq!(next_integer(FLOAT_1), NEXT_INTEGER_1);
-        assert_eq!(next_integer(FLOAT_2), NEXT_INTEGER_2);
-        assert_eq!(next_integer(FLOAT_3), NEXT_INTEGER_3);
-
-        assert_ne!(next_integer(FLOAT_1), NEXT_INTEGER_3);
-        assert_ne!(next_integer(FLOAT_2), NEXT_INTEGER_1);
-        assert_ne!(next_integer(FLOAT_3), NEXT_INTEGER_1);
-    }
-
-    #[test]
-    fn test_prev_integer() {
-        const PREV_INTEGER_1: f64 = 1.0;
-        const PREV_INTEGER_2: f64 = 1.0;
-        const PREV_INTEGER_3: f64 = 2.0;
-
-        assert_eq!(prev_integer(FLOAT_1), PREV_INTEGER_1);
-        assert_eq!(prev_integer(FLOAT_2), PREV_INTEGER_2);
-        assert_eq!(prev_integer(FLOAT_3), PREV_INTEGER_3);
-
-        assert_ne!(prev_integer(FLOAT_1), PREV_INTEGER_3);
-        assert_ne!(prev_integer(FLOAT_2), PREV_INTEGER_3);
-        assert_ne!(prev_integer(FLOAT_3), PREV_INTEGER_1);
-    }
+    use float_cmp::approx_eq;
 
     #[test]
     fn get_all_rays_check_size() {
@@ -285,23 +142,64 @@ mod tests {
             LinearGraph::from_radians(radians_1),
             LinearGraph {
                 radians: radians_1,
-                tangens: 0.0
+                direction: Coordinate { x: 1.0, y: 0.0 }
             }
         );
         assert_eq!(
             LinearGraph::from_radians(radians_2),
             LinearGraph {
                 radians: radians_2,
-                tangens: 0.0
+                direction: Coordinate { x: -1.0, y: 0.0 }
             }
         );
         assert_eq!(
             LinearGraph::from_radians(radians_3),
             LinearGraph {
                 radians: radians_3,
-                tangens: radians_3.tan()
+                direction: Coordinate {
+                    x: 3.0_f64.cos(),
+                    y: 3.0_f64.sin()
+                }
             }
         );
+        assert_eq!(
+            LinearGraph::default(),
+            LinearGraph::from_radians(Radians::ZERO)
+        );
+    }
+
+    #[test]
+    fn from_direction() {
+        let linear_graph = LinearGraph::from_direction(&Coordinate { x: -3.0, y: -4.0 });
+        assert_eq!(linear_graph.direction, Coordinate { x: -0.6, y: -0.8 });
+        assert!(approx_eq!(
+            f64,
+            linear_graph.radians.to_f64(),
+            (-4.0_f64).atan2(-3.0) + std::f64::consts::PI * 2.0,
+            ulps = 2
+        ));
+    }
+
+    #[test]
+    fn from_radians_vertical() {
+        assert_eq!(
+            LinearGraph::from_radians(Radians::PI / 2.0).direction,
+            Coordinate { x: 0.0, y: 1.0 }
+        );
+        assert_eq!(
+            LinearGraph::from_radians(Radians::new(std::f64::consts::PI * 3.0 / 2.0)).direction,
+            Coordinate { x: 0.0, y: -1.0 }
+        );
+        // close to vertical direction is still a unit vector
+        let direction =
+            LinearGraph::from_radians(Radians::new(std::f64::consts::PI / 2.0 + 1e-12)).direction;
+        assert!(direction.x < 0.0 && direction.x > -1e-11);
+        assert!(approx_eq!(
+            f64,
+            direction.x.hypot(direction.y),
+            1.0,
+            ulps = 2
+        ));
     }
 
     fn test_get_next_from_distance(
@@ -417,18 +315,4 @@ mod tests {
             linear_graph_2
         );
     }
-
-    #[test]
-    #[should_panic]
-    fn get_next_from_distance_radians_out_of_scope() {
-        let linear_graph = LinearGraph::from_radians(Radians::OUT_OF_RANGE);
-        linear_graph.get_next_from_distance(&Coordinate::default(), f64::default());
-    }
-
-    #[test]
-    #[should_panic]
-    fn get_next_radians_out_of_scope() {
-        let linear_graph = LinearGraph::from_radians(Radians::OUT_OF_RANGE);
-        GraphMethods::get_next(&linear_graph, &Coordinate::default());
-    }
 }
@@ -0,0 +1,12 @@
+use super::Coordinate;
+use crate::map_element::Color;
+
+// Horizontal polygon, e.g. floor or ceiling of the sector.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Surface {
+    pub corners: Vec<Coordinate>,
+    pub height: f64,
+    pub color: Color,
+    // floor is seen from above, ceiling from below
+    pub is_floor: bool,
+}
@@ -0,0 +1,203 @@
+use super::{Coordinate, LinearGraph};
+use crate::map_element::Color;
+use crate::player_utils::Radians;
+
+// Rotation followed by the move, used to carry rays and the player through
+// the portals. Mirrored transform flips the y axis before the rotation.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Transform {
+    angle: f64,
+    is_mirrored: bool,
+    offset: Coordinate,
+}
+
+// Crossing of the portal or the mirror by the ray or the move.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Crossing {
+    pub point: Coordinate,
+    // moves the ray to the place where it continues
+    pub transform: Transform,
+    // mixed into the colors of the walls seen through the crossing
+    pub tint: Option<Color>,
+    // ray which has already passed this many crossings goes straight on
+    pub max_passes: usize,
+}
+
+impl Transform {
+    // rotates by the angle around from, then moves from to to
+    pub fn new(from: &Coordinate, to: &Coordinate, angle: f64) -> Self {
+        Self::with_offset(angle, false, from, to)
+    }
+
+    // reflection across the line which goes through both points
+    pub fn reflection(first: &Coordinate, second: &Coordinate) -> Self {
+        let angle = (second.y - first.y).atan2(second.x - first.x);
+        Self::with_offset(angle * 2.0, true, first, first)
+    }
+
+    fn with_offset(angle: f64, is_mirrored: bool, from: &Coordinate, to: &Coordinate) -> Self {
+        let mut transform = Self {
+            angle,
+            is_mirrored,
+            offset: Coordinate { x: 0.0, y: 0.0 },
+        };
+        let rotated = transform.rotate(from);
+        transform.offset = Coordinate {
+            x: to.x - rotated.x,
+            y: to.y - rotated.y,
+        };
+        transform
+    }
+
+    pub fn angle(&self) -> Radians {
+        Radians::new(self.angle)
+    }
+
+    pub fn is_mirrored(&self) -> bool {
+        self.is_mirrored
+    }
+
+    pub fn apply(&self, coordinate: &Coordinate) -> Coordinate {
+        let rotated = self.rotate(coordinate);
+        Coordinate {
+            x: rotated.x + self.offset.x,
+            y: rotated.y + self.offset.y,
+        }
+    }
+
+    pub fn apply_ray(&self, ray: &LinearGraph) -> LinearGraph {
+        LinearGraph::from_direction(&self.rotate(&ray.direction))
+    }
+
+    pub fn inverse(&self) -> Self {
+        // reflection is its own inverse
+        let angle = if self.is_mirrored {
+            self.angle
+        } else {
+            -self.angle
+        };
+        Self::with_offset(
+            angle,
+            self.is_mirrored,
+            &self.offset,
+            &Coordinate { x: 0.0, y: 0.0 },
+        )
+    }
+
+    // transform which applies self first and then the next one
+    pub fn then(&self, next: &Transform) -> Self {
+        let angle = if next.is_mirrored {
+            next.angle - self.angle
+        } else {
+            next.angle + self.angle
+        };
+        Self {
+            angle,
+            is_mirrored: self.is_mirrored != next.is_mirrored,
+            offset: next.apply(&self.offset),
+        }
+    }
+
+    pub(crate) fn rotate(&self, coordinate: &Coordinate) -> Coordinate {
+        let (sin, cos) = self.angle.sin_cos();
+        let y = if self.is_mirrored {
+            -coordinate.y
+        } else {
+            coordinate.y
+        };
+        Coordinate {
+            x: coordinate.x * cos - y * sin,
+            y: coordinate.x * sin + y * cos,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    fn assert_coordinate(coordinate: Coordinate, x: f64, y: f64) {
+        assert!(
+            approx_eq!(f64, coordinate.x, x, epsilon = 1e-9)
+                && approx_eq!(f64, coordinate.y, y, epsilon = 1e-9),
+            "{:?} != ({}, {})",
+            coordinate,
+            x,
+            y
+        );
+    }
+
+    #[test]
+    fn apply() {
+        let transform = Transform::new(
+            &Coordinate { x: 1.0, y: 1.0 },
+            &Coordinate { x: 10.0, y: 5.0 },
+            std::f64::consts::PI / 2.0,
+        );
+        assert_coordinate(transform.apply(&Coordinate { x: 1.0, y: 1.0 }), 10.0, 5.0);
+        assert_coordinate(transform.apply(&Coordinate { x: 2.0, y: 1.0 }), 10.0, 6.0);
+        assert_coordinate(
+            transform.apply_ray(&LinearGraph::default()).direction,
+            0.0,
+            1.0,
+        );
+        assert_eq!(transform.angle(), Radians::PI / 2.0);
+    }
+
+    #[test]
+    fn inverse_then() {
+        let first = Transform::new(
+            &Coordinate { x: 1.0, y: 2.0 },
+            &Coordinate { x: 7.0, y: -3.0 },
+            0.7,
+        );
+        let second = Transform::new(
+            &Coordinate { x: -4.0, y: 2.0 },
+            &Coordinate { x: 0.5, y: 3.0 },
+            2.1,
+        );
+        let coordinate = Coordinate { x: 3.0, y: 4.0 };
+
+        let back = first.inverse().apply(&first.apply(&coordinate));
+        assert_coordinate(back, coordinate.x, coordinate.y);
+        let both = second.apply(&first.apply(&coordinate));
+        let combined = first.then(&second).apply(&coordinate);
+        assert_coordinate(combined, both.x, both.y);
+    }
+
+    #[test]
+    fn reflection() {
+        let mirror = Transform::reflection(
+            &Coordinate { x: 2.0, y: 0.0 },
+            &Coordinate { x: 4.0, y: 2.0 },
+        );
+        assert!(mirror.is_mirrored());
+        assert_coordinate(mirror.apply(&Coordinate { x: 2.0, y: 2.0 }), 4.0, 0.0);
+        assert_coordinate(mirror.apply(&Coordinate { x: 3.0, y: 1.0 }), 3.0, 1.0);
+        assert_coordinate(
+            mirror.apply_ray(&LinearGraph::default()).direction,
+            0.0,
+            1.0,
+        );
+
+        let rotation = Transform::new(
+            &Coordinate { x: 1.0, y: 2.0 },
+            &Coordinate { x: 7.0, y: -3.0 },
+            0.7,
+        );
+        let coordinate = Coordinate { x: 3.0, y: 4.0 };
+        let back = mirror.inverse().apply(&mirror.apply(&coordinate));
+        assert_coordinate(back, coordinate.x, coordinate.y);
+        for (first, second) in [(&mirror, &rotation), (&rotation, &mirror)] {
+            let both = second.apply(&first.apply(&coordinate));
+            let combined = first.then(second);
+            assert!(combined.is_mirrored());
+            assert_coordinate(combined.apply(&coordinate), both.x, both.y);
+            let back = combined.inverse().apply(&both);
+            assert_coordinate(back, coordinate.x, coordinate.y);
+        }
+        // two reflections make a rotation
+        assert!(!mirror.then(&mirror).is_mirrored());
+    }
+}
@@ -1,28 +1,103 @@
-use super::Coordinate;
-use crate::map_element::{Color, Point};
+use super::{Coordinate, LinearGraph};
+use crate::map_element::Color;
+
+const COLLINEAR_EPSILON: f64 = 1e-9;
+
+// Vertical extent of the wall, the elevation is the height of the wall bottom
+// above the floor. None height means the wall height passed to the engine.
+#[derive(PartialEq, Default, Clone, Copy, Debug)]
+pub struct WallHeight {
+    pub height: Option<f64>,
+    pub elevation: f64,
+}
+
+impl WallHeight {
+    pub fn new(height: f64, elevation: f64) -> Self {
+        Self {
+            height: Some(height),
+            elevation,
+        }
+    }
+
+    pub fn top(&self, wall_height: f64) -> f64 {
+        self.elevation + self.height.unwrap_or(wall_height)
+    }
+}
 
 #[derive(PartialEq, Default, Clone, Debug)]
 pub struct Wall {
-    pub start_point: Point,
-    pub end_point: Point,
+    pub start_point: Coordinate,
+    pub end_point: Coordinate,
     pub primary_object_color: Color,
+    pub height: WallHeight,
+}
+
+// checks if the point lies on the line which goes through start and end
+fn is_on_line(start: &Coordinate, end: &Coordinate, point: &Coordinate) -> bool {
+    let cross = (end.x - start.x) * (point.y - start.y) - (end.y - start.y) * (point.x - start.x);
+    cross.abs() <= COLLINEAR_EPSILON * start.distance(end) * start.distance(point)
+}
+
+// checks if the point lies between start and end, point has to be on the line
+fn is_between(start: &Coordinate, end: &Coordinate, point: &Coordinate) -> bool {
+    start.x.min(end.x) <= point.x
+        && point.x <= start.x.max(end.x)
+        && start.y.min(end.y) <= point.y
+        && point.y <= start.y.max(end.y)
 }
 
 impl Wall {
-    pub fn new(start_point: Point, end_point: Point, primary_object_color: Color) -> Self {
+    pub fn new(
+        start_point: Coordinate,
+        end_point: Coordinate,
+        primary_object_color: Color,
+    ) -> Self {
         Self {
             start_point,
             end_point,
             primary_object_color,
+            height: WallHeight::default(),
         }
     }
 
     pub fn point_distance_start(&self, coordinate: &Coordinate) -> f64 {
-        self.start_point.distance_coor(&coordinate)
+        self.start_point.distance(coordinate)
     }
 
     pub fn point_distance_end(&self, coordinate: &Coordinate) -> f64 {
-        self.end_point.distance_coor(&coordinate)
+        self.end_point.distance(coordinate)
+    }
+
+    // Returns the point where the ray from the position crosses the line of the
+    // wall, or the wall start if the ray is parallel to the wall.
+    pub fn cross_point(&self, position: &Coordinate, ray: &LinearGraph) -> Coordinate {
+        let wall_x = self.end_point.x - self.start_point.x;
+        let wall_y = self.end_point.y - self.start_point.y;
+        let denominator = ray.direction.x * wall_y - ray.direction.y * wall_x;
+        if denominator == 0.0 {
+            return self.start_point.clone();
+        }
+        let distance = ((self.start_point.x - position.x) * wall_y
+            - (self.start_point.y - position.y) * wall_x)
+            / denominator;
+        ray.get_next_from_distance(position, distance)
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.primary_object_color.is_transparent()
+    }
+
+    // Checks if the front side of the wall is seen from the position. Walls
+    // are oriented so their start is on the right side of the end.
+    pub fn is_facing(&self, position: &Coordinate) -> bool {
+        (self.start_point.x - position.x) * (self.end_point.y - position.y)
+            - (self.start_point.y - position.y) * (self.end_point.x - position.x)
+            >= 0.0
+    }
+
+    fn is_collinear(&self, wall: &Wall) -> bool {
+        is_on_line(&self.start_point, &self.end_point, &wall.start_point)
+            && is_on_line(&self.start_point, &self.end_point, &wall.end_point)
     }
 }
 
@@ -47,21 +122,24 @@ impl Walls {
     }
 
     pub fn try_extend_last_wall(&mut self, wall: Wall) {
+        if wall.start_point == wall.end_point {
+            return self.join_hit(wall);
+        }
         if let Some(last_wall) = self.0.last_mut() {
             if last_wall.start_point == wall.start_point {
                 return;
             }
             if last_wall.end_point == wall.start_point
                 && last_wall.primary_object_color == wall.primary_object_color
+                && last_wall.height == wall.height
+                && is_on_line(
+                    &last_wall.start_point,
+                    &last_wall.end_point,
+                    &wall.end_point,
+                )
             {
-                if (last_wall.start_point.x == last_wall.end_point.x
-                    && last_wall.end_point.x == wall.end_point.x)
-                    || (last_wall.start_point.y == last_wall.end_point.y
-                        && last_wall.end_point.y == wall.end_point.y)
-                {
-                    last_wall.end_point = wall.end_point;
-                    return;
-                }
+                last_wall.end_point = wall.end_point;
+                return;
             }
             self.0.push(wall);
         } else {
@@ -69,38 +147,41 @@ impl Walls {
         }
     }
 
+    // Hit seen in the mirror has no length, it's joined with the last wall of
+    // the same color and height.
+    fn join_hit(&mut self, hit: Wall) {
+        match self.0.last_mut() {
+            Some(last_wall)
+                if last_wall.primary_object_color == hit.primary_object_color
+                    && last_wall.height == hit.height =>
+            {
+                if is_on_line(&last_wall.start_point, &last_wall.end_point, &hit.end_point) {
+                    last_wall.end_point = hit.end_point;
+                } else {
+                    let start_point = last_wall.end_point.clone();
+                    self.0.push(Wall { start_point, ..hit });
+                }
+            }
+            _ => self.0.push(hit),
+        }
+    }
+
     pub fn merge(&mut self, mut walls_to_merge: Walls) {
         if let Some(wall) = self.0.last_mut() {
             if walls_to_merge.0.len() > 0 {
                 let wall_to_merge = walls_to_merge.0.remove(0);
-                if wall.primary_object_color != wall_to_merge.primary_object_color {
-                    self.0.push(wall_to_merge);
-                } else if wall.start_point.x == wall.end_point.x
-                    && wall.end_point.x == wall_to_merge.start_point.x
-                    && wall_to_merge.start_point.x == wall_to_merge.end_point.x
+                if wall.primary_object_color != wall_to_merge.primary_object_color
+                    || wall.height != wall_to_merge.height
                 {
-                    if wall_to_merge.start_point.y <= wall.end_point.y
-                        && wall.end_point.y <= wall_to_merge.end_point.y
-                    {
-                        wall.end_point = wall_to_merge.end_point;
-                    } else if wall_to_merge.end_point.y <= wall.end_point.y
-                        && wall.end_point.y <= wall_to_merge.start_point.y
-                    {
-                        wall.end_point = wall_to_merge.end_point;
-                    }
-                } else if wall.start_point.y == wall.end_point.y
-                    && wall.end_point.y == wall_to_merge.start_point.y
-                    && wall_to_merge.start_point.y == wall_to_merge.end_point.y
+                    self.0.push(wall_to_merge);
+                } else if wall.is_collinear(&wall_to_merge)
+                    && is_between(
+                        &wall_to_merge.start_point,
+                        &wall_to_merge.end_point,
+                        &wall.end_point,
+                    )
                 {
-                    if wall_to_merge.start_point.x <= wall.end_point.x
-                        && wall.end_point.x <= wall_to_merge.end_point.x
-                    {
-                        wall.end_point = wall_to_merge.end_point;
-                    } else if wall_to_merge.end_point.x <= wall.end_point.x
-                        && wall.end_point.x <= wall_to_merge.start_point.x
-                    {
-                        wall.end_point = wall_to_merge.end_point;
-                    }
+                    wall.end_point = wall_to_merge.end_point;
                 } else {
                     self.0.push(wall_to_merge);
                 }
@@ -109,3 +190,134 @@ impl Walls {
         self.0.append(&mut walls_to_merge.0);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wall(start: (f64, f64), end: (f64, f64)) -> Wall {
+        Wall::new(
+            Coordinate {
+                x: start.0,
+                y: start.1,
+            },
+            Coordinate { x: end.0, y: end.1 },
+            Color::Orange,
+        )
+    }
+
+    #[test]
+    fn cross_point() {
+        let position = Coordinate { x: 1.0, y: 1.0 };
+        let ray = LinearGraph::from_direction(&Coordinate { x: 1.0, y: 1.0 });
+        assert_eq!(
+            wall((4.0, 0.0), (4.0, 6.0)).cross_point(&position, &ray),
+            Coordinate { x: 4.0, y: 4.0 }
+        );
+        assert_eq!(
+            wall((3.0, 0.0), (0.0, 3.0)).cross_point(&position, &ray),
+            Coordinate { x: 1.5, y: 1.5 }
+        );
+        // ray parallel to the wall
+        assert_eq!(
+            wall((2.0, 1.0), (4.0, 3.0)).cross_point(&position, &ray),
+            Coordinate { x: 2.0, y: 1.0 }
+        );
+    }
+
+    #[test]
+    fn is_facing() {
+        let position = Coordinate { x: 1.0, y: 1.0 };
+        assert!(wall((4.0, 0.0), (4.0, 6.0)).is_facing(&position));
+        assert!(!wall((4.0, 6.0), (4.0, 0.0)).is_facing(&position));
+        assert!(wall((0.0, 3.0), (3.0, 3.0)).is_facing(&Coordinate { x: 1.0, y: 4.0 }));
+    }
+
+    #[test]
+    fn try_extend_last_wall_hits() {
+        let hit = |x: f64, y: f64| wall((x, y), (x, y));
+        let mut walls = Walls(vec![]);
+        walls.try_extend_last_wall(hit(4.0, 1.0));
+        walls.try_extend_last_wall(hit(4.0, 2.0));
+        walls.try_extend_last_wall(hit(4.0, 3.0));
+        assert_eq!(walls, Walls(vec![wall((4.0, 1.0), (4.0, 3.0))]));
+
+        // hit which isn't on the line starts the next wall
+        walls.try_extend_last_wall(hit(3.0, 4.0));
+        assert_eq!(
+            walls,
+            Walls(vec![
+                wall((4.0, 1.0), (4.0, 3.0)),
+                wall((4.0, 3.0), (3.0, 4.0))
+            ])
+        );
+
+        // hit of the other color isn't joined
+        let mut other = hit(2.0, 5.0);
+        other.primary_object_color = Color::Blue;
+        walls.try_extend_last_wall(other.clone());
+        assert_eq!(walls.0.last(), Some(&other));
+    }
+
+    #[test]
+    fn try_extend_last_wall_diagonal() {
+        let mut walls = Walls(vec![wall((4.0, 1.0), (3.0, 2.0))]);
+        walls.try_extend_last_wall(wall((3.0, 2.0), (1.5, 3.5)));
+        assert_eq!(walls, Walls(vec![wall((4.0, 1.0), (1.5, 3.5))]));
+
+        walls.try_extend_last_wall(wall((1.5, 3.5), (1.5, 4.5)));
+        assert_eq!(
+            walls,
+            Walls(vec![
+                wall((4.0, 1.0), (1.5, 3.5)),
+                wall((1.5, 3.5), (1.5, 4.5))
+            ])
+        );
+    }
+
+    #[test]
+    fn try_extend_last_wall_height() {
+        let mut lower_wall = wall((3.0, 2.0), (1.5, 3.5));
+        lower_wall.height = WallHeight::new(0.5, 0.0);
+        let mut walls = Walls(vec![wall((4.0, 1.0), (3.0, 2.0))]);
+        walls.try_extend_last_wall(lower_wall.clone());
+        assert_eq!(
+            walls,
+            Walls(vec![wall((4.0, 1.0), (3.0, 2.0)), lower_wall.clone()])
+        );
+
+        let mut walls = Walls(vec![wall((4.0, 1.0), (3.0, 2.0))]);
+        walls.merge(Walls(vec![lower_wall.clone()]));
+        assert_eq!(walls, Walls(vec![wall((4.0, 1.0), (3.0, 2.0)), lower_wall]));
+    }
+
+    #[test]
+    fn merge_diagonal() {
+        let mut walls = Walls(vec![wall((4.0, 1.0), (3.0, 2.0))]);
+        walls.merge(Walls(vec![
+            wall((2.5, 2.5), (1.0, 4.0)),
+            wall((1.0, 4.0), (1.0, 5.0)),
+        ]));
+        assert_eq!(
+            walls,
+            Walls(vec![
+                wall((4.0, 1.0), (3.0, 2.0)),
+                wall((2.5, 2.5), (1.0, 4.0)),
+                wall((1.0, 4.0), (1.0, 5.0))
+            ])
+        );
+
+        let mut walls = Walls(vec![wall((4.0, 1.0), (3.0, 2.0))]);
+        walls.merge(Walls(vec![
+            wall((3.5, 1.5), (1.0, 4.0)),
+            wall((1.0, 4.0), (1.0, 5.0)),
+        ]));
+        assert_eq!(
+            walls,
+            Walls(vec![
+                wall((4.0, 1.0), (1.0, 4.0)),
+                wall((1.0, 4.0), (1.0, 5.0))
+            ])
+        );
+    }
+}
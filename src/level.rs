@@ -0,0 +1,558 @@
+use crate::graph::Coordinate;
+use crate::map_element::{
+    Color, DoorArea, DoorType, DoorVelocity, MapElement, Point, Rectangle, WallMap,
+};
+use crate::player_utils::{Angle, Radians};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+cfg_if::cfg_if! {
+    if #[cfg(not(test))] {
+        use crate::map_element::{Door, MapElements};
+        use crate::player_utils::Player;
+    }
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(std::io::Error),
+    Image(image::ImageError),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelError::Io(error) => write!(f, "{}", error),
+            LevelError::Image(error) => write!(f, "{}", error),
+            LevelError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<std::io::Error> for LevelError {
+    fn from(error: std::io::Error) -> Self {
+        LevelError::Io(error)
+    }
+}
+
+impl From<image::ImageError> for LevelError {
+    fn from(error: image::ImageError) -> Self {
+        LevelError::Image(error)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct LevelDoor {
+    pub door_area: DoorArea,
+    pub velocity: DoorVelocity,
+    pub color: Option<Color>,
+}
+
+// Level made of the WallMap image and the text description which contains
+// the image file name, spawn position, player angle and doors, e.g.
+//
+// image level.png
+// wall_color orange
+// height_map 8
+// spawn 10.5 20.5
+// angle 2.356 3.927
+// door 55 43 76 45 horizontal fast open 50 40 80 48 color 0.1,0.2,0.3,1
+//
+// height_map uses the image luminance as the wall height (see WallMap::set_height_map),
+// it's optional as open area and color of the door.
+#[derive(Clone)]
+pub struct Level {
+    pub wall_map: WallMap,
+    pub spawn: Coordinate,
+    pub angle: Angle,
+    pub doors: Vec<LevelDoor>,
+}
+
+#[derive(PartialEq, Debug)]
+struct Description {
+    image: String,
+    wall_color: Option<Color>,
+    height_map: Option<f64>,
+    spawn: Coordinate,
+    angle: Angle,
+    doors: Vec<LevelDoor>,
+}
+
+impl Level {
+    pub fn new(wall_map: WallMap, spawn: Coordinate, angle: Angle) -> Self {
+        Self {
+            wall_map,
+            spawn,
+            angle,
+            doors: vec![],
+        }
+    }
+
+    // Image path in the description is relative to the description file.
+    pub fn load(path: &Path) -> Result<Self, LevelError> {
+        let description = parse_description(&fs::read_to_string(path)?)?;
+        let image_path = path
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(&description.image);
+        let mut wall_map = WallMap::new(&image_path, description.wall_color)?;
+        wall_map.set_height_map(description.height_map);
+        Ok(Self {
+            wall_map,
+            spawn: description.spawn,
+            angle: description.angle,
+            doors: description.doors,
+        })
+    }
+
+    // Saves the description to the path and the image next to it, the image
+    // has the same file name with png extension. Path with png extension is
+    // rejected, the description would overwrite the image.
+    pub fn save(&self, path: &Path) -> Result<(), LevelError> {
+        let invalid_input = |message| {
+            LevelError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                message,
+            ))
+        };
+        if matches!(path.extension(), Some(extension) if extension.eq_ignore_ascii_case("png")) {
+            return Err(invalid_input("level file can't have png extension"));
+        }
+        let image_path = path.with_extension("png");
+        let image = image_path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .ok_or_else(|| invalid_input("invalid level file name"))?;
+        let description = Description {
+            image: String::from(image),
+            wall_color: Some(self.wall_map.color()),
+            height_map: self.wall_map.height_map(),
+            spawn: self.spawn.clone(),
+            angle: self.angle.clone(),
+            doors: self.doors.clone(),
+        };
+        self.wall_map.save(&image_path)?;
+        fs::write(path, format_description(&description))?;
+        Ok(())
+    }
+
+    #[cfg(not(tarpaulin_include))]
+    #[cfg(not(test))]
+    pub fn player(&self, number_of_rays: usize) -> Player {
+        Player::new(self.angle.clone(), self.spawn.clone(), number_of_rays)
+    }
+
+    // Doors are named "door 0", "door 1", ... and the WallMap is named "walls".
+    #[cfg(not(tarpaulin_include))]
+    #[cfg(not(test))]
+    pub fn map_elements(&self) -> MapElements {
+        let mut map_elements = MapElements::new();
+        for (index, door) in self.doors.iter().enumerate() {
+            map_elements.insert_named(
+                &format!("door {}", index),
+                Box::new(Door::new(
+                    door.door_area.area.clone(),
+                    door.velocity.clone(),
+                    door.door_area.door_type.clone(),
+                    door.door_area.open_area.clone(),
+                    door.color.clone(),
+                )),
+            );
+        }
+        map_elements.insert_named("walls", Box::new(self.wall_map.clone()));
+        map_elements
+    }
+}
+
+fn color_to_string(color: &Color) -> String {
+    match color {
+        Color::Red => String::from("red"),
+        Color::Green => String::from("green"),
+        Color::Blue => String::from("blue"),
+        Color::Yellow => String::from("yellow"),
+        Color::Orange => String::from("orange"),
+        Color::Pink => String::from("pink"),
+        Color::Custom([r, g, b, a]) => format!("{},{},{},{}", r, g, b, a),
+    }
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    match value {
+        "red" => Some(Color::Red),
+        "green" => Some(Color::Green),
+        "blue" => Some(Color::Blue),
+        "yellow" => Some(Color::Yellow),
+        "orange" => Some(Color::Orange),
+        "pink" => Some(Color::Pink),
+        _ => {
+            let values = value
+                .split(',')
+                .map(|value| value.parse().ok())
+                .collect::<Option<Vec<f32>>>()?;
+            if values.len() != 4 {
+                return None;
+            }
+            Some(Color::Custom([values[0], values[1], values[2], values[3]]))
+        }
+    }
+}
+
+fn velocity_to_str(velocity: &DoorVelocity) -> &'static str {
+    match velocity {
+        DoorVelocity::VerySlow => "very_slow",
+        DoorVelocity::Slow => "slow",
+        DoorVelocity::Fast => "fast",
+        DoorVelocity::VeryFast => "very_fast",
+    }
+}
+
+fn parse_velocity(value: &str) -> Option<DoorVelocity> {
+    match value {
+        "very_slow" => Some(DoorVelocity::VerySlow),
+        "slow" => Some(DoorVelocity::Slow),
+        "fast" => Some(DoorVelocity::Fast),
+        "very_fast" => Some(DoorVelocity::VeryFast),
+        _ => None,
+    }
+}
+
+fn rectangle_to_string(rectangle: &Rectangle) -> String {
+    format!(
+        "{} {} {} {}",
+        rectangle.point_a.x, rectangle.point_a.y, rectangle.point_b.x, rectangle.point_b.y
+    )
+}
+
+fn format_description(description: &Description) -> String {
+    let mut text = format!("image {}\n", description.image);
+    if let Some(color) = &description.wall_color {
+        text += &format!("wall_color {}\n", color_to_string(color));
+    }
+    if let Some(max_height) = description.height_map {
+        text += &format!("height_map {}\n", max_height);
+    }
+    text += &format!("spawn {} {}\n", description.spawn.x, description.spawn.y);
+    text += &format!(
+        "angle {} {}\n",
+        description.angle.start.to_f64(),
+        description.angle.end.to_f64()
+    );
+    for door in &description.doors {
+        text += &format!(
+            "door {} {} {}",
+            rectangle_to_string(&door.door_area.area),
+            if door.door_area.door_type == DoorType::Vertical {
+                "vertical"
+            } else {
+                "horizontal"
+            },
+            velocity_to_str(&door.velocity)
+        );
+        if let Some(open_area) = &door.door_area.open_area {
+            text += &format!(" open {}", rectangle_to_string(open_area));
+        }
+        if let Some(color) = &door.color {
+            text += &format!(" color {}", color_to_string(color));
+        }
+        text += "\n";
+    }
+    text
+}
+
+struct Tokens<'a> {
+    tokens: std::str::SplitWhitespace<'a>,
+    line: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn error(&self, message: &str) -> LevelError {
+        LevelError::Parse {
+            line: self.line,
+            message: String::from(message),
+        }
+    }
+
+    fn next(&mut self, name: &str) -> Result<&'a str, LevelError> {
+        let line = self.line;
+        self.tokens.next().ok_or_else(|| LevelError::Parse {
+            line,
+            message: format!("missing {}", name),
+        })
+    }
+
+    fn parse<T: std::str::FromStr>(&mut self, name: &str) -> Result<T, LevelError> {
+        let value = self.next(name)?;
+        value
+            .parse()
+            .map_err(|_| self.error(&format!("invalid {}: {}", name, value)))
+    }
+
+    fn rectangle(&mut self) -> Result<Rectangle, LevelError> {
+        Ok(Rectangle {
+            point_a: Point {
+                x: self.parse("x")?,
+                y: self.parse("y")?,
+            },
+            point_b: Point {
+                x: self.parse("x")?,
+                y: self.parse("y")?,
+            },
+        })
+    }
+
+    fn color(&mut self) -> Result<Color, LevelError> {
+        let value = self.next("color")?;
+        parse_color(value).ok_or_else(|| self.error(&format!("invalid color: {}", value)))
+    }
+
+    fn door(&mut self) -> Result<LevelDoor, LevelError> {
+        let area = self.rectangle()?;
+        let door_type = match self.next("door type")? {
+            "vertical" => DoorType::Vertical,
+            "horizontal" => DoorType::Horizontal,
+            value => return Err(self.error(&format!("invalid door type: {}", value))),
+        };
+        let value = self.next("door velocity")?;
+        let velocity = parse_velocity(value)
+            .ok_or_else(|| self.error(&format!("invalid door velocity: {}", value)))?;
+        let mut door = LevelDoor {
+            door_area: DoorArea {
+                area,
+                door_type,
+                open_area: None,
+            },
+            velocity,
+            color: None,
+        };
+        while let Some(key) = self.tokens.next() {
+            match key {
+                "open" => door.door_area.open_area = Some(self.rectangle()?),
+                "color" => door.color = Some(self.color()?),
+                _ => return Err(self.error(&format!("unknown door option: {}", key))),
+            }
+        }
+        Ok(door)
+    }
+}
+
+fn parse_description(text: &str) -> Result<Description, LevelError> {
+    let mut image = None;
+    let mut wall_color = None;
+    let mut height_map = None;
+    let mut spawn = None;
+    let mut angle = None;
+    let mut doors = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut tokens = Tokens {
+            tokens: line.split_whitespace(),
+            line: index + 1,
+        };
+        match tokens.next("key")? {
+            "image" => image = Some(String::from(line["image".len()..].trim())),
+            "wall_color" => wall_color = Some(tokens.color()?),
+            "height_map" => height_map = Some(tokens.parse("max height")?),
+            "spawn" => {
+                spawn = Some(Coordinate {
+                    x: tokens.parse("x")?,
+                    y: tokens.parse("y")?,
+                })
+            }
+            "angle" => {
+                angle = Some(Angle {
+                    start: Radians::new(tokens.parse("angle start")?),
+                    end: Radians::new(tokens.parse("angle end")?),
+                })
+            }
+            "door" => doors.push(tokens.door()?),
+            key => return Err(tokens.error(&format!("unknown key: {}", key))),
+        }
+    }
+    let missing = |name: &str| LevelError::Parse {
+        line: text.lines().count(),
+        message: format!("missing {}", name),
+    };
+    Ok(Description {
+        image: image.ok_or_else(|| missing("image"))?,
+        wall_color,
+        height_map,
+        spawn: spawn.ok_or_else(|| missing("spawn"))?,
+        angle: angle.ok_or_else(|| missing("angle"))?,
+        doors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn description() -> Description {
+        Description {
+            image: String::from("level.png"),
+            wall_color: Some(Color::Green),
+            height_map: Some(8.5),
+            spawn: Coordinate { x: 10.5, y: 20.25 },
+            angle: Angle {
+                start: Radians::new(0.5),
+                end: Radians::new(1.75),
+            },
+            doors: vec![
+                LevelDoor {
+                    door_area: DoorArea {
+                        area: Rectangle {
+                            point_a: Point { x: 55, y: 43 },
+                            point_b: Point { x: 76, y: 45 },
+                        },
+                        door_type: DoorType::Horizontal,
+                        open_area: None,
+                    },
+                    velocity: DoorVelocity::Fast,
+                    color: None,
+                },
+                LevelDoor {
+                    door_area: DoorArea {
+                        area: Rectangle {
+                            point_a: Point { x: 10, y: 20 },
+                            point_b: Point { x: 12, y: 30 },
+                        },
+                        door_type: DoorType::Vertical,
+                        open_area: Some(Rectangle {
+                            point_a: Point { x: 5, y: 18 },
+                            point_b: Point { x: 17, y: 32 },
+                        }),
+                    },
+                    velocity: DoorVelocity::VerySlow,
+                    color: Some(Color::Custom([0.1, 0.25, 0.5, 1.0])),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn format_parse_description() {
+        let text = format_description(&description());
+        assert_eq!(
+            text,
+            "image level.png\n\
+             wall_color green\n\
+             height_map 8.5\n\
+             spawn 10.5 20.25\n\
+             angle 0.5 1.75\n\
+             door 55 43 76 45 horizontal fast\n\
+             door 10 20 12 30 vertical very_slow open 5 18 17 32 color 0.1,0.25,0.5,1\n"
+        );
+        assert_eq!(parse_description(&text).unwrap(), description());
+    }
+
+    #[test]
+    fn parse_description_comments_and_defaults() {
+        let text = "# level\n\nimage my level.png\nspawn 1 2\nangle 3 4\n";
+        let description = parse_description(text).unwrap();
+        assert_eq!(description.image, "my level.png");
+        assert_eq!(description.wall_color, None);
+        assert_eq!(description.height_map, None);
+        assert_eq!(description.spawn, Coordinate { x: 1.0, y: 2.0 });
+        assert_eq!(description.doors, vec![]);
+    }
+
+    #[test]
+    fn parse_description_errors() {
+        let error_message = |text: &str| match parse_description(text) {
+            Err(LevelError::Parse { line, message }) => format!("{}: {}", line, message),
+            _ => panic!("parse_description should fail for {:?}", text),
+        };
+        assert_eq!(error_message("image a.png\nspawn 1\n"), "2: missing y");
+        assert_eq!(error_message("image a.png\nspawn 1 x\n"), "2: invalid y: x");
+        assert_eq!(
+            error_message("image a.png\nfloor 1\n"),
+            "2: unknown key: floor"
+        );
+        assert_eq!(
+            error_message("image a.png\ndoor 1 2 3 4 diagonal fast\n"),
+            "2: invalid door type: diagonal"
+        );
+        assert_eq!(
+            error_message("image a.png\ndoor 1 2 3 4 vertical slower\n"),
+            "2: invalid door velocity: slower"
+        );
+        assert_eq!(
+            error_message("image a.png\ndoor 1 2 3 4 vertical slow color 1,2\n"),
+            "2: invalid color: 1,2"
+        );
+        assert_eq!(
+            error_message("image a.png\ndoor 1 2 3 4 vertical slow opened\n"),
+            "2: unknown door option: opened"
+        );
+        assert_eq!(
+            error_message("image a.png\nangle 1 2\n"),
+            "2: missing spawn"
+        );
+    }
+
+    #[test]
+    fn save_load() {
+        let directory =
+            std::env::temp_dir().join(format!("level_save_load_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("level.txt");
+
+        let mut image = image::RgbaImage::from_pixel(8, 6, image::Rgba([255, 255, 255, 255]));
+        image.put_pixel(3, 2, image::Rgba([0, 0, 0, 255]));
+        let description = description();
+        let mut level = Level::new(
+            WallMap::from_image(image, Some(Color::Pink)),
+            description.spawn.clone(),
+            description.angle.clone(),
+        );
+        level.doors = description.doors.clone();
+        level.save(&path).unwrap();
+
+        let loaded = Level::load(&path).unwrap();
+        assert_eq!(loaded.spawn, level.spawn);
+        assert_eq!(loaded.angle, level.angle);
+        assert_eq!(loaded.doors, level.doors);
+        assert_eq!(loaded.wall_map.color(), Color::Pink);
+        assert!(loaded.wall_map.is_black_pixel(3, 2));
+        assert!(!loaded.wall_map.is_black_pixel(2, 3));
+        assert!(directory.join("level.png").exists());
+
+        fs::remove_dir_all(&directory).unwrap();
+
+        match Level::load(&path) {
+            Err(LevelError::Io(error)) => assert_eq!(error.kind(), std::io::ErrorKind::NotFound),
+            _ => panic!("loading removed level should fail"),
+        }
+    }
+
+    #[test]
+    fn save_png_path() {
+        let directory = std::env::temp_dir().join(format!("level_save_png_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("level.png");
+
+        let level = Level::new(
+            WallMap::from_image(
+                image::RgbaImage::from_pixel(4, 4, image::Rgba([255, 255, 255, 255])),
+                None,
+            ),
+            Coordinate { x: 1.5, y: 1.5 },
+            Angle::default(),
+        );
+        match level.save(&path) {
+            Err(LevelError::Io(error)) => {
+                assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput)
+            }
+            _ => panic!("saving level to the png path should fail"),
+        }
+        assert!(!path.exists());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
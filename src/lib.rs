@@ -1,14 +1,21 @@
 mod engine;
 mod generator;
 mod graph;
+pub mod level;
 mod map;
 pub mod map_element;
+pub mod map_generator;
 mod player_utils;
 mod render_thread;
+pub mod validator;
 mod wrapper;
 
 pub use engine::Engine;
-pub use graph::Coordinate;
+pub use graph::{Coordinate, Crossing, Transform, WallHeight};
 pub use map::Map;
 pub use piston::window::Size;
-pub use player_utils::{Angle, Player, Radians};
+pub use player_utils::{
+    Angle, Axis, CameraEffects, CameraOffset, InputSettings, Movement, Player, PlayerHandle,
+    Radians, SpeedMultiplierId,
+};
+pub use render_thread::RenderMode;
@@ -1,17 +1,38 @@
-use crate::graph::{Coordinate, LinearGraph, Wall};
-use crate::map_element::MapElement;
-use mockall_double::double;
+use crate::graph::{Coordinate, Crossing, GridTraversal, LinearGraph, Transform, Wall};
+use crate::map_element::{Color, MapElements};
 
 #[cfg(test)]
 use mockall::automock;
 
-#[double]
-use crate::graph::GraphMethods;
-
 #[derive(Default, Clone)]
 pub struct Map {
     pub width: i64,
     pub height: i64,
+    // rays are not cast farther than this, None casts rays to the border of the map
+    pub max_ray_distance: Option<f64>,
+}
+
+// Walls lower than min_top are see-through while the rays of the layer are cast,
+// layers are drawn from the highest, so taller walls are visible behind lower ones.
+// Transparent walls are skipped by the opaque layers and drawn by the transparent
+// layers afterwards, from the farthest one.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) struct Layer {
+    pub min_top: f64,
+    // used for the walls without their own height
+    pub wall_height: f64,
+    // amount of the transparent walls in front of the wall found by the layer,
+    // None for the opaque layers
+    pub transparent_depth: Option<usize>,
+}
+
+impl Layer {
+    pub fn includes(&self, wall: &Wall) -> bool {
+        match self.transparent_depth {
+            None => !wall.is_transparent() && wall.height.top(self.wall_height) >= self.min_top,
+            Some(_) => true,
+        }
+    }
 }
 
 #[cfg_attr(test, automock)]
@@ -30,38 +51,162 @@ impl Map {
     fn get_wall(
         &self,
         position: &Coordinate, // has to return coordinates sorted in clockwise order
-        map_elements: &Vec<Box<dyn MapElement>>,
+        map_elements: &MapElements,
         start_position: &Coordinate,
+        layer: &Layer,
     ) -> Option<(Wall, LinearGraph)> {
-        for map_element in map_elements {
+        for map_element in map_elements.elements_at(position) {
             let wall = map_element.is_coordinate_in_object(position, start_position);
-            if wall != None {
+            if matches!(&wall, Some((wall, _)) if layer.includes(wall)) {
                 return wall;
             }
         }
         return None;
     }
 
+    // Ray which crosses a portal or a mirror continues from the place given by
+    // the crossing. Walls found there are moved back, so they are seen through
+    // the portal or in the mirror.
     pub(crate) fn cast_ray(
         &self,
         position: &Coordinate,
         ray: &LinearGraph,
-        map_elements: &Vec<Box<dyn MapElement>>,
+        map_elements: &MapElements,
+        layer: &Layer,
     ) -> Option<(Wall, LinearGraph)> {
-        let start_position = position;
-        let mut last_position = position.clone();
-        let mut next_position: Coordinate;
+        let mut transparent_walls = 0;
+        let mut path = RayPath::new(position, ray);
         loop {
-            next_position = GraphMethods::get_next(&ray, &last_position);
-            if !self.validate_coordinate(&next_position) {
-                return None;
-            }
-            let wall = self.get_wall(&next_position, map_elements, &start_position);
-            if wall != None {
-                return wall;
+            let mut previous = path.origin.clone();
+            let mut crossing = None;
+            for (next_position, distance) in GridTraversal::new(&path.ray, &path.origin) {
+                if !self.validate_coordinate(&next_position)
+                    || matches!(self.max_ray_distance, Some(max_ray_distance)
+                        if path.distance + distance > max_ray_distance)
+                {
+                    return None;
+                }
+                crossing = map_elements
+                    .portal_crossing(&previous, &next_position)
+                    .filter(|crossing| path.passes < crossing.max_passes);
+                if crossing.is_some() {
+                    break;
+                }
+                let wall = self
+                    .get_wall(&next_position, map_elements, &path.origin, layer)
+                    .map(|hit| path.seen_through(hit, ray));
+                let Some(transparent_depth) = layer.transparent_depth else {
+                    if wall != None {
+                        return wall;
+                    }
+                    previous = next_position;
+                    continue;
+                };
+                match &wall {
+                    // transparent layer ends at the opaque wall
+                    Some((found, _)) if !found.is_transparent() => return None,
+                    // back side of the transparent wall isn't drawn, the same
+                    // glass would be drawn twice
+                    Some((found, _)) if found.is_facing(position) => {
+                        if transparent_walls == transparent_depth {
+                            return wall;
+                        }
+                        transparent_walls += 1;
+                    }
+                    _ => {}
+                }
+                previous = next_position;
             }
-            last_position = next_position;
+            path.pass(crossing?);
+        }
+    }
+}
+
+// Ray moved through the portals and mirrors. Walls are searched from the
+// origin of the last part of the ray, so walls between the position moved
+// through the crossings and the exit of the crossing aren't found.
+struct RayPath {
+    position: Coordinate,
+    origin: Coordinate,
+    ray: LinearGraph,
+    // moves the position through all crossings, None before the first crossing
+    transform: Option<Transform>,
+    tints: Vec<Color>,
+    distance: f64,
+    passes: usize,
+}
+
+impl RayPath {
+    fn new(position: &Coordinate, ray: &LinearGraph) -> Self {
+        Self {
+            position: position.clone(),
+            origin: position.clone(),
+            ray: ray.clone(),
+            transform: None,
+            tints: vec![],
+            distance: 0.0,
+            passes: 0,
+        }
+    }
+
+    fn pass(&mut self, crossing: Crossing) {
+        self.distance += self.origin.distance(&crossing.point);
+        self.origin = crossing.transform.apply(&crossing.point);
+        self.ray = crossing.transform.apply_ray(&self.ray);
+        self.transform = Some(match &self.transform {
+            Some(transform) => transform.then(&crossing.transform),
+            None => crossing.transform,
+        });
+        self.tints.extend(crossing.tint);
+        self.passes += 1;
+    }
+
+    // moves the wall found at the end of the path back in front of the position
+    fn seen_through(
+        &self,
+        (wall, wall_ray): (Wall, LinearGraph),
+        ray: &LinearGraph,
+    ) -> (Wall, LinearGraph) {
+        let Some(transform) = &self.transform else {
+            return (wall, wall_ray);
+        };
+        let back = transform.inverse();
+        // the farthest tint is mixed first
+        let color = self
+            .tints
+            .iter()
+            .rev()
+            .fold(wall.primary_object_color.clone(), |color, tint| {
+                color.tint(tint)
+            });
+        // Wall seen in the mirror is reversed, so only the hit point is returned,
+        // hits of the following rays are joined into the walls.
+        if transform.is_mirrored() {
+            let hit = back.apply(&wall.cross_point(&self.origin, &self.ray));
+            return (
+                Wall {
+                    start_point: hit.clone(),
+                    end_point: hit,
+                    primary_object_color: color,
+                    height: wall.height,
+                },
+                ray.clone(),
+            );
         }
+        // returned ray goes from the origin to a point on the line of the wall
+        let ray_end = back.apply(&wall.cross_point(&self.origin, &wall_ray));
+        (
+            Wall {
+                start_point: back.apply(&wall.start_point),
+                end_point: back.apply(&wall.end_point),
+                primary_object_color: color,
+                height: wall.height,
+            },
+            LinearGraph::from_direction(&Coordinate {
+                x: ray_end.x - self.position.x,
+                y: ray_end.y - self.position.y,
+            }),
+        )
     }
 }
 
@@ -69,10 +214,16 @@ impl Map {
 mod tests {
     #![allow(non_upper_case_globals)]
     use super::*;
-    use crate::graph::MockGraphMethods;
-    use crate::map_element::{Color, MockMapElement, Point};
+    use crate::graph::{MockGraphMethods, WallHeight};
+    use crate::map_element::{unbounded_map_element, Mirror, Portal, Segment};
     use mockall::*;
 
+    static all_walls: Layer = Layer {
+        min_top: f64::NEG_INFINITY,
+        wall_height: 1.0,
+        transparent_depth: None,
+    };
+
     #[test]
     fn cast_ray_complex() {
         let mut seq = Sequence::new();
@@ -80,44 +231,33 @@ mod tests {
         let map = Map {
             width: 50,
             height: 50,
+            max_ray_distance: None,
         };
 
         lazy_static! {
             static ref current_position: Coordinate = Coordinate { x: 30.0, y: 20.0 };
-            static ref next_position_1: Coordinate = Coordinate { x: 40.0, y: 30.5 };
-            static ref next_position_2: Coordinate = Coordinate { x: 45.0, y: 35.5 };
+            static ref next_position_1: Coordinate = Coordinate { x: 31.0, y: 20.0 };
+            static ref next_position_2: Coordinate = Coordinate { x: 32.0, y: 20.0 };
             static ref ray: LinearGraph = LinearGraph::default();
         }
         let wall = Wall {
-            start_point: Point { x: 10, y: 15 },
-            end_point: Point { x: 20, y: 25 },
+            start_point: Coordinate { x: 10.0, y: 15.0 },
+            end_point: Coordinate { x: 20.0, y: 25.0 },
             primary_object_color: Color::Blue,
+            height: Default::default(),
         };
 
-        let get_next_context = MockGraphMethods::get_next_context();
-        let mut map_element = Box::new(MockMapElement::new());
-
-        get_next_context
-            .expect()
-            .times(1)
-            .withf(|_ray, coordinate| *_ray == *ray && *coordinate == *current_position)
-            .return_const(next_position_1.clone())
-            .in_sequence(&mut seq);
+        let mut map_element = unbounded_map_element();
 
         map_element
             .expect_is_coordinate_in_object()
             .times(1)
-            .withf(|coordinate, _| *coordinate == *next_position_1)
+            .withf(|coordinate, start_position| {
+                *coordinate == *next_position_1 && *start_position == *current_position
+            })
             .return_const(None)
             .in_sequence(&mut seq);
 
-        get_next_context
-            .expect()
-            .times(1)
-            .withf(|_ray, coordinate| *_ray == *ray && *coordinate == *next_position_1)
-            .return_const(next_position_2.clone())
-            .in_sequence(&mut seq);
-
         map_element
             .expect_is_coordinate_in_object()
             .times(1)
@@ -125,15 +265,18 @@ mod tests {
             .returning(|_, _| {
                 Some((
                     Wall {
-                        start_point: Point { x: 10, y: 15 },
-                        end_point: Point { x: 20, y: 25 },
+                        start_point: Coordinate { x: 10.0, y: 15.0 },
+                        end_point: Coordinate { x: 20.0, y: 25.0 },
                         primary_object_color: Color::Blue,
+                        height: Default::default(),
                     },
                     LinearGraph::default(),
                 ))
             })
             .in_sequence(&mut seq);
-        match map.cast_ray(&current_position, &ray, &vec![map_element]) {
+        let mut map_elements = MapElements::new();
+        map_elements.insert(map_element);
+        match map.cast_ray(&current_position, &ray, &map_elements, &all_walls) {
             Some((wall_, _)) => assert_eq!(wall_, wall),
             ret_wall @ _ => panic!(
                 "wrong value cast_ray_complex expected: {:?} received: {:?}",
@@ -143,32 +286,302 @@ mod tests {
     }
 
     #[test]
-    fn cast_ray_out_of_map() {
-        let mut seq = Sequence::new();
+    fn cast_ray_layer() {
+        let map = Map {
+            width: 50,
+            height: 50,
+            max_ray_distance: None,
+        };
+        lazy_static! {
+            static ref ray: LinearGraph = LinearGraph::default();
+        }
+        let layer = Layer {
+            min_top: 1.5,
+            wall_height: 2.0,
+            transparent_depth: None,
+        };
+        let wall = |x: f64, height: WallHeight| Wall {
+            start_point: Coordinate { x, y: 20.0 },
+            end_point: Coordinate { x, y: 21.0 },
+            primary_object_color: Color::Blue,
+            height,
+        };
 
+        // lower wall is see-through, the wall behind it is found
+        let mut map_element = unbounded_map_element();
+        map_element
+            .expect_is_coordinate_in_object()
+            .times(1)
+            .withf(|coordinate, _| coordinate.x == 31.0)
+            .return_const(Some((
+                wall(31.0, WallHeight::new(1.0, 0.0)),
+                LinearGraph::default(),
+            )));
+        map_element
+            .expect_is_coordinate_in_object()
+            .times(1)
+            .withf(|coordinate, _| coordinate.x == 32.0)
+            .return_const(Some((
+                wall(32.0, WallHeight::default()),
+                LinearGraph::default(),
+            )));
+
+        let mut map_elements = MapElements::new();
+        map_elements.insert(map_element);
+        assert_eq!(
+            map.cast_ray(
+                &Coordinate { x: 30.0, y: 20.0 },
+                &ray,
+                &map_elements,
+                &layer
+            ),
+            Some((wall(32.0, WallHeight::default()), LinearGraph::default()))
+        );
+    }
+
+    #[test]
+    fn cast_ray_transparent() {
         let map = Map {
             width: 50,
             height: 50,
+            max_ray_distance: None,
         };
         lazy_static! {
             static ref ray: LinearGraph = LinearGraph::default();
         }
+        static glass: Color = Color::Custom([0.5, 0.8, 1.0, 0.3]);
+        let wall = |x: f64, is_front: bool, color: Color| Wall {
+            start_point: Coordinate {
+                x,
+                y: if is_front { 20.0 } else { 21.0 },
+            },
+            end_point: Coordinate {
+                x,
+                y: if is_front { 21.0 } else { 20.0 },
+            },
+            primary_object_color: color,
+            height: WallHeight::default(),
+        };
+        let cast_ray = |transparent_depth: Option<usize>| {
+            // front and back side of the glass, another glass and the opaque wall
+            let mut map_element = unbounded_map_element();
+            map_element
+                .expect_is_coordinate_in_object()
+                .returning(move |coordinate, _| {
+                    let found = match coordinate.x as i64 {
+                        31 => wall(31.0, true, glass.clone()),
+                        32 => wall(32.0, false, glass.clone()),
+                        33 => wall(33.0, true, glass.clone()),
+                        34 => wall(34.0, true, Color::Blue),
+                        _ => return None,
+                    };
+                    Some((found, LinearGraph::default()))
+                });
+            let mut map_elements = MapElements::new();
+            map_elements.insert(map_element);
+            map.cast_ray(
+                &Coordinate { x: 30.0, y: 20.0 },
+                &ray,
+                &map_elements,
+                &Layer {
+                    min_top: f64::NEG_INFINITY,
+                    wall_height: 1.0,
+                    transparent_depth,
+                },
+            )
+            .map(|(found, _)| found)
+        };
 
-        static current_positon: Coordinate = Coordinate { x: 30.0, y: 20.0 };
-        let next_position_out_of_map = Coordinate { x: 55.0, y: 30.0 };
+        // opaque layer sees through the glass
+        assert_eq!(cast_ray(None), Some(wall(34.0, true, Color::Blue)));
+        assert_eq!(cast_ray(Some(0)), Some(wall(31.0, true, glass.clone())));
+        // back side of the glass is skipped
+        assert_eq!(cast_ray(Some(1)), Some(wall(33.0, true, glass.clone())));
+        // there is no glass in front of the opaque wall
+        assert_eq!(cast_ray(Some(2)), None);
+    }
 
-        let get_next_context = MockGraphMethods::get_next_context();
-        let map_element = Box::new(MockMapElement::new());
+    #[test]
+    fn cast_ray_portal() {
+        let map = Map {
+            width: 50,
+            height: 50,
+            max_ray_distance: None,
+        };
+        let segment = |x: f64| Segment {
+            start: Coordinate { x, y: 19.0 },
+            end: Coordinate { x, y: 22.0 },
+        };
+        // ray comes out of the second end in the opposite direction
+        let mut map_element = unbounded_map_element();
+        map_element
+            .expect_is_coordinate_in_object()
+            .returning(|coordinate, start_position| {
+                if coordinate.x != 38.0 {
+                    return None;
+                }
+                // walls are searched from the second end of the portal
+                assert!(start_position.distance(&Coordinate { x: 40.0, y: 21.0 }) < 1e-9);
+                Some((
+                    Wall {
+                        start_point: Coordinate { x: 38.0, y: 21.0 },
+                        end_point: Coordinate { x: 38.0, y: 22.0 },
+                        primary_object_color: Color::Blue,
+                        height: WallHeight::default(),
+                    },
+                    LinearGraph::from_direction(&Coordinate { x: -2.0, y: 1.0 }),
+                ))
+            });
+        let mut map_elements = MapElements::new();
+        map_elements.insert(Box::new(Portal::new(segment(32.0), segment(40.0))));
+        map_elements.insert(map_element);
+
+        let (wall, ray) = map
+            .cast_ray(
+                &Coordinate { x: 30.0, y: 20.0 },
+                &LinearGraph::default(),
+                &map_elements,
+                &all_walls,
+            )
+            .unwrap();
+        // wall is seen through the portal as if it was in front of it
+        for (coordinate, expected) in [
+            (wall.start_point, Coordinate { x: 34.0, y: 20.0 }),
+            (wall.end_point, Coordinate { x: 34.0, y: 19.0 }),
+            (
+                ray.direction,
+                Coordinate {
+                    x: 4.0 / 17.0_f64.sqrt(),
+                    y: -1.0 / 17.0_f64.sqrt(),
+                },
+            ),
+        ] {
+            assert!(
+                coordinate.distance(&expected) < 1e-9,
+                "{:?} != {:?}",
+                coordinate,
+                expected
+            );
+        }
+    }
 
-        get_next_context
+    #[test]
+    fn cast_ray_mirror() {
+        let map = Map {
+            width: 50,
+            height: 50,
+            max_ray_distance: None,
+        };
+        let cast_ray = |max_reflections: usize| {
+            let mut mirror = Mirror::new(
+                vec![Segment {
+                    start: Coordinate { x: 33.0, y: 15.0 },
+                    end: Coordinate { x: 33.0, y: 25.0 },
+                }],
+                Some(Color::Pink),
+                Some(Color::Custom([0.0, 0.0, 0.0, 0.5])),
+            );
+            mirror.set_max_reflections(max_reflections);
+            // wall behind the player is seen in the mirror
+            let mut map_element = unbounded_map_element();
+            map_element
+                .expect_is_coordinate_in_object()
+                .returning(|coordinate, _| {
+                    (coordinate.x == 28.0).then(|| {
+                        (
+                            Wall {
+                                start_point: Coordinate { x: 28.0, y: 21.0 },
+                                end_point: Coordinate { x: 28.0, y: 19.0 },
+                                primary_object_color: Color::Custom([1.0, 1.0, 1.0, 1.0]),
+                                height: WallHeight::default(),
+                            },
+                            LinearGraph::default(),
+                        )
+                    })
+                });
+            let mut map_elements = MapElements::new();
+            map_elements.insert(Box::new(mirror));
+            map_elements.insert(map_element);
+            map.cast_ray(
+                &Coordinate { x: 30.0, y: 20.0 },
+                &LinearGraph::default(),
+                &map_elements,
+                &all_walls,
+            )
+        };
+
+        // only the reflected hit point is returned, it's tinted
+        let (wall, ray) = cast_ray(1).unwrap();
+        assert!(wall.start_point.distance(&Coordinate { x: 38.0, y: 20.0 }) < 1e-9);
+        assert_eq!(wall.start_point, wall.end_point);
+        assert_eq!(
+            wall.primary_object_color,
+            Color::Custom([0.5, 0.5, 0.5, 1.0])
+        );
+        assert_eq!(ray, LinearGraph::default());
+
+        // ray which can't be reflected any more sees the mirror
+        let from_two_coordinates_context = MockGraphMethods::from_two_coordinates_context();
+        from_two_coordinates_context
             .expect()
+            .return_const(LinearGraph::default());
+        let (wall, _) = cast_ray(0).unwrap();
+        assert_eq!(wall.start_point.x, 33.0);
+        assert_eq!(wall.primary_object_color, Color::Pink);
+    }
+
+    #[test]
+    fn cast_ray_out_of_map() {
+        let map = Map {
+            width: 50,
+            height: 50,
+            max_ray_distance: None,
+        };
+        lazy_static! {
+            static ref ray: LinearGraph = LinearGraph::default();
+        }
+
+        static current_positon: Coordinate = Coordinate { x: 48.0, y: 20.0 };
+
+        let mut map_element = unbounded_map_element();
+        map_element
+            .expect_is_coordinate_in_object()
             .times(1)
-            .withf(|_ray, coordinate| *_ray == *ray && *coordinate == current_positon)
-            .return_const(next_position_out_of_map)
-            .in_sequence(&mut seq);
+            .withf(|coordinate, _| *coordinate == Coordinate { x: 49.0, y: 20.0 })
+            .return_const(None);
+
+        let mut map_elements = MapElements::new();
+        map_elements.insert(map_element);
+        assert_eq!(
+            map.cast_ray(&current_positon, &ray, &map_elements, &all_walls),
+            None
+        );
+    }
+
+    #[test]
+    fn cast_ray_max_distance() {
+        let map = Map {
+            width: 50,
+            height: 50,
+            max_ray_distance: Some(1.5),
+        };
+        lazy_static! {
+            static ref ray: LinearGraph = LinearGraph::default();
+        }
+
+        static current_positon: Coordinate = Coordinate { x: 30.0, y: 20.0 };
+
+        let mut map_element = unbounded_map_element();
+        map_element
+            .expect_is_coordinate_in_object()
+            .times(1)
+            .withf(|coordinate, _| *coordinate == Coordinate { x: 31.0, y: 20.0 })
+            .return_const(None);
 
+        let mut map_elements = MapElements::new();
+        map_elements.insert(map_element);
         assert_eq!(
-            map.cast_ray(&current_positon, &ray, &vec![map_element]),
+            map.cast_ray(&current_positon, &ray, &map_elements, &all_walls),
             None
         );
     }
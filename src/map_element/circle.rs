@@ -0,0 +1,391 @@
+use crate::graph::{Coordinate, LinearGraph, Wall, WallHeight};
+use crate::map_element::segment_map::{nearest_wall, CELL_DIAGONAL};
+use crate::map_element::{BoundingBox, Color, MapElement, Point, Segment};
+use crate::player_utils::{Radians, PI_2};
+
+// curved walls are rendered as facets not longer than this
+const MAX_FACET_LENGTH: f64 = 0.5;
+const MAX_FACET_ANGLE: f64 = std::f64::consts::PI / 16.0;
+
+// Part of the circle. Ray intersection is computed with the circle first, then
+// with the facet that lies under the cross point.
+#[derive(Clone)]
+pub struct CircularArc {
+    center: Coordinate,
+    radius: f64,
+    start_angle: f64,
+    facet_angle: f64,
+    closed: bool,
+    facets: Vec<Segment>,
+    color: Color,
+    wall_height: WallHeight,
+}
+
+impl CircularArc {
+    // arc goes from the start to the end angle in the direction of growing angle
+    pub fn new(
+        center: Coordinate,
+        radius: f64,
+        start_angle: Radians,
+        end_angle: Radians,
+        color: Option<Color>,
+    ) -> Self {
+        Self::from_range(
+            center,
+            radius,
+            start_angle.to_f64(),
+            (end_angle - start_angle).to_f64(),
+            false,
+            color,
+        )
+    }
+
+    fn from_range(
+        center: Coordinate,
+        radius: f64,
+        start_angle: f64,
+        range: f64,
+        closed: bool,
+        color: Option<Color>,
+    ) -> Self {
+        let facets_amount = (range * radius / MAX_FACET_LENGTH)
+            .max(range / MAX_FACET_ANGLE)
+            .ceil() as usize;
+        let facet_angle = if facets_amount > 0 {
+            range / facets_amount as f64
+        } else {
+            0.0
+        };
+        let vertex = |index: usize| {
+            let angle = start_angle + facet_angle * index as f64;
+            Coordinate {
+                x: center.x + radius * angle.cos(),
+                y: center.y + radius * angle.sin(),
+            }
+        };
+        let facets = (0..facets_amount)
+            .map(|index| Segment {
+                start: vertex(index),
+                // last vertex of the closed arc is the same as the first one
+                end: if closed && index + 1 == facets_amount {
+                    vertex(0)
+                } else {
+                    vertex(index + 1)
+                },
+            })
+            .collect();
+        Self {
+            center,
+            radius,
+            start_angle,
+            facet_angle,
+            closed,
+            facets,
+            color: color.unwrap_or(Color::Orange),
+            wall_height: WallHeight::default(),
+        }
+    }
+
+    pub fn set_wall_height(&mut self, wall_height: WallHeight) {
+        self.wall_height = wall_height;
+    }
+
+    pub fn center(&self) -> &Coordinate {
+        &self.center
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    pub fn facets(&self) -> &[Segment] {
+        &self.facets
+    }
+
+    fn facet_index(&self, coordinate: &Coordinate) -> i64 {
+        let angle = (coordinate.y - self.center.y).atan2(coordinate.x - self.center.x);
+        ((angle - self.start_angle).rem_euclid(PI_2) / self.facet_angle).floor() as i64
+    }
+
+    // Returns indexes of the facets placed around the points where the ray crosses the circle.
+    fn crossed_facets(&self, coordinate: &Coordinate, start_position: &Coordinate) -> Vec<usize> {
+        let ray = (
+            coordinate.x - start_position.x,
+            coordinate.y - start_position.y,
+        );
+        let from_center = (
+            start_position.x - self.center.x,
+            start_position.y - self.center.y,
+        );
+        let a = ray.0 * ray.0 + ray.1 * ray.1;
+        let b = 2.0 * (from_center.0 * ray.0 + from_center.1 * ray.1);
+        let c = from_center.0 * from_center.0 + from_center.1 * from_center.1
+            - self.radius * self.radius;
+        let discriminant = b * b - 4.0 * a * c;
+        if a == 0.0 || discriminant < 0.0 {
+            return vec![];
+        }
+        let facets_amount = self.facets.len() as i64;
+        let mut indexes = vec![];
+        for sign in [-1.0, 1.0].iter() {
+            let ray_part = (-b + sign * discriminant.sqrt()) / (2.0 * a);
+            let cross_point = Coordinate {
+                x: start_position.x + ray.0 * ray_part,
+                y: start_position.y + ray.1 * ray_part,
+            };
+            let facet_index = self.facet_index(&cross_point);
+            for index in facet_index - 1..=facet_index + 1 {
+                if self.closed {
+                    indexes.push(index.rem_euclid(facets_amount) as usize);
+                } else if index >= 0 && index < facets_amount {
+                    indexes.push(index as usize);
+                }
+            }
+        }
+        indexes
+    }
+}
+
+impl MapElement for CircularArc {
+    fn is_point_in_object(&self, _point: &Point) -> bool {
+        false
+    }
+
+    fn color(&self) -> Color {
+        self.color.clone()
+    }
+
+    fn wall_height(&self) -> WallHeight {
+        self.wall_height
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        Some(BoundingBox::new(&self.center, &self.center).extend(self.radius + CELL_DIAGONAL))
+    }
+
+    fn is_coordinate_in_object(
+        &self,
+        coordinate: &Coordinate,
+        start_position: &Coordinate,
+    ) -> Option<(Wall, LinearGraph)> {
+        if self.facets.is_empty() {
+            return None;
+        }
+        // facets are placed between the inscribed and the circumscribed circle
+        let distance = self.center.distance(coordinate);
+        if distance > self.radius + CELL_DIAGONAL
+            || distance < self.radius * (self.facet_angle / 2.0).cos() - CELL_DIAGONAL
+        {
+            return None;
+        }
+        nearest_wall(
+            self.crossed_facets(coordinate, start_position)
+                .into_iter()
+                .map(|index| &self.facets[index]),
+            coordinate,
+            start_position,
+            self.color(),
+            self.wall_height(),
+        )
+    }
+}
+
+// Round pillar or tower.
+#[derive(Clone)]
+pub struct Circle(CircularArc);
+
+impl Circle {
+    pub fn new(center: Coordinate, radius: f64, color: Option<Color>) -> Self {
+        Self(CircularArc::from_range(
+            center, radius, 0.0, PI_2, true, color,
+        ))
+    }
+
+    pub fn center(&self) -> &Coordinate {
+        self.0.center()
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.0.radius()
+    }
+
+    pub fn facets(&self) -> &[Segment] {
+        self.0.facets()
+    }
+
+    pub fn set_wall_height(&mut self, wall_height: WallHeight) {
+        self.0.set_wall_height(wall_height);
+    }
+}
+
+impl MapElement for Circle {
+    fn is_point_in_object(&self, point: &Point) -> bool {
+        Coordinate {
+            x: point.x as f64 + 0.5,
+            y: point.y as f64 + 0.5,
+        }
+        .distance(self.center())
+            < self.radius()
+    }
+
+    fn color(&self) -> Color {
+        self.0.color()
+    }
+
+    fn wall_height(&self) -> WallHeight {
+        self.0.wall_height()
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        self.0.bounding_box()
+    }
+
+    fn is_coordinate_in_object(
+        &self,
+        coordinate: &Coordinate,
+        start_position: &Coordinate,
+    ) -> Option<(Wall, LinearGraph)> {
+        self.0.is_coordinate_in_object(coordinate, start_position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_upper_case_globals)]
+    use super::*;
+    use crate::graph::MockGraphMethods;
+    use float_cmp::approx_eq;
+    use lazy_static::*;
+
+    lazy_static! {
+        static ref linear_graph: LinearGraph = LinearGraph::default();
+    }
+
+    #[test]
+    fn circle_facets() {
+        let circle = Circle::new(Coordinate { x: 5.0, y: 5.0 }, 2.0, None);
+        assert_eq!(circle.color(), Color::Orange);
+        assert_eq!(circle.facets().len(), 32);
+        assert_eq!(
+            circle.bounding_box(),
+            Some(BoundingBox {
+                min: Coordinate { x: 1.5, y: 1.5 },
+                max: Coordinate { x: 8.5, y: 8.5 },
+            })
+        );
+        for (facet, next_facet) in circle
+            .facets()
+            .iter()
+            .zip(circle.facets().iter().cycle().skip(1))
+        {
+            assert_eq!(facet.end, next_facet.start);
+            assert!(approx_eq!(
+                f64,
+                facet.start.distance(circle.center()),
+                2.0,
+                epsilon = 1e-9
+            ));
+        }
+
+        let arc = CircularArc::new(
+            Coordinate { x: 5.0, y: 5.0 },
+            3.0,
+            Radians::ZERO,
+            Radians::PI,
+            Some(Color::Green),
+        );
+        assert_eq!(arc.color(), Color::Green);
+        assert_eq!(arc.facets().len(), 19);
+        assert!(approx_eq!(f64, arc.facets()[18].end.x, 2.0, epsilon = 1e-9));
+        assert!(approx_eq!(f64, arc.facets()[18].end.y, 5.0, epsilon = 1e-9));
+    }
+
+    #[test]
+    fn circle_is_point_in_object() {
+        let circle = Circle::new(Coordinate { x: 5.0, y: 5.0 }, 2.0, None);
+        assert!(circle.is_point_in_object(&Point { x: 4, y: 4 }));
+        assert!(circle.is_point_in_object(&Point { x: 3, y: 5 }));
+        assert!(circle.is_point_in_object(&Point { x: 5, y: 6 }));
+        assert!(!circle.is_point_in_object(&Point { x: 3, y: 3 }));
+        assert!(!circle.is_point_in_object(&Point { x: 7, y: 5 }));
+    }
+
+    #[test]
+    fn circle_is_coordinate_in_object() {
+        let from_two_coordinates_context = MockGraphMethods::from_two_coordinates_context();
+        from_two_coordinates_context
+            .expect()
+            .times(1)
+            .return_const(linear_graph.clone());
+
+        let circle = Circle::new(Coordinate { x: 5.0, y: 5.0 }, 2.0, Some(Color::Green));
+        let start_position = Coordinate { x: 1.0, y: 5.1 };
+        assert_eq!(
+            circle.is_coordinate_in_object(&Coordinate { x: 2.0, y: 5.1 }, &start_position),
+            None
+        );
+        let (wall, _) = circle
+            .is_coordinate_in_object(&Coordinate { x: 3.5, y: 5.1 }, &start_position)
+            .unwrap();
+        // facet between angles 15 * pi / 16 and pi
+        let facet = &circle.facets()[15];
+        assert_eq!(wall.end_point, facet.start);
+        assert!(approx_eq!(f64, wall.start_point.y, 5.1, epsilon = 1e-9));
+        let facet_part = 0.1 / (facet.start.y - facet.end.y);
+        assert!(approx_eq!(
+            f64,
+            wall.start_point.x,
+            3.0 + (facet.start.x - 3.0) * facet_part,
+            epsilon = 1e-9
+        ));
+        assert_eq!(wall.primary_object_color, Color::Green);
+
+        // ray goes next to the circle
+        assert_eq!(
+            circle.is_coordinate_in_object(
+                &Coordinate { x: 4.0, y: 2.9 },
+                &Coordinate { x: 1.0, y: 2.9 }
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn arc_is_coordinate_in_object() {
+        let from_two_coordinates_context = MockGraphMethods::from_two_coordinates_context();
+        from_two_coordinates_context
+            .expect()
+            .times(1)
+            .return_const(linear_graph.clone());
+
+        let arc = CircularArc::new(
+            Coordinate { x: 5.0, y: 5.0 },
+            3.0,
+            Radians::ZERO,
+            Radians::PI,
+            None,
+        );
+        let start_position = Coordinate { x: 5.0, y: 5.0 };
+        assert_eq!(
+            arc.is_coordinate_in_object(&Coordinate { x: 5.0, y: 7.5 }, &start_position),
+            None
+        );
+        let (wall, _) = arc
+            .is_coordinate_in_object(&Coordinate { x: 5.0, y: 8.0 }, &start_position)
+            .unwrap();
+        assert_eq!(wall.end_point, arc.facets()[9].end);
+        assert!(approx_eq!(f64, wall.start_point.x, 5.0, epsilon = 1e-9));
+        assert!(approx_eq!(
+            f64,
+            wall.start_point.y,
+            5.0 + 3.0 * (std::f64::consts::PI / 38.0).cos(),
+            epsilon = 1e-9
+        ));
+
+        // there is no wall on the other side of the arc
+        assert_eq!(
+            arc.is_coordinate_in_object(&Coordinate { x: 5.0, y: 2.0 }, &start_position),
+            None
+        );
+    }
+}
@@ -29,6 +29,21 @@ impl Into<[f32; 4]> for Color {
     }
 }
 
+impl Color {
+    // walls with alpha below 1 are drawn over the walls behind them
+    pub fn is_transparent(&self) -> bool {
+        matches!(self, Color::Custom(color) if color[3] < 1.0)
+    }
+
+    // mixes the tint into the color by the alpha of the tint
+    pub fn tint(&self, tint: &Color) -> Color {
+        let color: [f32; 4] = self.clone().into();
+        let tint: [f32; 4] = tint.clone().into();
+        let mix = |index: usize| color[index] * (1.0 - tint[3]) + tint[index] * tint[3];
+        Color::Custom([mix(0), mix(1), mix(2), color[3]])
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -51,4 +66,23 @@ mod tests {
         assert_eq!(Into::<[f32; 4]>::into(Color::Pink), pink);
         assert_eq!(Into::<[f32; 4]>::into(Color::Custom(custom)), custom);
     }
+
+    #[test]
+    fn is_transparent() {
+        assert!(!Color::Red.is_transparent());
+        assert!(!Color::Custom([0.1, 0.2, 0.3, 1.0]).is_transparent());
+        assert!(Color::Custom([0.1, 0.2, 0.3, 0.4]).is_transparent());
+    }
+
+    #[test]
+    fn tint() {
+        assert_eq!(
+            Color::Custom([1.0, 0.0, 0.5, 0.5]).tint(&Color::Custom([0.0, 1.0, 0.5, 0.25])),
+            Color::Custom([0.75, 0.25, 0.5, 0.5])
+        );
+        assert_eq!(
+            Color::Green.tint(&Color::Custom([1.0, 1.0, 1.0, 0.0])),
+            Color::Custom([0.0, 0.6, 0.2, 1.0])
+        );
+    }
 }
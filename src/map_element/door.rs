@@ -1,7 +1,7 @@
 use super::Point;
-use crate::graph::Coordinate;
-use crate::map_element::Color;
+use crate::graph::{Coordinate, WallHeight};
 use crate::map_element::MapElement;
+use crate::map_element::{BoundingBox, Color};
 use mockall_double::double;
 
 #[double]
@@ -11,11 +11,13 @@ use super::Rectangle;
 
 pub struct Door {
     half_doors: (HalfDoor, HalfDoor),
+    area: BoundingBox,
     open_door_area: Rectangle,
     door_state: DoorState,
     door_velocity: f64,
     time_elapsed_ms: f64,
     color: Color,
+    wall_height: WallHeight,
 }
 
 impl Door {
@@ -30,6 +32,12 @@ impl Door {
     ) -> Self {
         Self {
             half_doors: HalfDoor::get_half_doors(&door_area, &door_type),
+            // half doors are moving only inside the door area
+            area: BoundingBox::new(
+                &door_area.point_a.clone().into(),
+                &door_area.point_b.clone().into(),
+            )
+            .extend(1.0),
             open_door_area: Rectangle::get_open_door_area(
                 &door_area,
                 open_door_area_opt,
@@ -39,9 +47,14 @@ impl Door {
             door_velocity: door_velocity.into(),
             time_elapsed_ms: 0.0,
             color: door_color.unwrap_or(Color::Blue),
+            wall_height: WallHeight::default(),
         }
     }
 
+    pub fn set_wall_height(&mut self, wall_height: WallHeight) {
+        self.wall_height = wall_height;
+    }
+
     fn open_door_step(&mut self) -> bool {
         let ret1 = self.half_doors.0.open_door_step();
         let ret2 = self.half_doors.1.open_door_step();
@@ -66,6 +79,14 @@ impl MapElement for Door {
         self.color.clone()
     }
 
+    fn wall_height(&self) -> WallHeight {
+        self.wall_height
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        Some(self.area.clone())
+    }
+
     fn update(&mut self, time_elapsed: f64) {
         if self.door_state == DoorState::Opening {
             self.time_elapsed_ms += time_elapsed * 1000.0;
@@ -117,6 +138,7 @@ impl Default for DoorState {
     }
 }
 
+#[derive(Clone, PartialEq, Debug)]
 pub enum DoorVelocity {
     VerySlow,
     Slow,
@@ -135,7 +157,7 @@ impl Into<f64> for DoorVelocity {
     }
 }
 
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Clone, Debug)]
 pub enum DoorType {
     Vertical,
     Horizontal,
@@ -188,11 +210,13 @@ mod tests {
 
         let door = Door {
             half_doors: (half_door_1, half_door_2),
+            area: Default::default(),
             open_door_area: Default::default(),
             door_state: DoorState::Closed,
             door_velocity: Default::default(),
             time_elapsed_ms: Default::default(),
             color: Default::default(),
+            wall_height: Default::default(),
         };
         assert!(door.is_point_in_object(&point));
         assert!(!door.is_point_in_object(&point));
@@ -203,11 +227,13 @@ mod tests {
         let color = Color::Green;
         let door = Door {
             half_doors: Default::default(),
+            area: Default::default(),
             open_door_area: Default::default(),
             door_state: Default::default(),
             door_velocity: Default::default(),
             time_elapsed_ms: Default::default(),
             color: color.clone(),
+            wall_height: Default::default(),
         };
         assert_eq!(door.color(), color);
     }
@@ -242,11 +268,13 @@ mod tests {
 
         let mut door = Door {
             half_doors: (half_door_1, half_door_2),
+            area: Default::default(),
             open_door_area: Default::default(),
             door_state: DoorState::Opening,
             door_velocity: 150.0,
             time_elapsed_ms: 0.0,
             color: Default::default(),
+            wall_height: Default::default(),
         };
         door.update(0.5);
         assert_eq!(door.door_state, DoorState::Opened)
@@ -282,11 +310,13 @@ mod tests {
 
         let mut door = Door {
             half_doors: (half_door_1, half_door_2),
+            area: Default::default(),
             open_door_area: Default::default(),
             door_state: DoorState::Closing,
             door_velocity: 150.0,
             time_elapsed_ms: 0.0,
             color: Default::default(),
+            wall_height: Default::default(),
         };
         door.update(0.5);
         assert_eq!(door.door_state, DoorState::Closed)
@@ -314,11 +344,13 @@ mod tests {
 
         let mut door = Door {
             half_doors: (half_door_1, half_door_2),
+            area: Default::default(),
             open_door_area: area,
             door_state: door_state_start,
             door_velocity: Default::default(),
             time_elapsed_ms: Default::default(),
             color: Default::default(),
+            wall_height: Default::default(),
         };
 
         door.on_position_update(&coordinate);
@@ -0,0 +1,49 @@
+use super::{DoorType, Rectangle};
+
+// Location, opening direction and opening area of a door that is not created yet.
+// Used to describe doors of generated and validated maps.
+#[derive(PartialEq, Clone, Debug)]
+pub struct DoorArea {
+    pub area: Rectangle,
+    pub door_type: DoorType,
+    pub open_area: Option<Rectangle>,
+}
+
+impl DoorArea {
+    // returns the area in which the door opens, the same one the Door uses
+    pub fn open_door_area(&self) -> Rectangle {
+        Rectangle::get_open_door_area(&self.area, self.open_area.clone(), &self.door_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_element::Point;
+
+    #[test]
+    fn open_door_area() {
+        let mut door_area = DoorArea {
+            area: Rectangle {
+                point_a: Point { x: 20, y: 10 },
+                point_b: Point { x: 30, y: 12 },
+            },
+            door_type: DoorType::Horizontal,
+            open_area: None,
+        };
+        assert_eq!(
+            door_area.open_door_area(),
+            Rectangle {
+                point_a: Point { x: 16, y: 0 },
+                point_b: Point { x: 34, y: 22 },
+            }
+        );
+
+        let open_area = Rectangle {
+            point_a: Point { x: 1, y: 2 },
+            point_b: Point { x: 3, y: 4 },
+        };
+        door_area.open_area = Some(open_area.clone());
+        assert_eq!(door_area.open_door_area(), open_area);
+    }
+}
@@ -1,6 +1,7 @@
-use crate::graph::{Coordinate, LinearGraph, Wall};
-use crate::map_element::{Color, Point};
+use crate::graph::{Coordinate, Crossing, LinearGraph, Surface, Wall, WallHeight};
+use crate::map_element::{BoundingBox, Color, Point};
 use mockall_double::double;
+use std::any::Any;
 
 #[double]
 use crate::graph::GraphMethods;
@@ -8,130 +9,217 @@ use crate::graph::GraphMethods;
 #[cfg(test)]
 use mockall::automock;
 
+// Used to downcast map elements to the concrete type.
+pub trait AsAny {
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
 #[cfg_attr(test, automock)]
-pub trait MapElement: Send + Sync {
+pub trait MapElement: AsAny + Send + Sync {
     fn is_point_in_object(&self, point: &Point) -> bool;
 
     fn color(&self) -> Color;
 
+    // height and elevation of the walls of the element
+    fn wall_height(&self) -> WallHeight {
+        WallHeight::default()
+    }
+
+    // height of the wall at the point, for elements with walls of different heights
+    fn wall_height_at(&self, _point: &Point) -> WallHeight {
+        self.wall_height()
+    }
+
+    // every height the walls can have, used to split the rendering into layers
+    fn wall_heights(&self) -> Vec<WallHeight> {
+        vec![self.wall_height()]
+    }
+
     fn update(&mut self, _time_elapsed: f64) {}
 
     fn on_position_update(&mut self, _coordinate: &Coordinate) {}
 
+    // Paints or erases the wall at the point, returns false when it's not supported.
+    fn set_wall(&mut self, _point: &Point, _is_wall: bool) -> bool {
+        false
+    }
+
+    // Area where is_coordinate_in_object can find a wall, None when it's not limited.
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        None
+    }
+
+    // height of the floor at the coordinate, None when the element has no floor there
+    fn floor_height_at(&self, _coordinate: &Coordinate) -> Option<f64> {
+        None
+    }
+
+    // floors and ceilings drawn under the walls
+    fn surfaces(&self) -> Vec<Surface> {
+        vec![]
+    }
+
+    // Portal or mirror crossed by the move from one coordinate to another.
+    fn portal_crossing(&self, _from: &Coordinate, _to: &Coordinate) -> Option<Crossing> {
+        None
+    }
+
     fn is_coordinate_in_object(
         &self,
         coordinate: &Coordinate,
         start_position: &Coordinate,
     ) -> Option<(Wall, LinearGraph)> {
+        // Cell ahead of the ray is checked first, so the wall behind the
+        // see-through lower wall is found.
         if coordinate.x.fract() == 0.0 {
-            if self.is_point_in_object(&Point {
-                x: coordinate.x as i64,
-                y: coordinate.y.floor() as i64,
-            }) {
-                return Some((
-                    Wall {
-                        start_point: Point {
-                            x: coordinate.x as i64,
-                            y: coordinate.y.floor() as i64,
-                        },
-                        end_point: Point {
-                            x: coordinate.x as i64,
-                            y: coordinate.y.ceil() as i64,
-                        },
-                        primary_object_color: self.color(),
-                    },
-                    GraphMethods::from_two_coordinates(
-                        start_position,
-                        Coordinate {
-                            x: coordinate.x,
-                            y: coordinate.y.ceil() + 0.0001,
+            let right_cell = || {
+                let point = Point {
+                    x: coordinate.x as i64,
+                    y: coordinate.y.floor() as i64,
+                };
+                self.is_point_in_object(&point).then(|| {
+                    (
+                        Wall {
+                            start_point: Coordinate {
+                                x: coordinate.x,
+                                y: coordinate.y.floor(),
+                            },
+                            end_point: Coordinate {
+                                x: coordinate.x,
+                                y: coordinate.y.ceil(),
+                            },
+                            primary_object_color: self.color(),
+                            height: self.wall_height_at(&point),
                         },
-                    ),
-                ));
-            }
-            if coordinate.x >= 1.0
-                && self.is_point_in_object(&Point {
+                        GraphMethods::from_two_coordinates(
+                            start_position,
+                            Coordinate {
+                                x: coordinate.x,
+                                y: coordinate.y.ceil() + 0.0001,
+                            },
+                        ),
+                    )
+                })
+            };
+            let left_cell = || {
+                let point = Point {
                     x: coordinate.x as i64 - 1,
                     y: coordinate.y.floor() as i64,
-                })
-            {
-                return Some((
-                    Wall {
-                        start_point: Point {
-                            x: coordinate.x as i64,
-                            y: coordinate.y.ceil() as i64,
+                };
+                (coordinate.x >= 1.0 && self.is_point_in_object(&point)).then(|| {
+                    (
+                        Wall {
+                            start_point: Coordinate {
+                                x: coordinate.x,
+                                y: coordinate.y.ceil(),
+                            },
+                            end_point: Coordinate {
+                                x: coordinate.x,
+                                y: coordinate.y.floor(),
+                            },
+                            primary_object_color: self.color(),
+                            height: self.wall_height_at(&point),
                         },
-                        end_point: Point {
-                            x: coordinate.x as i64,
-                            y: coordinate.y.floor() as i64,
-                        },
-
-                        primary_object_color: self.color(),
-                    },
-                    GraphMethods::from_two_coordinates(
-                        start_position,
-                        Coordinate {
-                            x: coordinate.x,
-                            y: coordinate.y.floor() - 0.0001,
-                        },
-                    ),
-                ));
+                        GraphMethods::from_two_coordinates(
+                            start_position,
+                            Coordinate {
+                                x: coordinate.x,
+                                y: coordinate.y.floor() - 0.0001,
+                            },
+                        ),
+                    )
+                })
+            };
+            if start_position.x > coordinate.x {
+                return left_cell().or_else(right_cell);
             }
+            return right_cell().or_else(left_cell);
         } else if coordinate.y.fract() == 0.0 {
-            if self.is_point_in_object(&Point {
-                x: coordinate.x.floor() as i64,
-                y: coordinate.y as i64,
-            }) {
-                return Some((
-                    Wall {
-                        start_point: Point {
-                            x: coordinate.x.ceil() as i64,
-                            y: coordinate.y as i64,
-                        },
-                        end_point: Point {
-                            x: coordinate.x.floor() as i64,
-                            y: coordinate.y as i64,
-                        },
-                        primary_object_color: self.color(),
-                    },
-                    GraphMethods::from_two_coordinates(
-                        start_position,
-                        Coordinate {
-                            x: coordinate.x.floor() - 0.0001,
-                            y: coordinate.y,
+            let lower_cell = || {
+                let point = Point {
+                    x: coordinate.x.floor() as i64,
+                    y: coordinate.y as i64,
+                };
+                self.is_point_in_object(&point).then(|| {
+                    (
+                        Wall {
+                            start_point: Coordinate {
+                                x: coordinate.x.ceil(),
+                                y: coordinate.y,
+                            },
+                            end_point: Coordinate {
+                                x: coordinate.x.floor(),
+                                y: coordinate.y,
+                            },
+                            primary_object_color: self.color(),
+                            height: self.wall_height_at(&point),
                         },
-                    ),
-                ));
-            }
-            if coordinate.y >= 1.0
-                && self.is_point_in_object(&Point {
+                        GraphMethods::from_two_coordinates(
+                            start_position,
+                            Coordinate {
+                                x: coordinate.x.floor() - 0.0001,
+                                y: coordinate.y,
+                            },
+                        ),
+                    )
+                })
+            };
+            let upper_cell = || {
+                let point = Point {
                     x: coordinate.x.floor() as i64,
                     y: coordinate.y as i64 - 1,
-                })
-            {
-                return Some((
-                    Wall {
-                        start_point: Point {
-                            x: coordinate.x.floor() as i64,
-                            y: coordinate.y as i64,
-                        },
-                        end_point: Point {
-                            x: coordinate.x.ceil() as i64,
-                            y: coordinate.y as i64,
+                };
+                (coordinate.y >= 1.0 && self.is_point_in_object(&point)).then(|| {
+                    (
+                        Wall {
+                            start_point: Coordinate {
+                                x: coordinate.x.floor(),
+                                y: coordinate.y,
+                            },
+                            end_point: Coordinate {
+                                x: coordinate.x.ceil(),
+                                y: coordinate.y,
+                            },
+                            primary_object_color: self.color(),
+                            height: self.wall_height_at(&point),
                         },
-
-                        primary_object_color: self.color(),
-                    },
-                    GraphMethods::from_two_coordinates(
-                        start_position,
-                        Coordinate {
-                            x: coordinate.x.ceil() + 0.0001,
-                            y: coordinate.y,
-                        },
-                    ),
-                ));
+                        GraphMethods::from_two_coordinates(
+                            start_position,
+                            Coordinate {
+                                x: coordinate.x.ceil() + 0.0001,
+                                y: coordinate.y,
+                            },
+                        ),
+                    )
+                })
+            };
+            if start_position.y > coordinate.y {
+                return upper_cell().or_else(lower_cell);
             }
+            return lower_cell().or_else(upper_cell);
         }
         return None;
     }
 }
+
+// Mock element without the bounding box, so it is found at every coordinate,
+// and without the portal crossing.
+#[cfg(test)]
+pub(crate) fn unbounded_map_element() -> Box<MockMapElement> {
+    let mut map_element = Box::new(MockMapElement::new());
+    map_element.expect_bounding_box().return_const(None);
+    map_element.expect_portal_crossing().return_const(None);
+    map_element
+}
@@ -0,0 +1,566 @@
+use crate::graph::{Coordinate, Crossing, Surface};
+use crate::map::Layer;
+use crate::map_element::spatial_grid::SpatialGrid;
+use crate::map_element::{MapElement, Point};
+use std::any::Any;
+use std::sync::{Arc, RwLock};
+
+// at most this many transparent walls are drawn over each other
+const MAX_TRANSPARENT_DEPTH: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ElementId(u64);
+
+// Collection of all map elements. Every inserted element gets an id which
+// stays valid until the element is removed, ids are never reused.
+// Element can also have a name used to find it.
+// Bounding boxes of the elements are kept in the grid, so the ray casting
+// checks only elements placed near the ray.
+#[derive(Default)]
+pub struct MapElements {
+    ids: Vec<ElementId>,
+    names: Vec<Option<String>>,
+    elements: Vec<Box<dyn MapElement>>,
+    next_id: u64,
+    grid: SpatialGrid,
+}
+
+impl MapElements {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn insert(&mut self, map_element: Box<dyn MapElement>) -> ElementId {
+        self.push(None, map_element)
+    }
+
+    pub fn insert_named(&mut self, name: &str, map_element: Box<dyn MapElement>) -> ElementId {
+        self.push(Some(String::from(name)), map_element)
+    }
+
+    pub fn remove(&mut self, id: ElementId) -> Option<Box<dyn MapElement>> {
+        let index = self.index(id)?;
+        self.ids.remove(index);
+        self.names.remove(index);
+        let map_element = self.elements.remove(index);
+        self.update_index();
+        Some(map_element)
+    }
+
+    // returns the first element with the given name
+    pub fn find(&self, name: &str) -> Option<ElementId> {
+        let index = self
+            .names
+            .iter()
+            .position(|element_name| element_name.as_deref() == Some(name))?;
+        Some(self.ids[index])
+    }
+
+    pub fn name(&self, id: ElementId) -> Option<&str> {
+        self.names[self.index(id)?].as_deref()
+    }
+
+    pub fn get(&self, id: ElementId) -> Option<&dyn MapElement> {
+        let index = self.index(id)?;
+        Some(self.elements[index].as_ref())
+    }
+
+    pub fn get_mut(&mut self, id: ElementId) -> Option<&mut (dyn MapElement + 'static)> {
+        let index = self.index(id)?;
+        Some(self.elements[index].as_mut())
+    }
+
+    // returns None when element doesn't exist or is not of type T
+    pub fn get_as<T: Any>(&self, id: ElementId) -> Option<&T> {
+        self.get(id)?.as_any().downcast_ref::<T>()
+    }
+
+    pub fn get_as_mut<T: Any>(&mut self, id: ElementId) -> Option<&mut T> {
+        self.get_mut(id)?.as_any_mut().downcast_mut::<T>()
+    }
+
+    // ids in the order in which elements are checked during the ray casting
+    pub fn ids(&self) -> &[ElementId] {
+        &self.ids
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn MapElement>> {
+        self.elements.iter_mut()
+    }
+
+    // elements which can have a wall at the coordinate, in the insertion order
+    pub(crate) fn elements_at(
+        &self,
+        coordinate: &Coordinate,
+    ) -> impl Iterator<Item = &Box<dyn MapElement>> {
+        self.grid
+            .indexes(coordinate)
+            .iter()
+            .map(move |index| &self.elements[*index])
+    }
+
+    // Render layers ordered from the highest, there is one layer for every
+    // height of the wall tops, the last opaque layer includes all opaque walls.
+    // When some element is transparent, transparent layers follow from the
+    // farthest one. Every layer casts all rays again, so every distinct top
+    // height (e.g. up to 16 levels of the height map) adds the cost of one
+    // frame. Heights which are not finite don't get their own layer.
+    pub(crate) fn layers(&self, wall_height: f64) -> Vec<Layer> {
+        let mut tops: Vec<f64> = self
+            .elements
+            .iter()
+            .flat_map(|map_element| map_element.wall_heights())
+            .map(|height| height.top(wall_height))
+            .filter(|top| top.is_finite())
+            .collect();
+        tops.sort_by(|first, second| second.total_cmp(first));
+        tops.dedup();
+        tops.pop();
+        tops.push(f64::NEG_INFINITY);
+        let mut layers: Vec<Layer> = tops
+            .into_iter()
+            .map(|min_top| Layer {
+                min_top,
+                wall_height,
+                transparent_depth: None,
+            })
+            .collect();
+        if self
+            .elements
+            .iter()
+            .any(|map_element| map_element.color().is_transparent())
+        {
+            layers.extend((0..MAX_TRANSPARENT_DEPTH).rev().map(|depth| Layer {
+                min_top: f64::NEG_INFINITY,
+                wall_height,
+                transparent_depth: Some(depth),
+            }));
+        }
+        layers
+    }
+
+    // floor of the last inserted element which has the floor at the coordinate
+    pub(crate) fn floor_height_at(&self, coordinate: &Coordinate) -> f64 {
+        self.elements_at(coordinate)
+            .filter_map(|map_element| map_element.floor_height_at(coordinate))
+            .last()
+            .unwrap_or(0.0)
+    }
+
+    // the nearest portal or mirror crossed by the move, from has to be close to to
+    pub(crate) fn portal_crossing(&self, from: &Coordinate, to: &Coordinate) -> Option<Crossing> {
+        self.elements_at(to)
+            .filter_map(|map_element| map_element.portal_crossing(from, to))
+            .min_by(|first, second| {
+                from.distance(&first.point)
+                    .total_cmp(&from.distance(&second.point))
+            })
+    }
+
+    pub(crate) fn surfaces(&self) -> Vec<Surface> {
+        self.elements
+            .iter()
+            .flat_map(|map_element| map_element.surfaces())
+            .collect()
+    }
+
+    // Has to be called after elements are changed, the grid is rebuilt only
+    // when some bounding box has changed.
+    pub(crate) fn update_index(&mut self) {
+        let bounding_boxes: Vec<_> = self
+            .elements
+            .iter()
+            .map(|map_element| map_element.bounding_box())
+            .collect();
+        if bounding_boxes != self.grid.bounding_boxes() {
+            self.grid = SpatialGrid::new(bounding_boxes);
+        }
+    }
+
+    fn push(&mut self, name: Option<String>, map_element: Box<dyn MapElement>) -> ElementId {
+        let id = ElementId(self.next_id);
+        self.next_id += 1;
+        self.ids.push(id);
+        self.names.push(name);
+        self.elements.push(map_element);
+        self.update_index();
+        id
+    }
+
+    fn index(&self, id: ElementId) -> Option<usize> {
+        self.ids.iter().position(|element_id| *element_id == id)
+    }
+}
+
+impl From<Vec<Box<dyn MapElement>>> for MapElements {
+    fn from(map_elements: Vec<Box<dyn MapElement>>) -> Self {
+        let mut elements = Self::new();
+        for map_element in map_elements {
+            elements.insert(map_element);
+        }
+        elements
+    }
+}
+
+// Handle to the map elements used by the running Engine. It can be cloned and
+// moved to other threads, every change is visible in the next frame.
+// Render threads read map elements during the whole frame, so elements are
+// inserted and removed between the frames.
+#[derive(Clone)]
+pub struct MapElementsHandle {
+    map_elements: Arc<RwLock<MapElements>>,
+}
+
+impl MapElementsHandle {
+    pub(crate) fn new(map_elements: Arc<RwLock<MapElements>>) -> Self {
+        Self { map_elements }
+    }
+
+    pub fn insert(&self, map_element: Box<dyn MapElement>) -> ElementId {
+        self.map_elements.write().unwrap().insert(map_element)
+    }
+
+    pub fn insert_named(&self, name: &str, map_element: Box<dyn MapElement>) -> ElementId {
+        self.map_elements
+            .write()
+            .unwrap()
+            .insert_named(name, map_element)
+    }
+
+    pub fn remove(&self, id: ElementId) -> Option<Box<dyn MapElement>> {
+        self.map_elements.write().unwrap().remove(id)
+    }
+
+    pub fn find(&self, name: &str) -> Option<ElementId> {
+        self.map_elements.read().unwrap().find(name)
+    }
+
+    pub fn ids(&self) -> Vec<ElementId> {
+        self.map_elements.read().unwrap().ids().to_vec()
+    }
+
+    // Calls f with the element of type T, returns None when element doesn't
+    // exist or is not of type T. Map elements are locked while f is running.
+    pub fn with_element<T: Any, R>(&self, id: ElementId, f: impl FnOnce(&T) -> R) -> Option<R> {
+        Some(f(self.map_elements.read().unwrap().get_as::<T>(id)?))
+    }
+
+    pub fn with_element_mut<T: Any, R>(
+        &self,
+        id: ElementId,
+        f: impl FnOnce(&mut T) -> R,
+    ) -> Option<R> {
+        let mut map_elements = self.map_elements.write().unwrap();
+        let result = f(map_elements.get_as_mut::<T>(id)?);
+        map_elements.update_index();
+        Some(result)
+    }
+
+    // returns false when element doesn't exist or can't be painted
+    pub fn paint_wall(&self, id: ElementId, point: &Point) -> bool {
+        self.set_wall(id, point, true)
+    }
+
+    pub fn erase_wall(&self, id: ElementId, point: &Point) -> bool {
+        self.set_wall(id, point, false)
+    }
+
+    fn set_wall(&self, id: ElementId, point: &Point, is_wall: bool) -> bool {
+        let mut map_elements = self.map_elements.write().unwrap();
+        let is_set = match map_elements.get_mut(id) {
+            Some(map_element) => map_element.set_wall(point, is_wall),
+            None => false,
+        };
+        map_elements.update_index();
+        is_set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::WallHeight;
+    use crate::map_element::{
+        unbounded_map_element, BoundingBox, Color, MockMapElement, Rectangle, Sector, WallMap,
+    };
+
+    fn element_with_color(color: Color) -> Box<MockMapElement> {
+        let mut map_element = unbounded_map_element();
+        map_element.expect_color().return_const(color);
+        map_element
+    }
+
+    #[test]
+    fn insert_remove() {
+        let mut map_elements = MapElements::new();
+        let red = map_elements.insert(element_with_color(Color::Red));
+        let green = map_elements.insert(element_with_color(Color::Green));
+        let blue = map_elements.insert(element_with_color(Color::Blue));
+        assert_eq!(map_elements.ids(), &[red, green, blue]);
+
+        assert!(map_elements.remove(green).is_some());
+        assert!(map_elements.remove(green).is_none());
+        assert_eq!(map_elements.len(), 2);
+        assert_eq!(map_elements.get(red).unwrap().color(), Color::Red);
+        assert_eq!(map_elements.get(blue).unwrap().color(), Color::Blue);
+        assert!(map_elements.get(green).is_none());
+
+        let yellow = map_elements.insert(element_with_color(Color::Yellow));
+        assert_ne!(yellow, green);
+        assert_eq!(map_elements.ids(), &[red, blue, yellow]);
+        assert_eq!(
+            map_elements
+                .elements
+                .iter()
+                .map(|map_element| map_element.color())
+                .collect::<Vec<_>>(),
+            vec![Color::Red, Color::Blue, Color::Yellow]
+        );
+    }
+
+    #[test]
+    fn elements_at() {
+        let bounding_box = Arc::new(RwLock::new(BoundingBox {
+            min: Coordinate { x: 0.0, y: 0.0 },
+            max: Coordinate { x: 2.0, y: 2.0 },
+        }));
+        let mut moving = Box::new(MockMapElement::new());
+        let moving_box = Arc::clone(&bounding_box);
+        moving
+            .expect_bounding_box()
+            .returning(move || Some(moving_box.read().unwrap().clone()));
+        moving.expect_color().return_const(Color::Green);
+
+        let mut map_elements = MapElements::new();
+        map_elements.insert(element_with_color(Color::Red));
+        map_elements.insert(moving);
+        let colors_at = |map_elements: &MapElements, x: f64, y: f64| -> Vec<Color> {
+            map_elements
+                .elements_at(&Coordinate { x, y })
+                .map(|map_element| map_element.color())
+                .collect()
+        };
+        assert_eq!(
+            colors_at(&map_elements, 1.0, 1.0),
+            [Color::Red, Color::Green]
+        );
+        assert_eq!(colors_at(&map_elements, 20.0, 20.0), [Color::Red]);
+
+        *bounding_box.write().unwrap() = BoundingBox {
+            min: Coordinate { x: 20.0, y: 20.0 },
+            max: Coordinate { x: 22.0, y: 22.0 },
+        };
+        assert_eq!(colors_at(&map_elements, 20.0, 20.0), [Color::Red]);
+        map_elements.update_index();
+        assert_eq!(colors_at(&map_elements, 1.0, 1.0), [Color::Red]);
+        assert_eq!(
+            colors_at(&map_elements, 20.0, 20.0),
+            [Color::Red, Color::Green]
+        );
+    }
+
+    #[test]
+    fn floor_height_at() {
+        let sector = |x: i64, floor_height: f64| {
+            Box::new(Sector::new(
+                Rectangle {
+                    point_a: Point { x, y: 0 },
+                    point_b: Point { x: x + 1, y: 1 },
+                },
+                floor_height,
+                None,
+                None,
+                None,
+            ))
+        };
+        let mut map_elements = MapElements::new();
+        map_elements.insert(sector(0, 1.0));
+        map_elements.insert(sector(1, -0.5));
+
+        assert_eq!(
+            map_elements.floor_height_at(&Coordinate { x: 0.5, y: 0.5 }),
+            1.0
+        );
+        // the last inserted sector is used where sectors overlap
+        assert_eq!(
+            map_elements.floor_height_at(&Coordinate { x: 1.5, y: 0.5 }),
+            -0.5
+        );
+        assert_eq!(
+            map_elements.floor_height_at(&Coordinate { x: 5.5, y: 0.5 }),
+            0.0
+        );
+    }
+
+    #[test]
+    fn from_vec() {
+        let map_elements = MapElements::from(vec![
+            element_with_color(Color::Red) as Box<dyn MapElement>,
+            element_with_color(Color::Green),
+        ]);
+        assert_eq!(map_elements.ids(), &[ElementId(0), ElementId(1)]);
+        assert_eq!(
+            map_elements.get(ElementId(1)).unwrap().color(),
+            Color::Green
+        );
+        assert!(MapElements::new().is_empty());
+    }
+
+    #[test]
+    fn layers() {
+        let element_with_height = |wall_height: WallHeight| {
+            let mut map_element = element_with_color(Color::Red);
+            map_element
+                .expect_wall_heights()
+                .return_const(vec![wall_height]);
+            map_element as Box<dyn MapElement>
+        };
+        let layer = |min_top| Layer {
+            min_top,
+            wall_height: 2.0,
+            transparent_depth: None,
+        };
+
+        let map_elements = MapElements::from(vec![
+            element_with_height(WallHeight::default()),
+            element_with_height(WallHeight::new(1.0, 0.0)),
+            element_with_height(WallHeight::new(3.0, 0.5)),
+            element_with_height(WallHeight::new(0.5, 1.5)),
+        ]);
+        assert_eq!(
+            map_elements.layers(2.0),
+            vec![layer(3.5), layer(2.0), layer(f64::NEG_INFINITY)]
+        );
+        assert_eq!(
+            MapElements::new().layers(2.0),
+            vec![layer(f64::NEG_INFINITY)]
+        );
+
+        // heights which are not finite don't panic and don't add layers
+        let map_elements = MapElements::from(vec![
+            element_with_height(WallHeight::new(f64::NAN, 0.0)),
+            element_with_height(WallHeight::new(f64::INFINITY, 0.0)),
+            element_with_height(WallHeight::new(1.0, 0.0)),
+            element_with_height(WallHeight::default()),
+        ]);
+        assert_eq!(
+            map_elements.layers(2.0),
+            vec![layer(2.0), layer(f64::NEG_INFINITY)]
+        );
+
+        // transparent layers are drawn after the opaque ones, from the farthest
+        let mut glass = element_with_color(Color::Custom([0.5, 0.8, 1.0, 0.3]));
+        glass
+            .expect_wall_heights()
+            .return_const(vec![WallHeight::default()]);
+        let transparent_layer = |depth| Layer {
+            min_top: f64::NEG_INFINITY,
+            wall_height: 2.0,
+            transparent_depth: Some(depth),
+        };
+        assert_eq!(
+            MapElements::from(vec![glass as Box<dyn MapElement>]).layers(2.0),
+            vec![
+                layer(f64::NEG_INFINITY),
+                transparent_layer(2),
+                transparent_layer(1),
+                transparent_layer(0)
+            ]
+        );
+    }
+
+    #[test]
+    fn names() {
+        let mut map_elements = MapElements::new();
+        let unnamed = map_elements.insert(element_with_color(Color::Red));
+        let door = map_elements.insert_named("red door", element_with_color(Color::Red));
+        let wall = map_elements.insert_named("wall", element_with_color(Color::Green));
+
+        assert_eq!(map_elements.find("red door"), Some(door));
+        assert_eq!(map_elements.find("wall"), Some(wall));
+        assert_eq!(map_elements.find("door"), None);
+        assert_eq!(map_elements.name(door), Some("red door"));
+        assert_eq!(map_elements.name(unnamed), None);
+
+        map_elements.remove(door);
+        assert_eq!(map_elements.find("red door"), None);
+        assert_eq!(map_elements.name(door), None);
+        assert_eq!(map_elements.name(wall), Some("wall"));
+    }
+
+    #[test]
+    fn get_as() {
+        let image = image::RgbaImage::from_pixel(4, 4, image::Rgba([255, 255, 255, 255]));
+        let mut map_elements = MapElements::new();
+        let wall_map = map_elements.insert(Box::new(WallMap::from_image(image, None)));
+        let other = map_elements.insert(unbounded_map_element());
+
+        assert!(map_elements.get_as::<WallMap>(wall_map).is_some());
+        assert!(map_elements.get_as::<MockMapElement>(wall_map).is_none());
+        assert!(map_elements.get_as::<MockMapElement>(other).is_some());
+        assert!(map_elements.get_as::<WallMap>(other).is_none());
+
+        map_elements
+            .get_as_mut::<WallMap>(wall_map)
+            .unwrap()
+            .set_wall(&Point { x: 1, y: 1 }, true);
+        assert!(map_elements
+            .get(wall_map)
+            .unwrap()
+            .is_point_in_object(&Point { x: 1, y: 1 }));
+
+        map_elements.remove(wall_map);
+        assert!(map_elements.get_as::<WallMap>(wall_map).is_none());
+    }
+
+    #[test]
+    fn handle_with_element() {
+        let image = image::RgbaImage::from_pixel(4, 4, image::Rgba([255, 255, 255, 255]));
+        let handle = MapElementsHandle::new(Arc::new(RwLock::new(MapElements::new())));
+        let wall_map = handle.insert_named("walls", Box::new(WallMap::from_image(image, None)));
+        assert_eq!(handle.find("walls"), Some(wall_map));
+
+        assert_eq!(
+            handle.with_element_mut(wall_map, |wall_map: &mut WallMap| {
+                wall_map.set_wall(&Point { x: 2, y: 3 }, true)
+            }),
+            Some(true)
+        );
+        assert_eq!(
+            handle.with_element(wall_map, |wall_map: &WallMap| {
+                wall_map.is_black_pixel(2, 3)
+            }),
+            Some(true)
+        );
+        assert_eq!(handle.with_element(wall_map, |_: &MockMapElement| ()), None);
+    }
+
+    #[test]
+    fn handle_paint_erase_wall() {
+        let image = image::RgbaImage::from_pixel(4, 4, image::Rgba([255, 255, 255, 255]));
+        let handle = MapElementsHandle::new(Arc::new(RwLock::new(MapElements::new())));
+        let wall_map = handle.insert(Box::new(WallMap::from_image(image, None)));
+        let mut map_element = unbounded_map_element();
+        map_element.expect_set_wall().return_const(false);
+        let other = handle.insert(map_element);
+
+        let point = Point { x: 1, y: 2 };
+        assert!(handle.paint_wall(wall_map, &point));
+        assert!(handle.map_elements.read().unwrap().elements[0].is_point_in_object(&point));
+        assert!(handle.erase_wall(wall_map, &point));
+        assert!(!handle.map_elements.read().unwrap().elements[0].is_point_in_object(&point));
+        assert!(!handle.paint_wall(other, &point));
+
+        assert!(handle.remove(wall_map).is_some());
+        assert!(!handle.paint_wall(wall_map, &point));
+        assert_eq!(handle.ids(), vec![other]);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::graph::{Coordinate, Crossing, LinearGraph, Transform, Wall, WallHeight};
+use crate::map_element::segment_map::nearest_crossing;
+use crate::map_element::{BoundingBox, Color, MapElement, Point, Segment, SegmentMap};
+
+const DEFAULT_MIRROR_COLOR: Color = Color::Custom([0.6, 0.7, 0.75, 1.0]);
+const DEFAULT_MAX_REFLECTIONS: usize = 4;
+
+// Reflective segments. Ray crossing the mirror is reflected and continues,
+// the ray which was already reflected max_reflections times sees the mirror
+// in its color. Optional tint is mixed into the colors of the reflected walls
+// by the alpha of the tint.
+#[derive(Clone)]
+pub struct Mirror {
+    faces: SegmentMap,
+    tint: Option<Color>,
+    max_reflections: usize,
+}
+
+impl Mirror {
+    pub fn new(segments: Vec<Segment>, color: Option<Color>, tint: Option<Color>) -> Self {
+        Self {
+            faces: SegmentMap::new(segments, Some(color.unwrap_or(DEFAULT_MIRROR_COLOR))),
+            tint,
+            max_reflections: DEFAULT_MAX_REFLECTIONS,
+        }
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        self.faces.segments()
+    }
+
+    pub fn max_reflections(&self) -> usize {
+        self.max_reflections
+    }
+
+    // rays passed through portals are counted too
+    pub fn set_max_reflections(&mut self, max_reflections: usize) {
+        self.max_reflections = max_reflections;
+    }
+}
+
+impl MapElement for Mirror {
+    fn is_point_in_object(&self, _point: &Point) -> bool {
+        false
+    }
+
+    fn color(&self) -> Color {
+        self.faces.color()
+    }
+
+    fn wall_height(&self) -> WallHeight {
+        self.faces.wall_height()
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        self.faces.bounding_box()
+    }
+
+    fn portal_crossing(&self, from: &Coordinate, to: &Coordinate) -> Option<Crossing> {
+        let (index, point) = nearest_crossing(self.segments(), from, to)?;
+        let segment = &self.segments()[index];
+        Some(Crossing {
+            point,
+            transform: Transform::reflection(&segment.start, &segment.end),
+            tint: self.tint.clone(),
+            max_passes: self.max_reflections,
+        })
+    }
+
+    fn is_coordinate_in_object(
+        &self,
+        coordinate: &Coordinate,
+        start_position: &Coordinate,
+    ) -> Option<(Wall, LinearGraph)> {
+        self.faces
+            .is_coordinate_in_object(coordinate, start_position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    #[test]
+    fn portal_crossing() {
+        let tint = Color::Custom([0.0, 0.0, 1.0, 0.2]);
+        let mut mirror = Mirror::new(
+            vec![Segment {
+                start: Coordinate { x: 5.0, y: 0.0 },
+                end: Coordinate { x: 5.0, y: 4.0 },
+            }],
+            None,
+            Some(tint.clone()),
+        );
+        mirror.set_max_reflections(2);
+
+        let crossing = mirror
+            .portal_crossing(
+                &Coordinate { x: 4.5, y: 1.0 },
+                &Coordinate { x: 5.5, y: 1.5 },
+            )
+            .unwrap();
+        assert!(approx_eq!(f64, crossing.point.x, 5.0, epsilon = 1e-9));
+        assert!(approx_eq!(f64, crossing.point.y, 1.25, epsilon = 1e-9));
+        // the move is reflected back to the side where it started
+        let reflected = crossing.transform.apply(&Coordinate { x: 5.5, y: 1.5 });
+        assert!(approx_eq!(f64, reflected.x, 4.5, epsilon = 1e-9));
+        assert!(approx_eq!(f64, reflected.y, 1.5, epsilon = 1e-9));
+        assert_eq!(crossing.tint, Some(tint));
+        assert_eq!(crossing.max_passes, 2);
+
+        assert!(mirror
+            .portal_crossing(
+                &Coordinate { x: 4.5, y: 5.0 },
+                &Coordinate { x: 5.5, y: 5.0 }
+            )
+            .is_none());
+    }
+}
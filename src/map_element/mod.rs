@@ -1,21 +1,38 @@
+mod circle;
 mod color;
 mod door;
+mod door_area;
 mod half_door;
 mod map_element;
+mod map_elements;
+mod mirror;
 mod point;
+mod portal;
 mod rectangle;
+mod sector;
+mod segment_map;
+mod spatial_grid;
 mod wall_map;
 
+pub use circle::{Circle, CircularArc};
 pub use color::Color;
 pub use door::{Door, DoorType, DoorVelocity};
-pub use map_element::MapElement;
+pub use door_area::DoorArea;
+pub use map_element::{AsAny, MapElement};
+pub use map_elements::{ElementId, MapElements, MapElementsHandle};
+pub use mirror::Mirror;
 pub use point::Point;
+pub use portal::Portal;
 pub use rectangle::Rectangle;
+pub use sector::Sector;
+pub use segment_map::{Segment, SegmentMap};
+pub use spatial_grid::BoundingBox;
 pub use wall_map::WallMap;
 
 cfg_if::cfg_if! {
     if #[cfg(test)]{
         pub use map_element::MockMapElement;
+        pub(crate) use map_element::unbounded_map_element;
         pub use rectangle::MockRectangle;
     }
 }
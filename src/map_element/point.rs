@@ -1,5 +1,3 @@
-use crate::graph::Coordinate;
-
 #[derive(PartialEq, Default, Clone, Debug)]
 pub struct Point {
     pub x: i64,
@@ -25,12 +23,6 @@ impl Point {
     pub(crate) fn distance(&self, point: &Self) -> f64 {
         return (((self.x - point.x).pow(2) + (self.y - point.y).pow(2)) as f64).sqrt();
     }
-
-    pub(crate) fn distance_coor(&self, coordinate: &Coordinate) -> f64 {
-        return ((self.x as f64 - coordinate.x).powf(2.0)
-            + (self.y as f64 - coordinate.y).powf(2.0))
-        .sqrt();
-    }
 }
 
 #[cfg(test)]
@@ -56,12 +48,4 @@ mod tests {
         let distance = 32_f64.sqrt();
         assert_eq!(point_1.distance(&point_2), distance);
     }
-
-    #[test]
-    fn distance_coor() {
-        let point = Point::new_i64(3, 6);
-        let coordinate = Coordinate { x: 5.5, y: 8.5 };
-        let distance = 12.5_f64.sqrt();
-        assert_eq!(point.distance_coor(&coordinate), distance);
-    }
 }
@@ -0,0 +1,173 @@
+use crate::graph::{Coordinate, Crossing, LinearGraph, Transform, Wall, WallHeight};
+use crate::map_element::segment_map::{nearest_crossing, CELL_DIAGONAL};
+use crate::map_element::{BoundingBox, Color, MapElement, Point, Segment};
+
+// ray passes at most this many portals, e.g. between two facing portals
+const MAX_PASSES: usize = 8;
+
+// Pair of connected portal ends. Ray crossing one end continues from the
+// other end, the player walking into one end leaves the other one. The start
+// of one end is connected with the end of the other one, so the player
+// leaves on the same side of the end as they entered. Ends should have the
+// same length, the middle of one end is moved to the middle of the other.
+#[derive(Clone)]
+pub struct Portal {
+    ends: [Segment; 2],
+}
+
+impl Portal {
+    pub fn new(first: Segment, second: Segment) -> Self {
+        Self {
+            ends: [first, second],
+        }
+    }
+
+    pub fn ends(&self) -> &[Segment; 2] {
+        &self.ends
+    }
+
+    // transform which moves the from end onto the to end
+    fn transform(from: &Segment, to: &Segment) -> Transform {
+        let from_angle = (from.end.y - from.start.y).atan2(from.end.x - from.start.x);
+        let to_angle = (to.start.y - to.end.y).atan2(to.start.x - to.end.x);
+        Transform::new(&middle(from), &middle(to), to_angle - from_angle)
+    }
+}
+
+fn middle(segment: &Segment) -> Coordinate {
+    Coordinate {
+        x: (segment.start.x + segment.end.x) / 2.0,
+        y: (segment.start.y + segment.end.y) / 2.0,
+    }
+}
+
+impl MapElement for Portal {
+    fn is_point_in_object(&self, _point: &Point) -> bool {
+        false
+    }
+
+    fn color(&self) -> Color {
+        Color::default()
+    }
+
+    // portal has no walls
+    fn wall_heights(&self) -> Vec<WallHeight> {
+        vec![]
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        let [first, second] = &self.ends;
+        Some(
+            BoundingBox::new(&first.start, &first.end)
+                .join(&BoundingBox::new(&second.start, &second.end))
+                .extend(CELL_DIAGONAL),
+        )
+    }
+
+    fn portal_crossing(&self, from: &Coordinate, to: &Coordinate) -> Option<Crossing> {
+        let (index, point) = nearest_crossing(&self.ends, from, to)?;
+        Some(Crossing {
+            point,
+            transform: Self::transform(&self.ends[index], &self.ends[1 - index]),
+            tint: None,
+            max_passes: MAX_PASSES,
+        })
+    }
+
+    fn is_coordinate_in_object(
+        &self,
+        _coordinate: &Coordinate,
+        _start_position: &Coordinate,
+    ) -> Option<(Wall, LinearGraph)> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    fn segment(start: (f64, f64), end: (f64, f64)) -> Segment {
+        Segment {
+            start: Coordinate {
+                x: start.0,
+                y: start.1,
+            },
+            end: Coordinate { x: end.0, y: end.1 },
+        }
+    }
+
+    fn assert_coordinate(coordinate: &Coordinate, x: f64, y: f64) {
+        assert!(
+            approx_eq!(f64, coordinate.x, x, epsilon = 1e-9)
+                && approx_eq!(f64, coordinate.y, y, epsilon = 1e-9),
+            "{:?} != ({}, {})",
+            coordinate,
+            x,
+            y
+        );
+    }
+
+    #[test]
+    fn portal_crossing() {
+        // second end is turned by a right angle
+        let portal = Portal::new(
+            segment((5.0, 2.0), (5.0, 4.0)),
+            segment((20.0, 10.0), (22.0, 10.0)),
+        );
+
+        let Crossing {
+            point: cross_point,
+            transform,
+            ..
+        } = portal
+            .portal_crossing(
+                &Coordinate { x: 4.5, y: 2.5 },
+                &Coordinate { x: 5.5, y: 2.5 },
+            )
+            .unwrap();
+        assert_coordinate(&cross_point, 5.0, 2.5);
+        // start of the first end is connected with the end of the second one
+        assert_coordinate(&transform.apply(&cross_point), 21.5, 10.0);
+        // move along x axis comes out of the second end along y axis
+        assert_coordinate(&transform.apply(&Coordinate { x: 5.5, y: 2.5 }), 21.5, 10.5);
+
+        // way back leads to the first end
+        let Crossing {
+            point: cross_point,
+            transform,
+            ..
+        } = portal
+            .portal_crossing(
+                &Coordinate { x: 21.5, y: 10.5 },
+                &Coordinate { x: 21.5, y: 9.5 },
+            )
+            .unwrap();
+        assert_coordinate(&transform.apply(&cross_point), 5.0, 2.5);
+        assert_coordinate(&transform.apply(&Coordinate { x: 21.5, y: 9.5 }), 4.5, 2.5);
+    }
+
+    #[test]
+    fn portal_crossing_missed() {
+        let portal = Portal::new(
+            segment((5.0, 2.0), (5.0, 4.0)),
+            segment((20.0, 10.0), (22.0, 10.0)),
+        );
+
+        // move beside the portal
+        assert!(portal
+            .portal_crossing(
+                &Coordinate { x: 4.5, y: 5.0 },
+                &Coordinate { x: 5.5, y: 5.0 }
+            )
+            .is_none());
+        // move which starts on the portal goes out of it
+        assert!(portal
+            .portal_crossing(
+                &Coordinate { x: 5.0, y: 3.0 },
+                &Coordinate { x: 5.5, y: 3.0 }
+            )
+            .is_none());
+    }
+}
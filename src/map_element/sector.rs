@@ -0,0 +1,241 @@
+use crate::graph::{Coordinate, LinearGraph, Surface, Wall, WallHeight};
+use crate::map_element::{BoundingBox, Color, MapElement, Point, Rectangle, SegmentMap};
+
+const DEFAULT_FLOOR_COLOR: Color = Color::Custom([0.55, 0.55, 0.55, 1.0]);
+const DEFAULT_CEILING_COLOR: Color = Color::Custom([0.7, 0.7, 0.7, 1.0]);
+
+// Region of the map with its own floor and ceiling: stairs, raised platforms
+// and sunken pits. Sides of the raised floor are seen from the outside of the
+// sector, sides of the sunken floor from the inside.
+#[derive(Clone)]
+pub struct Sector {
+    area: BoundingBox,
+    floor_height: f64,
+    ceiling_height: Option<f64>,
+    floor_color: Color,
+    ceiling_color: Color,
+    sides: SegmentMap,
+}
+
+impl Sector {
+    // rectangle describes the cells of the sector, both corners are included
+    pub fn new(
+        area: Rectangle,
+        floor_height: f64,
+        ceiling_height: Option<f64>,
+        floor_color: Option<Color>,
+        ceiling_color: Option<Color>,
+    ) -> Self {
+        let area = BoundingBox::new(
+            &Coordinate {
+                x: area.point_a.x.min(area.point_b.x) as f64,
+                y: area.point_a.y.min(area.point_b.y) as f64,
+            },
+            &Coordinate {
+                x: (area.point_a.x.max(area.point_b.x) + 1) as f64,
+                y: (area.point_a.y.max(area.point_b.y) + 1) as f64,
+            },
+        );
+        let floor_color = floor_color.unwrap_or(DEFAULT_FLOOR_COLOR);
+        let mut sides = SegmentMap::from_points(&corners(&area), true, Some(floor_color.clone()));
+        sides.set_wall_height(if floor_height > 0.0 {
+            WallHeight::new(floor_height, 0.0)
+        } else {
+            WallHeight::new(-floor_height, floor_height)
+        });
+        Self {
+            area,
+            floor_height,
+            ceiling_height,
+            floor_color,
+            ceiling_color: ceiling_color.unwrap_or(DEFAULT_CEILING_COLOR),
+            sides,
+        }
+    }
+
+    pub fn floor_height(&self) -> f64 {
+        self.floor_height
+    }
+
+    pub fn ceiling_height(&self) -> Option<f64> {
+        self.ceiling_height
+    }
+
+    pub fn contains(&self, coordinate: &Coordinate) -> bool {
+        self.area.contains(coordinate)
+    }
+}
+
+fn corners(area: &BoundingBox) -> Vec<Coordinate> {
+    vec![
+        area.min.clone(),
+        Coordinate {
+            x: area.max.x,
+            y: area.min.y,
+        },
+        area.max.clone(),
+        Coordinate {
+            x: area.min.x,
+            y: area.max.y,
+        },
+    ]
+}
+
+impl MapElement for Sector {
+    fn is_point_in_object(&self, _point: &Point) -> bool {
+        false
+    }
+
+    fn color(&self) -> Color {
+        self.floor_color.clone()
+    }
+
+    fn wall_height(&self) -> WallHeight {
+        self.sides.wall_height()
+    }
+
+    fn wall_heights(&self) -> Vec<WallHeight> {
+        if self.floor_height == 0.0 {
+            return vec![];
+        }
+        vec![self.wall_height()]
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        self.sides.bounding_box()
+    }
+
+    fn floor_height_at(&self, coordinate: &Coordinate) -> Option<f64> {
+        self.contains(coordinate).then_some(self.floor_height)
+    }
+
+    fn surfaces(&self) -> Vec<Surface> {
+        let mut surfaces = vec![Surface {
+            corners: corners(&self.area),
+            height: self.floor_height,
+            color: self.floor_color.clone(),
+            is_floor: true,
+        }];
+        if let Some(ceiling_height) = self.ceiling_height {
+            surfaces.push(Surface {
+                corners: corners(&self.area),
+                height: ceiling_height,
+                color: self.ceiling_color.clone(),
+                is_floor: false,
+            });
+        }
+        surfaces
+    }
+
+    fn is_coordinate_in_object(
+        &self,
+        coordinate: &Coordinate,
+        start_position: &Coordinate,
+    ) -> Option<(Wall, LinearGraph)> {
+        let is_inside = self.contains(start_position);
+        if (self.floor_height > 0.0 && !is_inside) || (self.floor_height < 0.0 && is_inside) {
+            return self
+                .sides
+                .is_coordinate_in_object(coordinate, start_position);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::MockGraphMethods;
+
+    fn sector(floor_height: f64) -> Sector {
+        Sector::new(
+            Rectangle {
+                point_a: Point { x: 4, y: 3 },
+                point_b: Point { x: 2, y: 5 },
+            },
+            floor_height,
+            Some(3.0),
+            Some(Color::Green),
+            None,
+        )
+    }
+
+    #[test]
+    fn floor_height_at() {
+        let sector = sector(0.5);
+        assert_eq!(
+            sector.floor_height_at(&Coordinate { x: 2.0, y: 5.5 }),
+            Some(0.5)
+        );
+        assert_eq!(
+            sector.floor_height_at(&Coordinate { x: 5.0, y: 6.0 }),
+            Some(0.5)
+        );
+        assert_eq!(sector.floor_height_at(&Coordinate { x: 5.5, y: 4.0 }), None);
+    }
+
+    #[test]
+    fn surfaces() {
+        let corners = vec![
+            Coordinate { x: 2.0, y: 3.0 },
+            Coordinate { x: 5.0, y: 3.0 },
+            Coordinate { x: 5.0, y: 6.0 },
+            Coordinate { x: 2.0, y: 6.0 },
+        ];
+        assert_eq!(
+            sector(-1.0).surfaces(),
+            vec![
+                Surface {
+                    corners: corners.clone(),
+                    height: -1.0,
+                    color: Color::Green,
+                    is_floor: true,
+                },
+                Surface {
+                    corners,
+                    height: 3.0,
+                    color: DEFAULT_CEILING_COLOR,
+                    is_floor: false,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn wall_heights() {
+        assert_eq!(sector(0.5).wall_heights(), vec![WallHeight::new(0.5, 0.0)]);
+        assert_eq!(
+            sector(-1.0).wall_heights(),
+            vec![WallHeight::new(1.0, -1.0)]
+        );
+        assert_eq!(sector(0.0).wall_heights(), vec![]);
+    }
+
+    #[test]
+    fn is_coordinate_in_object() {
+        let from_two_coordinates_context = MockGraphMethods::from_two_coordinates_context();
+        from_two_coordinates_context
+            .expect()
+            .return_const(LinearGraph::default());
+
+        let outside = Coordinate { x: 0.5, y: 4.5 };
+        let inside = Coordinate { x: 3.5, y: 4.5 };
+        let border = Coordinate { x: 2.0, y: 4.5 };
+        let far_border = Coordinate { x: 5.0, y: 4.5 };
+
+        // raised floor is seen only from the outside
+        let raised = sector(0.5);
+        let (wall, _) = raised.is_coordinate_in_object(&border, &outside).unwrap();
+        assert_eq!(wall.height, WallHeight::new(0.5, 0.0));
+        assert_eq!(wall.primary_object_color, Color::Green);
+        assert_eq!(raised.is_coordinate_in_object(&far_border, &inside), None);
+
+        // sunken floor is seen only from the inside
+        let sunken = sector(-1.0);
+        assert_eq!(sunken.is_coordinate_in_object(&border, &outside), None);
+        let (wall, _) = sunken
+            .is_coordinate_in_object(&far_border, &inside)
+            .unwrap();
+        assert_eq!(wall.height, WallHeight::new(1.0, -1.0));
+    }
+}
@@ -0,0 +1,457 @@
+use crate::graph::{Coordinate, LinearGraph, Wall, WallHeight};
+use crate::map_element::{BoundingBox, Color, MapElement, Point};
+use mockall_double::double;
+
+#[double]
+use crate::graph::GraphMethods;
+
+// returned ray goes a bit past the end of the wall
+const RAY_OFFSET: f64 = 0.0001;
+// cross point closer than this to the wall start is moved to the wall start
+const SNAP_DISTANCE: f64 = 0.001;
+// cross point is always in the last grid cell before the checked coordinate
+pub(crate) const CELL_DIAGONAL: f64 = 1.5;
+// crossing closer than this to the start of the move is not a crossing, so
+// the ray leaving the portal or the mirror doesn't go back through it
+const MIN_CROSS_DISTANCE: f64 = 1e-6;
+
+fn cross(first: (f64, f64), second: (f64, f64)) -> f64 {
+    first.0 * second.1 - first.1 * second.0
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Segment {
+    pub start: Coordinate,
+    pub end: Coordinate,
+}
+
+impl Segment {
+    // Returns part of the ray (0, 1] from start_position to coordinate where
+    // the ray crosses the segment.
+    fn cross_ray(&self, coordinate: &Coordinate, start_position: &Coordinate) -> Option<f64> {
+        let ray = (
+            coordinate.x - start_position.x,
+            coordinate.y - start_position.y,
+        );
+        let segment_vector = (self.end.x - self.start.x, self.end.y - self.start.y);
+        let denominator = cross(ray, segment_vector);
+        if denominator == 0.0 {
+            return None;
+        }
+        let start_delta = (
+            self.start.x - start_position.x,
+            self.start.y - start_position.y,
+        );
+        let ray_part = cross(start_delta, segment_vector) / denominator;
+        let segment_part = cross(start_delta, ray) / denominator;
+        if ray_part <= 0.0 || ray_part > 1.0 || !(0.0..=1.0).contains(&segment_part) {
+            return None;
+        }
+        Some(ray_part)
+    }
+
+    // start of the wall is on the right side of the end when looking from the start position
+    fn oriented(&self, start_position: &Coordinate) -> Option<(Coordinate, Coordinate)> {
+        let orientation = cross(
+            (
+                self.start.x - start_position.x,
+                self.start.y - start_position.y,
+            ),
+            (self.end.x - start_position.x, self.end.y - start_position.y),
+        );
+        if orientation > 0.0 {
+            return Some((self.start.clone(), self.end.clone()));
+        } else if orientation < 0.0 {
+            return Some((self.end.clone(), self.start.clone()));
+        }
+        None
+    }
+}
+
+// Walls made of line segments placed at any angle. Every segment is intersected
+// with the ray and returned as one Wall, so sloped walls aren't split into steps.
+#[derive(Clone)]
+pub struct SegmentMap {
+    segments: Vec<Segment>,
+    color: Color,
+    wall_height: WallHeight,
+}
+
+impl SegmentMap {
+    pub fn new(segments: Vec<Segment>, color: Option<Color>) -> Self {
+        Self {
+            segments,
+            color: color.unwrap_or(Color::Orange),
+            wall_height: WallHeight::default(),
+        }
+    }
+
+    pub fn set_wall_height(&mut self, wall_height: WallHeight) {
+        self.wall_height = wall_height;
+    }
+
+    // connects the following points, the last point is connected with the first one when closed
+    pub fn from_points(points: &[Coordinate], closed: bool, color: Option<Color>) -> Self {
+        let mut segments: Vec<Segment> = points
+            .windows(2)
+            .map(|points| Segment {
+                start: points[0].clone(),
+                end: points[1].clone(),
+            })
+            .collect();
+        if closed && points.len() > 2 {
+            segments.push(Segment {
+                start: points[points.len() - 1].clone(),
+                end: points[0].clone(),
+            });
+        }
+        Self::new(segments, color)
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    fn is_near(segment: &Segment, coordinate: &Coordinate) -> bool {
+        BoundingBox::new(&segment.start, &segment.end)
+            .extend(CELL_DIAGONAL)
+            .contains(coordinate)
+    }
+}
+
+// Returns the wall made from the nearest segment crossed by the ray.
+pub(crate) fn nearest_wall<'a>(
+    segments: impl Iterator<Item = &'a Segment>,
+    coordinate: &Coordinate,
+    start_position: &Coordinate,
+    color: Color,
+    height: WallHeight,
+) -> Option<(Wall, LinearGraph)> {
+    let (segment, ray_part) = segments
+        .filter_map(|segment| Some((segment, segment.cross_ray(coordinate, start_position)?)))
+        .min_by(|(_, first), (_, second)| first.total_cmp(second))?;
+    let (segment_start, segment_end) = segment.oriented(start_position)?;
+
+    let mut cross_point = Coordinate {
+        x: start_position.x + (coordinate.x - start_position.x) * ray_part,
+        y: start_position.y + (coordinate.y - start_position.y) * ray_part,
+    };
+    if cross_point.distance(&segment_start) < SNAP_DISTANCE {
+        cross_point = segment_start.clone();
+    }
+    let length = segment_start.distance(&segment_end);
+    let ray_end = Coordinate {
+        x: segment_end.x + (segment_end.x - segment_start.x) / length * RAY_OFFSET,
+        y: segment_end.y + (segment_end.y - segment_start.y) / length * RAY_OFFSET,
+    };
+    Some((
+        Wall {
+            start_point: cross_point,
+            end_point: segment_end,
+            primary_object_color: color,
+            height,
+        },
+        GraphMethods::from_two_coordinates(start_position, ray_end),
+    ))
+}
+
+// Returns the index of the nearest segment crossed by the move and the
+// crossing point.
+pub(crate) fn nearest_crossing(
+    segments: &[Segment],
+    from: &Coordinate,
+    to: &Coordinate,
+) -> Option<(usize, Coordinate)> {
+    let min_part = MIN_CROSS_DISTANCE / from.distance(to);
+    let (index, part) = segments
+        .iter()
+        .enumerate()
+        .filter_map(|(index, segment)| Some((index, segment.cross_ray(to, from)?)))
+        .filter(|(_, part)| *part > min_part)
+        .min_by(|(_, first), (_, second)| first.total_cmp(second))?;
+    Some((
+        index,
+        Coordinate {
+            x: from.x + (to.x - from.x) * part,
+            y: from.y + (to.y - from.y) * part,
+        },
+    ))
+}
+
+impl MapElement for SegmentMap {
+    fn is_point_in_object(&self, _point: &Point) -> bool {
+        false
+    }
+
+    fn color(&self) -> Color {
+        self.color.clone()
+    }
+
+    fn wall_height(&self) -> WallHeight {
+        self.wall_height
+    }
+
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        let bounding_box = self
+            .segments
+            .iter()
+            .map(|segment| BoundingBox::new(&segment.start, &segment.end))
+            .fold(
+                None,
+                |joined: Option<BoundingBox>, bounding_box| match joined {
+                    Some(joined) => Some(joined.join(&bounding_box)),
+                    None => Some(bounding_box),
+                },
+            )
+            .unwrap_or_default();
+        Some(bounding_box.extend(CELL_DIAGONAL))
+    }
+
+    fn is_coordinate_in_object(
+        &self,
+        coordinate: &Coordinate,
+        start_position: &Coordinate,
+    ) -> Option<(Wall, LinearGraph)> {
+        nearest_wall(
+            self.segments
+                .iter()
+                .filter(|segment| Self::is_near(segment, coordinate)),
+            coordinate,
+            start_position,
+            self.color(),
+            self.wall_height(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(non_upper_case_globals)]
+    use super::*;
+    use crate::graph::MockGraphMethods;
+    use float_cmp::approx_eq;
+    use lazy_static::*;
+
+    lazy_static! {
+        static ref linear_graph: LinearGraph = LinearGraph::default();
+        static ref start_position: Coordinate = Coordinate { x: 1.5, y: 1.5 };
+    }
+
+    fn diagonal_segment() -> Segment {
+        Segment {
+            start: Coordinate { x: 4.0, y: 1.0 },
+            end: Coordinate { x: 1.0, y: 4.0 },
+        }
+    }
+
+    fn is_ray(end: Coordinate) -> impl Fn(&Coordinate, &Coordinate) -> bool + Send + 'static {
+        move |start, ray_end| {
+            *start == *start_position
+                && approx_eq!(f64, ray_end.x, end.x, epsilon = 1e-9)
+                && approx_eq!(f64, ray_end.y, end.y, epsilon = 1e-9)
+        }
+    }
+
+    #[test]
+    fn from_points() {
+        let points = [
+            Coordinate { x: 1.0, y: 1.0 },
+            Coordinate { x: 5.0, y: 1.0 },
+            Coordinate { x: 3.0, y: 4.0 },
+        ];
+        let open = SegmentMap::from_points(&points, false, None);
+        assert_eq!(open.color(), Color::Orange);
+        assert_eq!(
+            open.segments(),
+            &[
+                Segment {
+                    start: points[0].clone(),
+                    end: points[1].clone()
+                },
+                Segment {
+                    start: points[1].clone(),
+                    end: points[2].clone()
+                },
+            ]
+        );
+        let closed = SegmentMap::from_points(&points, true, Some(Color::Red));
+        assert_eq!(closed.color(), Color::Red);
+        assert_eq!(closed.segments().len(), 3);
+        assert_eq!(
+            closed.segments()[2],
+            Segment {
+                start: points[2].clone(),
+                end: points[0].clone()
+            }
+        );
+    }
+
+    #[test]
+    fn bounding_box() {
+        let segment_map = SegmentMap::new(
+            vec![
+                diagonal_segment(),
+                Segment {
+                    start: Coordinate { x: 3.0, y: 0.0 },
+                    end: Coordinate { x: 3.0, y: 3.0 },
+                },
+            ],
+            None,
+        );
+        assert_eq!(
+            segment_map.bounding_box(),
+            Some(BoundingBox {
+                min: Coordinate { x: -0.5, y: -1.5 },
+                max: Coordinate { x: 5.5, y: 5.5 },
+            })
+        );
+    }
+
+    #[test]
+    fn is_coordinate_in_object_diagonal() {
+        let from_two_coordinates_context = MockGraphMethods::from_two_coordinates_context();
+        let offset = RAY_OFFSET / 2.0_f64.sqrt();
+        from_two_coordinates_context
+            .expect()
+            .times(1)
+            .withf(is_ray(Coordinate {
+                x: 1.0 - offset,
+                y: 4.0 + offset,
+            }))
+            .return_const(linear_graph.clone());
+        from_two_coordinates_context
+            .expect()
+            .times(1)
+            .withf(is_ray(Coordinate {
+                x: 1.0 - offset,
+                y: 4.0 + offset,
+            }))
+            .return_const(linear_graph.clone());
+        let expected_wall = Wall {
+            start_point: Coordinate { x: 2.5, y: 2.5 },
+            end_point: Coordinate { x: 1.0, y: 4.0 },
+            primary_object_color: Color::Green,
+            height: Default::default(),
+        };
+
+        let segment_map = SegmentMap::new(vec![diagonal_segment()], Some(Color::Green));
+        assert_eq!(
+            segment_map.is_coordinate_in_object(&Coordinate { x: 2.0, y: 2.0 }, &start_position),
+            None
+        );
+        assert_eq!(
+            segment_map.is_coordinate_in_object(&Coordinate { x: 3.0, y: 3.0 }, &start_position),
+            Some((expected_wall.clone(), linear_graph.clone()))
+        );
+
+        // orientation doesn't depend on the order of the segment points
+        let reversed = Segment {
+            start: diagonal_segment().end,
+            end: diagonal_segment().start,
+        };
+        let segment_map = SegmentMap::new(vec![reversed], Some(Color::Green));
+        assert_eq!(
+            segment_map.is_coordinate_in_object(&Coordinate { x: 3.0, y: 3.0 }, &start_position),
+            Some((expected_wall, linear_graph.clone()))
+        );
+    }
+
+    #[test]
+    fn is_coordinate_in_object_wall_start() {
+        let from_two_coordinates_context = MockGraphMethods::from_two_coordinates_context();
+        let offset = RAY_OFFSET / 2.0_f64.sqrt();
+        from_two_coordinates_context
+            .expect()
+            .times(1)
+            .withf(is_ray(Coordinate {
+                x: 1.0 - offset,
+                y: 4.0 + offset,
+            }))
+            .return_const(linear_graph.clone());
+
+        let segment_map = SegmentMap::new(vec![diagonal_segment()], None);
+        assert_eq!(
+            segment_map.is_coordinate_in_object(
+                &Coordinate {
+                    x: 4.0 - SNAP_DISTANCE / 4.0,
+                    y: 1.0 + SNAP_DISTANCE / 4.0
+                },
+                &start_position
+            ),
+            Some((
+                Wall {
+                    start_point: Coordinate { x: 4.0, y: 1.0 },
+                    end_point: Coordinate { x: 1.0, y: 4.0 },
+                    primary_object_color: Color::Orange,
+                    height: Default::default(),
+                },
+                linear_graph.clone()
+            ))
+        );
+    }
+
+    #[test]
+    fn is_coordinate_in_object_nearest_segment() {
+        let from_two_coordinates_context = MockGraphMethods::from_two_coordinates_context();
+        from_two_coordinates_context
+            .expect()
+            .times(1)
+            .withf(is_ray(Coordinate {
+                x: 3.0,
+                y: 3.0 + RAY_OFFSET,
+            }))
+            .return_const(linear_graph.clone());
+
+        let segment_map = SegmentMap::new(
+            vec![
+                diagonal_segment(),
+                Segment {
+                    start: Coordinate { x: 3.0, y: 0.0 },
+                    end: Coordinate { x: 3.0, y: 3.0 },
+                },
+            ],
+            None,
+        );
+        assert_eq!(
+            segment_map.is_coordinate_in_object(&Coordinate { x: 3.0, y: 1.5 }, &start_position),
+            Some((
+                Wall {
+                    start_point: Coordinate { x: 3.0, y: 1.5 },
+                    end_point: Coordinate { x: 3.0, y: 3.0 },
+                    primary_object_color: Color::Orange,
+                    height: Default::default(),
+                },
+                linear_graph.clone()
+            ))
+        );
+    }
+
+    #[test]
+    fn is_coordinate_in_object_missed() {
+        let segment_map = SegmentMap::new(
+            vec![
+                diagonal_segment(),
+                // parallel to the ray
+                Segment {
+                    start: Coordinate { x: 0.0, y: 0.0 },
+                    end: Coordinate { x: 3.0, y: 3.0 },
+                },
+                // behind the start position
+                Segment {
+                    start: Coordinate { x: 1.0, y: 0.0 },
+                    end: Coordinate { x: 0.0, y: 1.0 },
+                },
+            ],
+            None,
+        );
+        assert_eq!(
+            segment_map.is_coordinate_in_object(&Coordinate { x: 2.0, y: 2.0 }, &start_position),
+            None
+        );
+        assert_eq!(
+            segment_map.is_coordinate_in_object(&Coordinate { x: 1.0, y: 1.0 }, &start_position),
+            None
+        );
+        assert!(!segment_map.is_point_in_object(&Point { x: 2, y: 2 }));
+    }
+}
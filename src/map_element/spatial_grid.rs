@@ -0,0 +1,199 @@
+use crate::graph::Coordinate;
+use std::collections::HashMap;
+
+// size of the grid cell in the map units
+const CELL_SIZE: f64 = 8.0;
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct BoundingBox {
+    pub min: Coordinate,
+    pub max: Coordinate,
+}
+
+impl BoundingBox {
+    pub fn new(first: &Coordinate, second: &Coordinate) -> Self {
+        Self {
+            min: Coordinate {
+                x: first.x.min(second.x),
+                y: first.y.min(second.y),
+            },
+            max: Coordinate {
+                x: first.x.max(second.x),
+                y: first.y.max(second.y),
+            },
+        }
+    }
+
+    // returns the smallest box which contains both boxes
+    pub fn join(&self, other: &BoundingBox) -> Self {
+        Self {
+            min: Coordinate {
+                x: self.min.x.min(other.min.x),
+                y: self.min.y.min(other.min.y),
+            },
+            max: Coordinate {
+                x: self.max.x.max(other.max.x),
+                y: self.max.y.max(other.max.y),
+            },
+        }
+    }
+
+    // returns the box extended in every direction by the margin
+    pub fn extend(&self, margin: f64) -> Self {
+        Self {
+            min: Coordinate {
+                x: self.min.x - margin,
+                y: self.min.y - margin,
+            },
+            max: Coordinate {
+                x: self.max.x + margin,
+                y: self.max.y + margin,
+            },
+        }
+    }
+
+    fn is_finite(&self) -> bool {
+        self.min.x.is_finite()
+            && self.min.y.is_finite()
+            && self.max.x.is_finite()
+            && self.max.y.is_finite()
+    }
+
+    pub fn contains(&self, coordinate: &Coordinate) -> bool {
+        self.min.x <= coordinate.x
+            && coordinate.x <= self.max.x
+            && self.min.y <= coordinate.y
+            && coordinate.y <= self.max.y
+    }
+}
+
+fn cell(coordinate: &Coordinate) -> (i64, i64) {
+    (
+        (coordinate.x / CELL_SIZE).floor() as i64,
+        (coordinate.y / CELL_SIZE).floor() as i64,
+    )
+}
+
+// Uniform grid of the map element bounding boxes. Every cell keeps indexes of
+// the elements which overlap it, sorted in the insertion order. Elements
+// without the bounding box or with the box which is not finite are placed in
+// every cell.
+#[derive(Default)]
+pub(crate) struct SpatialGrid {
+    bounding_boxes: Vec<Option<BoundingBox>>,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    unbounded: Vec<usize>,
+}
+
+impl SpatialGrid {
+    pub(crate) fn new(bounding_boxes: Vec<Option<BoundingBox>>) -> Self {
+        let mut cells: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        let mut unbounded = vec![];
+        for (index, bounding_box) in bounding_boxes.iter().enumerate() {
+            match bounding_box {
+                Some(bounding_box) if bounding_box.is_finite() => {
+                    let (min_x, min_y) = cell(&bounding_box.min);
+                    let (max_x, max_y) = cell(&bounding_box.max);
+                    for x in min_x..=max_x {
+                        for y in min_y..=max_y {
+                            cells
+                                .entry((x, y))
+                                .or_insert_with(|| unbounded.clone())
+                                .push(index);
+                        }
+                    }
+                }
+                _ => {
+                    unbounded.push(index);
+                    for indexes in cells.values_mut() {
+                        indexes.push(index);
+                    }
+                }
+            }
+        }
+        Self {
+            bounding_boxes,
+            cells,
+            unbounded,
+        }
+    }
+
+    pub(crate) fn bounding_boxes(&self) -> &[Option<BoundingBox>] {
+        &self.bounding_boxes
+    }
+
+    // indexes of the elements which can have a wall at the coordinate
+    pub(crate) fn indexes(&self, coordinate: &Coordinate) -> &[usize] {
+        self.cells.get(&cell(coordinate)).unwrap_or(&self.unbounded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn some_box(min: (f64, f64), max: (f64, f64)) -> Option<BoundingBox> {
+        Some(BoundingBox {
+            min: Coordinate { x: min.0, y: min.1 },
+            max: Coordinate { x: max.0, y: max.1 },
+        })
+    }
+
+    #[test]
+    fn bounding_box() {
+        let bounding_box = BoundingBox::new(
+            &Coordinate { x: 5.0, y: 1.0 },
+            &Coordinate { x: 2.0, y: 4.0 },
+        );
+        assert_eq!(
+            bounding_box,
+            BoundingBox {
+                min: Coordinate { x: 2.0, y: 1.0 },
+                max: Coordinate { x: 5.0, y: 4.0 },
+            }
+        );
+        assert!(bounding_box.contains(&Coordinate { x: 5.0, y: 2.5 }));
+        assert!(!bounding_box.contains(&Coordinate { x: 5.5, y: 2.5 }));
+        assert!(bounding_box
+            .extend(1.0)
+            .contains(&Coordinate { x: 5.5, y: 0.0 }));
+        assert_eq!(
+            bounding_box.join(&BoundingBox::new(
+                &Coordinate { x: 3.0, y: 0.0 },
+                &Coordinate { x: 4.0, y: 2.0 }
+            )),
+            BoundingBox {
+                min: Coordinate { x: 2.0, y: 0.0 },
+                max: Coordinate { x: 5.0, y: 4.0 },
+            }
+        );
+    }
+
+    #[test]
+    fn indexes() {
+        let grid = SpatialGrid::new(vec![
+            some_box((1.0, 1.0), (6.0, 6.0)),
+            None,
+            some_box((4.0, 4.0), (12.0, 5.0)),
+            some_box((30.0, 30.0), (31.0, 31.0)),
+            None,
+        ]);
+        assert_eq!(grid.indexes(&Coordinate { x: 2.0, y: 2.0 }), &[0, 1, 2, 4]);
+        assert_eq!(grid.indexes(&Coordinate { x: 8.0, y: 4.5 }), &[1, 2, 4]);
+        assert_eq!(grid.indexes(&Coordinate { x: 30.5, y: 31.0 }), &[1, 3, 4]);
+        assert_eq!(grid.indexes(&Coordinate { x: 20.0, y: 20.0 }), &[1, 4]);
+        assert_eq!(grid.indexes(&Coordinate { x: -3.0, y: 2.0 }), &[1, 4]);
+        assert_eq!(grid.bounding_boxes().len(), 5);
+    }
+
+    #[test]
+    fn not_finite_is_unbounded() {
+        let grid = SpatialGrid::new(vec![
+            some_box((1.0, 1.0), (2.0, 2.0)),
+            some_box((0.0, 0.0), (f64::INFINITY, 4.0)),
+            some_box((f64::NAN, 0.0), (1.0, 1.0)),
+        ]);
+        assert_eq!(grid.indexes(&Coordinate { x: 1.5, y: 1.5 }), &[0, 1, 2]);
+        assert_eq!(grid.indexes(&Coordinate { x: -50.0, y: 90.0 }), &[1, 2]);
+    }
+}
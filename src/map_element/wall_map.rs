@@ -1,3 +1,4 @@
+use crate::graph::WallHeight;
 use crate::map_element::{Color, MapElement, Point};
 use crate::Map;
 use std::path::Path;
@@ -5,10 +6,25 @@ use std::path::Path;
 #[cfg(test)]
 use crate::graph::Coordinate;
 
+// luminance of the height map is rounded to this many levels, every level
+// present in the image is rendered as a separate layer
+const HEIGHT_LEVELS: usize = 16;
+
+// level 0 is the floor, the brightest pixel has the highest level
+fn height_level(pixel: &image::Rgba<u8>) -> usize {
+    let luminance = 0.299 * pixel[0] as f64 + 0.587 * pixel[1] as f64 + 0.114 * pixel[2] as f64;
+    (luminance / 255.0 * HEIGHT_LEVELS as f64).round() as usize
+}
+
 #[derive(Clone)]
 pub struct WallMap {
     image: image::RgbaImage,
     color: Color,
+    wall_height: WallHeight,
+    // height of the white pixel when the luminance is used as the wall height
+    height_map: Option<f64>,
+    // amount of the pixels on every height level
+    level_counts: Vec<usize>,
 }
 
 impl WallMap {
@@ -17,9 +33,54 @@ impl WallMap {
         return Ok(Self {
             image: pic.to_rgba(),
             color: color.unwrap_or(Color::Orange),
+            wall_height: WallHeight::default(),
+            height_map: None,
+            level_counts: vec![],
         });
     }
 
+    pub fn from_image(image: image::RgbaImage, color: Option<Color>) -> Self {
+        Self {
+            image,
+            color: color.unwrap_or(Color::Orange),
+            wall_height: WallHeight::default(),
+            height_map: None,
+            level_counts: vec![],
+        }
+    }
+
+    pub fn set_wall_height(&mut self, wall_height: WallHeight) {
+        self.wall_height = wall_height;
+    }
+
+    // Uses the pixel luminance as the wall height, white pixel is max_height
+    // high and black pixel is the floor. None goes back to the black walls.
+    // Elevation of the walls is taken from the wall height.
+    pub fn set_height_map(&mut self, max_height: Option<f64>) {
+        self.height_map = max_height;
+        self.level_counts = vec![0; HEIGHT_LEVELS + 1];
+        if max_height.is_some() {
+            for pixel in self.image.pixels() {
+                self.level_counts[height_level(pixel)] += 1;
+            }
+        }
+    }
+
+    pub fn height_map(&self) -> Option<f64> {
+        self.height_map
+    }
+
+    fn level_height(&self, level: usize, max_height: f64) -> WallHeight {
+        WallHeight::new(
+            max_height * level as f64 / HEIGHT_LEVELS as f64,
+            self.wall_height.elevation,
+        )
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        self.image.save(path)
+    }
+
     #[cfg(test)]
     fn validate_coordinate(&self, coordinate: &Coordinate) -> bool {
         if coordinate.x < 0.0
@@ -36,6 +97,7 @@ impl WallMap {
         Map {
             width: self.image.width() as i64,
             height: self.image.height() as i64,
+            max_ray_distance: None,
         }
     }
 
@@ -43,16 +105,75 @@ impl WallMap {
         let pixel = self.image.get_pixel(x, y);
         pixel[0] < 100 && pixel[1] < 100 && pixel[2] < 100
     }
+
+    // every pixel above the floor is a wall in the height map
+    pub fn is_wall_pixel(&self, x: u32, y: u32) -> bool {
+        match self.height_map {
+            Some(_) => height_level(self.image.get_pixel(x, y)) > 0,
+            None => self.is_black_pixel(x, y),
+        }
+    }
 }
 
 impl MapElement for WallMap {
     fn is_point_in_object(&self, point: &Point) -> bool {
-        self.is_black_pixel(point.x as u32, point.y as u32)
+        self.is_wall_pixel(point.x as u32, point.y as u32)
     }
 
     fn color(&self) -> Color {
         self.color.clone()
     }
+
+    fn wall_height(&self) -> WallHeight {
+        self.wall_height
+    }
+
+    fn wall_height_at(&self, point: &Point) -> WallHeight {
+        match self.height_map {
+            Some(max_height) => self.level_height(
+                height_level(self.image.get_pixel(point.x as u32, point.y as u32)),
+                max_height,
+            ),
+            None => self.wall_height,
+        }
+    }
+
+    fn wall_heights(&self) -> Vec<WallHeight> {
+        match self.height_map {
+            Some(max_height) => self
+                .level_counts
+                .iter()
+                .enumerate()
+                .skip(1)
+                .filter(|(_, count)| **count > 0)
+                .map(|(level, _)| self.level_height(level, max_height))
+                .collect(),
+            None => vec![self.wall_height],
+        }
+    }
+
+    fn set_wall(&mut self, point: &Point, is_wall: bool) -> bool {
+        if point.x < 0
+            || point.y < 0
+            || point.x >= self.image.width() as i64
+            || point.y >= self.image.height() as i64
+        {
+            return false;
+        }
+        // walls of the height map are painted with the highest level
+        let pixel = if is_wall != self.height_map.is_some() {
+            image::Rgba([0, 0, 0, 255])
+        } else {
+            image::Rgba([255, 255, 255, 255])
+        };
+        if self.height_map.is_some() {
+            let old_pixel = self.image.get_pixel(point.x as u32, point.y as u32);
+            self.level_counts[height_level(old_pixel)] -= 1;
+            self.level_counts[height_level(&pixel)] += 1;
+        }
+        self.image.put_pixel(point.x as u32, point.y as u32, pixel);
+        true
+    }
 }
 
 #[cfg(test)]
@@ -126,6 +247,111 @@ mod tests {
         }
     }
 
+    #[test]
+    fn from_image() {
+        let mut image = image::RgbaImage::from_pixel(4, 3, image::Rgba([255, 255, 255, 255]));
+        image.put_pixel(2, 1, image::Rgba([0, 0, 0, 255]));
+
+        let wall_map = WallMap::from_image(image, None);
+        assert_eq!(wall_map.color(), Color::Orange);
+        assert_eq!(wall_map.is_point_in_object(&Point { x: 2, y: 1 }), true);
+        assert_eq!(wall_map.is_point_in_object(&Point { x: 1, y: 2 }), false);
+
+        let map = wall_map.get_map();
+        assert_eq!(map.width, 4);
+        assert_eq!(map.height, 3);
+    }
+
+    #[test]
+    fn set_wall() {
+        let image = image::RgbaImage::from_pixel(4, 3, image::Rgba([255, 255, 255, 255]));
+        let mut wall_map = WallMap::from_image(image, None);
+
+        assert_eq!(wall_map.set_wall(&Point { x: 3, y: 2 }, true), true);
+        assert_eq!(wall_map.is_point_in_object(&Point { x: 3, y: 2 }), true);
+        assert_eq!(wall_map.set_wall(&Point { x: 3, y: 2 }, false), true);
+        assert_eq!(wall_map.is_point_in_object(&Point { x: 3, y: 2 }), false);
+
+        assert_eq!(wall_map.set_wall(&Point { x: 4, y: 2 }, true), false);
+        assert_eq!(wall_map.set_wall(&Point { x: 0, y: 3 }, true), false);
+        assert_eq!(wall_map.set_wall(&Point { x: -1, y: 0 }, true), false);
+    }
+
+    #[test]
+    fn height_map() {
+        let mut image = image::RgbaImage::from_pixel(4, 3, image::Rgba([0, 0, 0, 255]));
+        image.put_pixel(1, 1, image::Rgba([128, 128, 128, 255]));
+        image.put_pixel(2, 1, image::Rgba([255, 255, 255, 255]));
+        let mut wall_map = WallMap::from_image(image, None);
+        wall_map.set_wall_height(WallHeight {
+            height: None,
+            elevation: 0.5,
+        });
+        wall_map.set_height_map(Some(8.0));
+
+        assert_eq!(wall_map.is_point_in_object(&Point { x: 0, y: 0 }), false);
+        assert_eq!(wall_map.is_point_in_object(&Point { x: 1, y: 1 }), true);
+        assert_eq!(
+            wall_map.wall_height_at(&Point { x: 1, y: 1 }),
+            WallHeight::new(4.0, 0.5)
+        );
+        assert_eq!(
+            wall_map.wall_height_at(&Point { x: 2, y: 1 }),
+            WallHeight::new(8.0, 0.5)
+        );
+        assert_eq!(
+            wall_map.wall_heights(),
+            vec![WallHeight::new(4.0, 0.5), WallHeight::new(8.0, 0.5)]
+        );
+
+        // painted walls have the highest level
+        assert_eq!(wall_map.set_wall(&Point { x: 1, y: 1 }, false), true);
+        assert_eq!(wall_map.set_wall(&Point { x: 3, y: 2 }, true), true);
+        assert_eq!(wall_map.is_point_in_object(&Point { x: 1, y: 1 }), false);
+        assert_eq!(wall_map.is_point_in_object(&Point { x: 3, y: 2 }), true);
+        assert_eq!(wall_map.wall_heights(), vec![WallHeight::new(8.0, 0.5)]);
+
+        wall_map.set_height_map(None);
+        assert_eq!(wall_map.is_point_in_object(&Point { x: 0, y: 0 }), true);
+        assert_eq!(
+            wall_map.wall_heights(),
+            vec![WallHeight {
+                height: None,
+                elevation: 0.5
+            }]
+        );
+    }
+
+    #[test]
+    fn is_coordinate_in_object_cell_ahead() {
+        let mut image = image::RgbaImage::from_pixel(4, 3, image::Rgba([0, 0, 0, 255]));
+        image.put_pixel(1, 1, image::Rgba([128, 128, 128, 255]));
+        image.put_pixel(2, 1, image::Rgba([255, 255, 255, 255]));
+        let mut wall_map = WallMap::from_image(image, None);
+        wall_map.set_height_map(Some(8.0));
+
+        let from_two_coordinates_context = MockGraphMethods::from_two_coordinates_context();
+        from_two_coordinates_context
+            .expect()
+            .return_const(LinearGraph::default());
+
+        // both cells next to the line are walls, the one ahead of the ray is returned
+        let coordinate = Coordinate { x: 2.0, y: 1.5 };
+        let wall_height = |start_position: Coordinate| {
+            wall_map
+                .is_coordinate_in_object(&coordinate, &start_position)
+                .map(|(wall, _)| wall.height)
+        };
+        assert_eq!(
+            wall_height(Coordinate { x: 0.5, y: 1.5 }),
+            Some(WallHeight::new(8.0, 0.0))
+        );
+        assert_eq!(
+            wall_height(Coordinate { x: 3.5, y: 1.5 }),
+            Some(WallHeight::new(4.0, 0.0))
+        );
+    }
+
     #[test]
     fn new_map_file_not_found() {
         if let Err(image::ImageError::IoError(err)) =
@@ -186,15 +412,16 @@ mod tests {
                 wall_map.is_coordinate_in_object(&coordinate_1, &start_position),
                 Some((
                     Wall {
-                        start_point: Point {
-                            x: coordinate_1.x.ceil() as i64,
-                            y: coordinate_1.y as i64
+                        start_point: Coordinate {
+                            x: coordinate_1.x.ceil(),
+                            y: coordinate_1.y
                         },
-                        end_point: Point {
-                            x: coordinate_1.x.floor() as i64,
-                            y: coordinate_1.y as i64
+                        end_point: Coordinate {
+                            x: coordinate_1.x.floor(),
+                            y: coordinate_1.y
                         },
-                        primary_object_color: color.clone()
+                        primary_object_color: color.clone(),
+                        height: Default::default(),
                     },
                     linear_graph.clone()
                 ))
@@ -216,15 +443,16 @@ mod tests {
                 wall_map.is_coordinate_in_object(&coordinate_2, &start_position),
                 Some((
                     Wall {
-                        start_point: Point {
-                            x: coordinate_2.x.floor() as i64,
-                            y: coordinate_2.y as i64
+                        start_point: Coordinate {
+                            x: coordinate_2.x.floor(),
+                            y: coordinate_2.y
                         },
-                        end_point: Point {
-                            x: coordinate_2.x.ceil() as i64,
-                            y: coordinate_2.y as i64
+                        end_point: Coordinate {
+                            x: coordinate_2.x.ceil(),
+                            y: coordinate_2.y
                         },
-                        primary_object_color: color.clone()
+                        primary_object_color: color.clone(),
+                        height: Default::default(),
                     },
                     linear_graph.clone()
                 ))
@@ -246,15 +474,16 @@ mod tests {
                 wall_map.is_coordinate_in_object(&coordinate_3, &start_position),
                 Some((
                     Wall {
-                        start_point: Point {
-                            x: coordinate_3.x as i64,
-                            y: coordinate_3.y.floor() as i64
+                        start_point: Coordinate {
+                            x: coordinate_3.x,
+                            y: coordinate_3.y.floor()
                         },
-                        end_point: Point {
-                            x: coordinate_3.x as i64,
-                            y: coordinate_3.y.ceil() as i64
+                        end_point: Coordinate {
+                            x: coordinate_3.x,
+                            y: coordinate_3.y.ceil()
                         },
-                        primary_object_color: color.clone()
+                        primary_object_color: color.clone(),
+                        height: Default::default(),
                     },
                     linear_graph.clone()
                 ))
@@ -276,15 +505,16 @@ mod tests {
                 wall_map.is_coordinate_in_object(&coordinate_4, &start_position),
                 Some((
                     Wall {
-                        start_point: Point {
-                            x: coordinate_4.x as i64,
-                            y: coordinate_4.y.ceil() as i64
+                        start_point: Coordinate {
+                            x: coordinate_4.x,
+                            y: coordinate_4.y.ceil()
                         },
-                        end_point: Point {
-                            x: coordinate_4.x as i64,
-                            y: coordinate_4.y.floor() as i64
+                        end_point: Coordinate {
+                            x: coordinate_4.x,
+                            y: coordinate_4.y.floor()
                         },
-                        primary_object_color: color.clone()
+                        primary_object_color: color.clone(),
+                        height: Default::default(),
                     },
                     linear_graph.clone()
                 ))
@@ -0,0 +1,153 @@
+use super::grid::{Grid, Tile};
+use super::random::Random;
+
+const INITIAL_WALL_CHANCE: f64 = 0.45;
+const SMOOTHING_STEPS: usize = 5;
+const WALL_NEIGHBOURS_LIMIT: usize = 5;
+
+fn walls_around(grid: &Grid, x: usize, y: usize) -> usize {
+    let mut walls = 0;
+    for neighbour_y in y - 1..=y + 1 {
+        for neighbour_x in x - 1..=x + 1 {
+            if grid.is_wall(neighbour_x, neighbour_y) {
+                walls += 1;
+            }
+        }
+    }
+    walls
+}
+
+fn smooth(grid: &Grid) -> Grid {
+    let mut smoothed = grid.clone();
+    for y in 1..grid.height - 1 {
+        for x in 1..grid.width - 1 {
+            if walls_around(grid, x, y) >= WALL_NEIGHBOURS_LIMIT {
+                smoothed.set(x, y, Tile::Wall);
+            } else {
+                smoothed.set(x, y, Tile::Floor);
+            }
+        }
+    }
+    smoothed
+}
+
+// Only the biggest cave is kept, the smaller ones are filled with walls
+// so every floor tile is reachable.
+fn keep_biggest_region(grid: &mut Grid) {
+    let mut checked = vec![false; grid.width * grid.height];
+    let mut biggest_region: Vec<(usize, usize)> = vec![];
+    for y in 0..grid.height {
+        for x in 0..grid.width {
+            if checked[y * grid.width + x] || grid.is_wall(x, y) {
+                continue;
+            }
+            let region = grid.flood_fill((x, y));
+            for (region_x, region_y) in &region {
+                checked[region_y * grid.width + region_x] = true;
+            }
+            if region.len() > biggest_region.len() {
+                biggest_region = region;
+            }
+        }
+    }
+    let mut in_biggest_region = vec![false; grid.width * grid.height];
+    for (x, y) in biggest_region {
+        in_biggest_region[y * grid.width + x] = true;
+    }
+    for y in 0..grid.height {
+        for x in 0..grid.width {
+            if !in_biggest_region[y * grid.width + x] {
+                grid.set(x, y, Tile::Wall);
+            }
+        }
+    }
+}
+
+// width and height are given in tiles
+pub fn cave(width: usize, height: usize, random: &mut Random) -> Grid {
+    let mut grid = Grid::new(width, height, Tile::Wall);
+    if width < 3 || height < 3 {
+        return grid;
+    }
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            if !random.chance(INITIAL_WALL_CHANCE) {
+                grid.set(x, y, Tile::Floor);
+            }
+        }
+    }
+    for _ in 0..SMOOTHING_STEPS {
+        grid = smooth(&grid);
+    }
+    keep_biggest_region(&mut grid);
+    grid
+}
+
+// returns the floor tile closest to the center of the grid
+pub fn cave_spawn(grid: &Grid) -> Option<(usize, usize)> {
+    let center_x = grid.width as f64 / 2.0;
+    let center_y = grid.height as f64 / 2.0;
+    let mut spawn = None;
+    let mut spawn_distance = f64::MAX;
+    for y in 0..grid.height {
+        for x in 0..grid.width {
+            let distance = (x as f64 - center_x).powi(2) + (y as f64 - center_y).powi(2);
+            if !grid.is_wall(x, y) && distance < spawn_distance {
+                spawn = Some((x, y));
+                spawn_distance = distance;
+            }
+        }
+    }
+    spawn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floor_tiles(grid: &Grid) -> Vec<(usize, usize)> {
+        let mut tiles = vec![];
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                if !grid.is_wall(x, y) {
+                    tiles.push((x, y));
+                }
+            }
+        }
+        tiles
+    }
+
+    #[test]
+    fn cave_single_region() {
+        let grid = cave(50, 40, &mut Random::new(17));
+        let floor = floor_tiles(&grid);
+        assert!(!floor.is_empty());
+        assert_eq!(grid.flood_fill(floor[0]).len(), floor.len());
+        for (x, y) in floor {
+            assert!(!grid.is_border(x, y));
+        }
+    }
+
+    #[test]
+    fn cave_spawn_on_floor() {
+        let grid = cave(50, 40, &mut Random::new(17));
+        let (x, y) = cave_spawn(&grid).unwrap();
+        assert!(!grid.is_wall(x, y));
+        assert_eq!(cave_spawn(&Grid::new(4, 4, Tile::Wall)), None);
+    }
+
+    #[test]
+    fn smooth() {
+        let mut grid = Grid::new(5, 5, Tile::Floor);
+        grid.set(1, 1, Tile::Wall);
+        grid.set(2, 1, Tile::Wall);
+        grid.set(1, 2, Tile::Wall);
+        grid.set(2, 2, Tile::Wall);
+        grid.set(3, 3, Tile::Wall);
+        let smoothed = super::smooth(&grid);
+
+        assert!(smoothed.is_wall(2, 2));
+        assert!(!smoothed.is_wall(3, 3));
+        assert!(!smoothed.is_wall(3, 1));
+    }
+}
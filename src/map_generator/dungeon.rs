@@ -0,0 +1,196 @@
+use super::grid::{Grid, Tile};
+use super::random::Random;
+use crate::map_element::DoorType;
+
+const MIN_ROOM_SIZE: usize = 4;
+const MAX_ROOM_SIZE: usize = 10;
+const PLACEMENT_ATTEMPTS_PER_ROOM: usize = 20;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Room {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Room {
+    pub fn center(&self) -> (usize, usize) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    // rooms have to be separated by at least one wall tile
+    fn intersects(&self, other: &Room) -> bool {
+        self.x <= other.x + other.width
+            && other.x <= self.x + self.width
+            && self.y <= other.y + other.height
+            && other.y <= self.y + self.height
+    }
+}
+
+pub struct Dungeon {
+    pub grid: Grid,
+    pub rooms: Vec<Room>,
+    pub doors: Vec<(usize, usize, DoorType)>,
+}
+
+fn place_rooms(width: usize, height: usize, rooms_amount: usize, random: &mut Random) -> Vec<Room> {
+    let mut rooms: Vec<Room> = vec![];
+    if width < MIN_ROOM_SIZE + 2 || height < MIN_ROOM_SIZE + 2 {
+        return rooms;
+    }
+    for _ in 0..rooms_amount * PLACEMENT_ATTEMPTS_PER_ROOM {
+        if rooms.len() == rooms_amount {
+            break;
+        }
+        let room_width = random.range(MIN_ROOM_SIZE, MAX_ROOM_SIZE.min(width - 2) + 1);
+        let room_height = random.range(MIN_ROOM_SIZE, MAX_ROOM_SIZE.min(height - 2) + 1);
+        let room = Room {
+            x: random.range(1, width - room_width),
+            y: random.range(1, height - room_height),
+            width: room_width,
+            height: room_height,
+        };
+        if rooms
+            .iter()
+            .all(|placed_room| !placed_room.intersects(&room))
+        {
+            rooms.push(room);
+        }
+    }
+    rooms
+}
+
+fn carve_corridor_tile(grid: &mut Grid, x: usize, y: usize) {
+    if grid.is_wall(x, y) {
+        grid.set(x, y, Tile::Floor);
+    }
+}
+
+fn carve_corridor(
+    grid: &mut Grid,
+    from: (usize, usize),
+    to: (usize, usize),
+    horizontal_first: bool,
+) {
+    let corner = if horizontal_first {
+        (to.0, from.1)
+    } else {
+        (from.0, to.1)
+    };
+    for (start, end) in [(from, corner), (corner, to)].iter() {
+        for x in start.0.min(end.0)..=start.0.max(end.0) {
+            for y in start.1.min(end.1)..=start.1.max(end.1) {
+                carve_corridor_tile(grid, x, y);
+            }
+        }
+    }
+}
+
+// Door is placed on the corridor tile which leads straight into a room.
+fn door_type(grid: &Grid, x: usize, y: usize) -> Option<DoorType> {
+    if grid.get(x, y) != Tile::Floor || grid.is_border(x, y) {
+        return None;
+    }
+    let room_left = grid.get(x - 1, y) == Tile::Room;
+    let room_right = grid.get(x + 1, y) == Tile::Room;
+    let room_up = grid.get(x, y - 1) == Tile::Room;
+    let room_down = grid.get(x, y + 1) == Tile::Room;
+    let rooms_amount = [room_left, room_right, room_up, room_down]
+        .iter()
+        .filter(|is_room| **is_room)
+        .count();
+    if rooms_amount != 1 {
+        return None;
+    }
+    if (room_left || room_right) && grid.is_wall(x, y - 1) && grid.is_wall(x, y + 1) {
+        return Some(DoorType::Vertical);
+    }
+    if (room_up || room_down) && grid.is_wall(x - 1, y) && grid.is_wall(x + 1, y) {
+        return Some(DoorType::Horizontal);
+    }
+    None
+}
+
+// width and height are given in tiles
+pub fn dungeon(width: usize, height: usize, rooms_amount: usize, random: &mut Random) -> Dungeon {
+    let mut grid = Grid::new(width, height, Tile::Wall);
+    let rooms = place_rooms(width, height, rooms_amount, random);
+    for room in &rooms {
+        for x in room.x..room.x + room.width {
+            for y in room.y..room.y + room.height {
+                grid.set(x, y, Tile::Room);
+            }
+        }
+    }
+    for index in 1..rooms.len() {
+        let horizontal_first = random.chance(0.5);
+        carve_corridor(
+            &mut grid,
+            rooms[index - 1].center(),
+            rooms[index].center(),
+            horizontal_first,
+        );
+    }
+
+    let mut doors = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            if let Some(door_type) = door_type(&grid, x, y) {
+                doors.push((x, y, door_type));
+            }
+        }
+    }
+    Dungeon { grid, rooms, doors }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rooms_do_not_touch() {
+        let dungeon = dungeon(60, 40, 8, &mut Random::new(9));
+        assert!(dungeon.rooms.len() > 1);
+        for (index, room) in dungeon.rooms.iter().enumerate() {
+            assert!(room.x >= 1 && room.x + room.width < 60);
+            assert!(room.y >= 1 && room.y + room.height < 40);
+            for other in &dungeon.rooms[index + 1..] {
+                assert!(!room.intersects(other));
+            }
+        }
+    }
+
+    #[test]
+    fn rooms_connected() {
+        let dungeon = dungeon(60, 40, 8, &mut Random::new(9));
+        let region = dungeon.grid.flood_fill(dungeon.rooms[0].center());
+        for room in &dungeon.rooms {
+            assert!(region.contains(&room.center()));
+        }
+    }
+
+    #[test]
+    fn doors_lead_into_rooms() {
+        let dungeon = dungeon(60, 40, 8, &mut Random::new(21));
+        assert!(!dungeon.doors.is_empty());
+        for (x, y, door_type) in &dungeon.doors {
+            let grid = &dungeon.grid;
+            assert_eq!(grid.get(*x, *y), Tile::Floor);
+            if *door_type == DoorType::Vertical {
+                assert!(grid.get(x - 1, *y) == Tile::Room || grid.get(x + 1, *y) == Tile::Room);
+                assert!(grid.is_wall(*x, y - 1) && grid.is_wall(*x, y + 1));
+            } else {
+                assert!(grid.get(*x, y - 1) == Tile::Room || grid.get(*x, y + 1) == Tile::Room);
+                assert!(grid.is_wall(x - 1, *y) && grid.is_wall(x + 1, *y));
+            }
+        }
+    }
+
+    #[test]
+    fn too_small_area() {
+        let dungeon = dungeon(5, 30, 3, &mut Random::new(1));
+        assert!(dungeon.rooms.is_empty());
+        assert!(dungeon.doors.is_empty());
+    }
+}
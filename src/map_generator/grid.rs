@@ -0,0 +1,158 @@
+use crate::graph::Coordinate;
+use std::collections::VecDeque;
+
+const WALL_PIXEL: image::Rgba<u8> = image::Rgba([0, 0, 0, 255]);
+const FLOOR_PIXEL: image::Rgba<u8> = image::Rgba([255, 255, 255, 255]);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Tile {
+    Wall,
+    Floor,
+    Room,
+}
+
+// Map layout where every tile is turned into cell_size x cell_size pixels
+// of the WallMap image.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    tiles: Vec<Tile>,
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize, tile: Tile) -> Self {
+        Self {
+            width,
+            height,
+            tiles: vec![tile; width * height],
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Tile {
+        self.tiles[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, tile: Tile) {
+        self.tiles[y * self.width + x] = tile;
+    }
+
+    pub fn is_wall(&self, x: usize, y: usize) -> bool {
+        self.get(x, y) == Tile::Wall
+    }
+
+    pub fn is_border(&self, x: usize, y: usize) -> bool {
+        x == 0 || y == 0 || x == self.width - 1 || y == self.height - 1
+    }
+
+    pub fn neighbours(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let mut neighbours = Vec::with_capacity(4);
+        if x > 0 {
+            neighbours.push((x - 1, y));
+        }
+        if y > 0 {
+            neighbours.push((x, y - 1));
+        }
+        if x + 1 < self.width {
+            neighbours.push((x + 1, y));
+        }
+        if y + 1 < self.height {
+            neighbours.push((x, y + 1));
+        }
+        neighbours
+    }
+
+    // returns all tiles that are not walls and are connected with the start tile
+    pub fn flood_fill(&self, start: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut visited = vec![false; self.width * self.height];
+        let mut region = vec![];
+        if self.is_wall(start.0, start.1) {
+            return region;
+        }
+        let mut queue = VecDeque::new();
+        visited[start.1 * self.width + start.0] = true;
+        queue.push_back(start);
+        while let Some((x, y)) = queue.pop_front() {
+            region.push((x, y));
+            for (next_x, next_y) in self.neighbours(x, y) {
+                let index = next_y * self.width + next_x;
+                if !visited[index] && !self.is_wall(next_x, next_y) {
+                    visited[index] = true;
+                    queue.push_back((next_x, next_y));
+                }
+            }
+        }
+        region
+    }
+
+    pub fn tile_center(x: usize, y: usize, cell_size: u32) -> Coordinate {
+        Coordinate {
+            x: (x as f64 + 0.5) * cell_size as f64,
+            y: (y as f64 + 0.5) * cell_size as f64,
+        }
+    }
+
+    pub fn to_image(&self, cell_size: u32) -> image::RgbaImage {
+        image::RgbaImage::from_fn(
+            self.width as u32 * cell_size,
+            self.height as u32 * cell_size,
+            |x, y| {
+                if self.is_wall((x / cell_size) as usize, (y / cell_size) as usize) {
+                    WALL_PIXEL
+                } else {
+                    FLOOR_PIXEL
+                }
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_set() {
+        let mut grid = Grid::new(3, 2, Tile::Wall);
+        grid.set(2, 1, Tile::Room);
+        assert_eq!(grid.get(2, 1), Tile::Room);
+        assert_eq!(grid.get(1, 1), Tile::Wall);
+        assert!(!grid.is_wall(2, 1));
+        assert!(grid.is_border(2, 1));
+    }
+
+    #[test]
+    fn neighbours() {
+        let grid = Grid::new(3, 3, Tile::Wall);
+        assert_eq!(grid.neighbours(0, 0), vec![(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbours(1, 1), vec![(0, 1), (1, 0), (2, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn flood_fill() {
+        let mut grid = Grid::new(5, 3, Tile::Wall);
+        grid.set(1, 1, Tile::Floor);
+        grid.set(2, 1, Tile::Floor);
+        grid.set(4, 1, Tile::Floor);
+
+        assert_eq!(grid.flood_fill((1, 1)), vec![(1, 1), (2, 1)]);
+        assert_eq!(grid.flood_fill((4, 1)), vec![(4, 1)]);
+        assert_eq!(grid.flood_fill((0, 0)), vec![]);
+    }
+
+    #[test]
+    fn to_image() {
+        let mut grid = Grid::new(2, 1, Tile::Wall);
+        grid.set(1, 0, Tile::Floor);
+        let image = grid.to_image(3);
+
+        assert_eq!(image.dimensions(), (6, 3));
+        assert_eq!(*image.get_pixel(2, 2), WALL_PIXEL);
+        assert_eq!(*image.get_pixel(3, 0), FLOOR_PIXEL);
+    }
+
+    #[test]
+    fn tile_center() {
+        assert_eq!(Grid::tile_center(2, 3, 4), Coordinate { x: 10.0, y: 14.0 });
+    }
+}
@@ -0,0 +1,150 @@
+use super::grid::{Grid, Tile};
+use super::random::Random;
+
+// Maze cells are placed on odd grid positions, walls between them on even ones.
+fn cell_position(cell_x: usize, cell_y: usize) -> (usize, usize) {
+    (cell_x * 2 + 1, cell_y * 2 + 1)
+}
+
+fn cell_neighbours(
+    cell_x: usize,
+    cell_y: usize,
+    width: usize,
+    height: usize,
+) -> Vec<(usize, usize)> {
+    let mut neighbours = Vec::with_capacity(4);
+    if cell_x > 0 {
+        neighbours.push((cell_x - 1, cell_y));
+    }
+    if cell_y > 0 {
+        neighbours.push((cell_x, cell_y - 1));
+    }
+    if cell_x + 1 < width {
+        neighbours.push((cell_x + 1, cell_y));
+    }
+    if cell_y + 1 < height {
+        neighbours.push((cell_x, cell_y + 1));
+    }
+    neighbours
+}
+
+fn carve_passage(grid: &mut Grid, from: (usize, usize), to: (usize, usize)) {
+    let (from_x, from_y) = cell_position(from.0, from.1);
+    let (to_x, to_y) = cell_position(to.0, to.1);
+    grid.set(to_x, to_y, Tile::Floor);
+    grid.set((from_x + to_x) / 2, (from_y + to_y) / 2, Tile::Floor);
+}
+
+// width and height are given in maze cells, returned grid has size (2 * width + 1) x (2 * height + 1)
+pub fn recursive_backtracker(width: usize, height: usize, random: &mut Random) -> Grid {
+    let mut grid = Grid::new(width * 2 + 1, height * 2 + 1, Tile::Wall);
+    let mut visited = vec![false; width * height];
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+    grid.set(1, 1, Tile::Floor);
+
+    while let Some(&(cell_x, cell_y)) = stack.last() {
+        let unvisited: Vec<(usize, usize)> = cell_neighbours(cell_x, cell_y, width, height)
+            .into_iter()
+            .filter(|(x, y)| !visited[y * width + x])
+            .collect();
+        if unvisited.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let next = unvisited[random.range(0, unvisited.len())];
+        visited[next.1 * width + next.0] = true;
+        carve_passage(&mut grid, (cell_x, cell_y), next);
+        stack.push(next);
+    }
+    grid
+}
+
+// width and height are given in maze cells, returned grid has size (2 * width + 1) x (2 * height + 1)
+pub fn prim(width: usize, height: usize, random: &mut Random) -> Grid {
+    let mut grid = Grid::new(width * 2 + 1, height * 2 + 1, Tile::Wall);
+    let mut visited = vec![false; width * height];
+    let mut frontier: Vec<((usize, usize), (usize, usize))> = vec![];
+    visited[0] = true;
+    grid.set(1, 1, Tile::Floor);
+    for neighbour in cell_neighbours(0, 0, width, height) {
+        frontier.push(((0, 0), neighbour));
+    }
+
+    while !frontier.is_empty() {
+        let (from, to) = frontier.swap_remove(random.range(0, frontier.len()));
+        if visited[to.1 * width + to.0] {
+            continue;
+        }
+        visited[to.1 * width + to.0] = true;
+        carve_passage(&mut grid, from, to);
+        for neighbour in cell_neighbours(to.0, to.1, width, height) {
+            if !visited[neighbour.1 * width + neighbour.0] {
+                frontier.push((to, neighbour));
+            }
+        }
+    }
+    grid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floor_tiles(grid: &Grid) -> usize {
+        let mut amount = 0;
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                if !grid.is_wall(x, y) {
+                    amount += 1;
+                }
+            }
+        }
+        amount
+    }
+
+    // Perfect maze connects all cells and has no loops, so it contains
+    // width * height cells and width * height - 1 passages between them.
+    fn check_perfect_maze(grid: &Grid, width: usize, height: usize) {
+        assert_eq!(grid.width, width * 2 + 1);
+        assert_eq!(grid.height, height * 2 + 1);
+        assert_eq!(floor_tiles(grid), width * height * 2 - 1);
+        assert_eq!(grid.flood_fill((1, 1)).len(), width * height * 2 - 1);
+        for x in 0..grid.width {
+            assert!(grid.is_wall(x, 0));
+            assert!(grid.is_wall(x, grid.height - 1));
+        }
+        for y in 0..grid.height {
+            assert!(grid.is_wall(0, y));
+            assert!(grid.is_wall(grid.width - 1, y));
+        }
+    }
+
+    #[test]
+    fn recursive_backtracker_perfect_maze() {
+        let grid = recursive_backtracker(12, 7, &mut Random::new(3));
+        check_perfect_maze(&grid, 12, 7);
+    }
+
+    #[test]
+    fn prim_perfect_maze() {
+        let grid = prim(9, 14, &mut Random::new(5));
+        check_perfect_maze(&grid, 9, 14);
+    }
+
+    #[test]
+    fn same_seed_same_maze() {
+        assert_eq!(
+            recursive_backtracker(10, 10, &mut Random::new(11)),
+            recursive_backtracker(10, 10, &mut Random::new(11))
+        );
+        assert_eq!(
+            prim(10, 10, &mut Random::new(11)),
+            prim(10, 10, &mut Random::new(11))
+        );
+        assert_ne!(
+            prim(10, 10, &mut Random::new(11)),
+            prim(10, 10, &mut Random::new(12))
+        );
+    }
+}
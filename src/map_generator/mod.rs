@@ -0,0 +1,265 @@
+mod cave;
+mod dungeon;
+mod grid;
+mod maze;
+mod random;
+
+use crate::graph::Coordinate;
+use crate::map_element::{Color, DoorArea, DoorType, Point, Rectangle, WallMap};
+use grid::{Grid, Tile};
+use random::Random;
+
+#[cfg(not(test))]
+use crate::map_element::{Door, DoorVelocity, MapElement};
+
+pub struct GeneratedMap {
+    pub wall_map: WallMap,
+    pub doors: Vec<DoorArea>,
+    pub spawn: Coordinate,
+}
+
+impl GeneratedMap {
+    // returns doors followed by the wall map, ready to be passed to the Engine
+    #[cfg(not(tarpaulin_include))]
+    #[cfg(not(test))]
+    pub fn into_map_elements(self, door_velocity: DoorVelocity) -> Vec<Box<dyn MapElement>> {
+        let mut map_elements: Vec<Box<dyn MapElement>> = vec![];
+        for door in self.doors {
+            map_elements.push(Box::new(Door::new(
+                door.area,
+                door_velocity.clone(),
+                door.door_type,
+                door.open_area,
+                None,
+            )));
+        }
+        map_elements.push(Box::new(self.wall_map));
+        map_elements
+    }
+}
+
+// Generates maps from the seed. Every tile of the generated layout is
+// turned into cell_size x cell_size pixels of the WallMap image.
+// The same seed and parameters always give the same map.
+pub struct MapGenerator {
+    seed: u64,
+    cell_size: u32,
+    color: Option<Color>,
+}
+
+impl MapGenerator {
+    pub fn new(seed: u64, cell_size: u32, color: Option<Color>) -> Self {
+        Self {
+            seed,
+            cell_size: cell_size.max(1),
+            color,
+        }
+    }
+
+    fn generated_map(
+        &self,
+        grid: &Grid,
+        doors: Vec<DoorArea>,
+        spawn: (usize, usize),
+    ) -> GeneratedMap {
+        GeneratedMap {
+            wall_map: WallMap::from_image(grid.to_image(self.cell_size), self.color.clone()),
+            doors,
+            spawn: Grid::tile_center(spawn.0, spawn.1, self.cell_size),
+        }
+    }
+
+    // door fills the whole corridor tile, so there is no gap between the door and the walls
+    fn door_area(&self, x: usize, y: usize, door_type: DoorType) -> DoorArea {
+        let cell_size = self.cell_size as i64;
+        let start_x = x as i64 * cell_size;
+        let start_y = y as i64 * cell_size;
+        DoorArea {
+            area: Rectangle {
+                point_a: Point {
+                    x: start_x,
+                    y: start_y,
+                },
+                point_b: Point {
+                    x: start_x + cell_size - 1,
+                    y: start_y + cell_size - 1,
+                },
+            },
+            door_type,
+            open_area: None,
+        }
+    }
+
+    // perfect maze generated with the recursive backtracker algorithm,
+    // width and height are given in maze cells
+    pub fn recursive_backtracker(&self, width: usize, height: usize) -> GeneratedMap {
+        let grid =
+            maze::recursive_backtracker(width.max(1), height.max(1), &mut Random::new(self.seed));
+        self.generated_map(&grid, vec![], (1, 1))
+    }
+
+    // perfect maze generated with the randomized Prim's algorithm,
+    // width and height are given in maze cells
+    pub fn prim(&self, width: usize, height: usize) -> GeneratedMap {
+        let grid = maze::prim(width.max(1), height.max(1), &mut Random::new(self.seed));
+        self.generated_map(&grid, vec![], (1, 1))
+    }
+
+    // rooms connected with corridors, doors are placed where corridors enter rooms,
+    // width and height are given in tiles
+    pub fn dungeon(&self, width: usize, height: usize, rooms_amount: usize) -> GeneratedMap {
+        let dungeon = dungeon::dungeon(width, height, rooms_amount, &mut Random::new(self.seed));
+        let doors = dungeon
+            .doors
+            .into_iter()
+            .map(|(x, y, door_type)| self.door_area(x, y, door_type))
+            .collect();
+        let mut grid = dungeon.grid;
+        let spawn = spawn_or_center(&mut grid, dungeon.rooms.first().map(|room| room.center()));
+        self.generated_map(&grid, doors, spawn)
+    }
+
+    // cave layout generated with the cellular automaton,
+    // width and height are given in tiles
+    pub fn cave(&self, width: usize, height: usize) -> GeneratedMap {
+        let mut grid = cave::cave(width, height, &mut Random::new(self.seed));
+        let cave_spawn = cave::cave_spawn(&grid);
+        let spawn = spawn_or_center(&mut grid, cave_spawn);
+        self.generated_map(&grid, vec![], spawn)
+    }
+}
+
+// Layout without any floor, e.g. too small for a room, gets the floor tile in
+// the center, so the spawn is never inside the wall. Grid without tiles has no
+// valid spawn at all.
+fn spawn_or_center(grid: &mut Grid, spawn: Option<(usize, usize)>) -> (usize, usize) {
+    if let Some(spawn) = spawn {
+        return spawn;
+    }
+    let center = (grid.width / 2, grid.height / 2);
+    if grid.width > 0 && grid.height > 0 {
+        grid.set(center.0, center.1, Tile::Floor);
+    }
+    center
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_element::MapElement;
+
+    fn is_spawn_in_wall(generated_map: &GeneratedMap) -> bool {
+        generated_map
+            .wall_map
+            .is_point_in_object(&Point::new(generated_map.spawn.x, generated_map.spawn.y))
+    }
+
+    fn wall_points(generated_map: &GeneratedMap) -> Vec<bool> {
+        let map = generated_map.wall_map.get_map();
+        let mut points = vec![];
+        for y in 0..map.height {
+            for x in 0..map.width {
+                points.push(generated_map.wall_map.is_point_in_object(&Point { x, y }));
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn recursive_backtracker() {
+        let generator = MapGenerator::new(5, 4, None);
+        let generated_map = generator.recursive_backtracker(10, 6);
+        let map = generated_map.wall_map.get_map();
+
+        assert_eq!(map.width, 21 * 4);
+        assert_eq!(map.height, 13 * 4);
+        assert_eq!(generated_map.spawn, Coordinate { x: 6.0, y: 6.0 });
+        assert!(!is_spawn_in_wall(&generated_map));
+        assert!(generated_map.doors.is_empty());
+    }
+
+    #[test]
+    fn prim() {
+        let generated_map = MapGenerator::new(5, 3, Some(Color::Green)).prim(7, 8);
+        let map = generated_map.wall_map.get_map();
+
+        assert_eq!(map.width, 15 * 3);
+        assert_eq!(map.height, 17 * 3);
+        assert_eq!(generated_map.wall_map.color(), Color::Green);
+        assert!(!is_spawn_in_wall(&generated_map));
+    }
+
+    #[test]
+    fn dungeon() {
+        let generated_map = MapGenerator::new(21, 5, None).dungeon(60, 40, 8);
+        assert!(!is_spawn_in_wall(&generated_map));
+        assert!(!generated_map.doors.is_empty());
+        for door in &generated_map.doors {
+            let area = &door.area;
+            for x in area.point_a.x..=area.point_b.x {
+                for y in area.point_a.y..=area.point_b.y {
+                    assert!(!generated_map.wall_map.is_point_in_object(&Point { x, y }));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dungeon_too_small_for_rooms() {
+        let generated_map = MapGenerator::new(1, 4, None).dungeon(5, 5, 3);
+        assert_eq!(generated_map.spawn, Coordinate { x: 10.0, y: 10.0 });
+        assert!(!is_spawn_in_wall(&generated_map));
+        assert!(generated_map.doors.is_empty());
+
+        let generated_map = MapGenerator::new(1, 4, None).cave(2, 2);
+        assert!(!is_spawn_in_wall(&generated_map));
+    }
+
+    #[test]
+    fn door_area() {
+        let generator = MapGenerator::new(0, 8, None);
+        assert_eq!(
+            generator.door_area(2, 3, DoorType::Vertical),
+            DoorArea {
+                area: Rectangle {
+                    point_a: Point { x: 16, y: 24 },
+                    point_b: Point { x: 23, y: 31 },
+                },
+                door_type: DoorType::Vertical,
+                open_area: None,
+            }
+        );
+    }
+
+    #[test]
+    fn cave() {
+        let generated_map = MapGenerator::new(8, 2, None).cave(50, 40);
+        assert!(!is_spawn_in_wall(&generated_map));
+    }
+
+    #[test]
+    fn same_seed_same_map() {
+        let generator_1 = MapGenerator::new(1234, 4, None);
+        let generator_2 = MapGenerator::new(1234, 4, None);
+        let generator_3 = MapGenerator::new(4321, 4, None);
+
+        let dungeon_1 = generator_1.dungeon(50, 50, 6);
+        let dungeon_2 = generator_2.dungeon(50, 50, 6);
+        assert_eq!(wall_points(&dungeon_1), wall_points(&dungeon_2));
+        assert_eq!(dungeon_1.doors, dungeon_2.doors);
+        assert_eq!(dungeon_1.spawn, dungeon_2.spawn);
+
+        assert_eq!(
+            wall_points(&generator_1.cave(40, 30)),
+            wall_points(&generator_2.cave(40, 30))
+        );
+        assert_ne!(
+            wall_points(&generator_1.cave(40, 30)),
+            wall_points(&generator_3.cave(40, 30))
+        );
+        assert_eq!(
+            wall_points(&generator_1.recursive_backtracker(8, 8)),
+            wall_points(&generator_2.recursive_backtracker(8, 8))
+        );
+    }
+}
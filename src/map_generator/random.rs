@@ -0,0 +1,67 @@
+// Small deterministic pseudo-random number generator (SplitMix64).
+// Implemented here instead of using an external crate so the same seed
+// produces the same map regardless of dependency versions.
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut value = self.state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        value ^ (value >> 31)
+    }
+
+    // returns value from range [min, max)
+    pub fn range(&mut self, min: usize, max: usize) -> usize {
+        if max <= min {
+            return min;
+        }
+        min + (self.next_u64() % (max - min) as u64) as usize
+    }
+
+    pub fn chance(&mut self, probability: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_values() {
+        let mut random_1 = Random::new(42);
+        let mut random_2 = Random::new(42);
+        let mut random_3 = Random::new(43);
+        let values_1: Vec<u64> = (0..10).map(|_| random_1.next_u64()).collect();
+        let values_2: Vec<u64> = (0..10).map(|_| random_2.next_u64()).collect();
+        let values_3: Vec<u64> = (0..10).map(|_| random_3.next_u64()).collect();
+        assert_eq!(values_1, values_2);
+        assert_ne!(values_1, values_3);
+    }
+
+    #[test]
+    fn range() {
+        let mut random = Random::new(7);
+        for _ in 0..1000 {
+            let value = random.range(3, 8);
+            assert!((3..8).contains(&value));
+        }
+        assert_eq!(random.range(5, 5), 5);
+        assert_eq!(random.range(6, 2), 6);
+    }
+
+    #[test]
+    fn chance() {
+        let mut random = Random::new(7);
+        assert!((0..100).all(|_| random.chance(1.0)));
+        assert!((0..100).all(|_| !random.chance(0.0)));
+    }
+}
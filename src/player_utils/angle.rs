@@ -28,6 +28,13 @@ impl Angle {
         self.end - self.start
     }
 
+    // changes the value of the angle, direction stays the same
+    pub(crate) fn set_value(&mut self, value: Radians) {
+        let direction = self.get_direction();
+        self.start = direction - value / 2.0;
+        self.end = direction + value / 2.0;
+    }
+
     pub(crate) fn rotate_left(&mut self, angle_delta: Radians) {
         self.start += angle_delta;
         self.end += angle_delta;
@@ -57,6 +64,7 @@ impl Angle {
         )
     }
 
+    #[cfg(test)]
     pub(crate) fn is_inside(&self, radians: Radians) -> bool {
         if self.start > self.end {
             if radians >= self.start || radians <= self.end {
@@ -79,6 +87,7 @@ impl Angle {
 mod tests {
     use super::*;
     use crate::player_utils::radians::PI_2;
+    use float_cmp::approx_eq;
 
     #[test]
     fn angle_value() {
@@ -190,4 +199,27 @@ mod tests {
         assert!(angle.is_inside(Radians::new(2.3)));
         assert!(!angle.is_inside(Radians::new(2.5)));
     }
+
+    #[test]
+    fn set_value() {
+        let mut angle = Angle {
+            start: Radians::new(5.5),
+            end: Radians::new(0.5),
+        };
+        let direction = angle.get_direction();
+        angle.set_value(Radians::new(0.4));
+        assert!(approx_eq!(f64, angle.value().to_f64(), 0.4, epsilon = 1e-9));
+        assert!(approx_eq!(
+            f64,
+            angle.get_direction().to_f64(),
+            direction.to_f64(),
+            epsilon = 1e-9
+        ));
+        assert!(approx_eq!(
+            f64,
+            angle.start.to_f64(),
+            direction.to_f64() - 0.2,
+            epsilon = 1e-9
+        ));
+    }
 }
@@ -0,0 +1,185 @@
+use super::PI_2;
+
+// Settings of the camera effects which move the view without moving the
+// player. Zero factor turns its effect off, enabled false turns off all of
+// them, e.g. for the players sensitive to the motion.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CameraEffects {
+    pub enabled: bool,
+    // eye height change per map unit per second of the walking speed
+    pub head_bob: f64,
+    // distance walked during one head bob cycle
+    pub head_bob_step: f64,
+    // pitch and roll in radians of the shake with the intensity 1
+    pub shake: f64,
+    // how fast the shake fades out, per second
+    pub shake_decay: f64,
+    // eye drop per map unit per second of the landing velocity
+    pub landing_dip: f64,
+    // how fast the eye comes back after the landing, per second
+    pub landing_recovery: f64,
+    // roll in radians per map unit per second of the sideways speed
+    pub strafe_roll: f64,
+}
+
+impl Default for CameraEffects {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            head_bob: 0.005,
+            head_bob_step: 8.0,
+            shake: 0.05,
+            shake_decay: 4.0,
+            landing_dip: 0.05,
+            landing_recovery: 8.0,
+            strafe_roll: 0.001,
+        }
+    }
+}
+
+// Change of the view made by the camera effects
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct CameraOffset {
+    pub eye_offset: f64,
+    pub pitch: f64,
+    pub roll: f64,
+}
+
+#[derive(Clone, Default, Debug)]
+pub(crate) struct CameraState {
+    // seconds since the start, drives the shake
+    time: f64,
+    // part of the head bob cycle in [0, 1)
+    bob_phase: f64,
+    bob_speed: f64,
+    sideways_speed: f64,
+    shake: f64,
+    // landing velocity which is still not recovered
+    dip: f64,
+}
+
+impl CameraState {
+    // Head bob follows the speed on the ground, shake and landing dip fade
+    // out during dt.
+    pub(crate) fn update(
+        &mut self,
+        effects: &CameraEffects,
+        dt: f64,
+        speed: f64,
+        sideways_speed: f64,
+        is_on_ground: bool,
+    ) {
+        self.time += dt;
+        self.bob_speed = if is_on_ground { speed } else { 0.0 };
+        if effects.head_bob_step > 0.0 {
+            self.bob_phase = (self.bob_phase + self.bob_speed * dt / effects.head_bob_step).fract();
+        }
+        self.sideways_speed = sideways_speed;
+        self.shake *= (-effects.shake_decay * dt).exp();
+        self.dip *= (-effects.landing_recovery * dt).exp();
+    }
+
+    // intensities of the shakes are added, negative intensity is ignored
+    pub(crate) fn shake(&mut self, intensity: f64) {
+        self.shake += intensity.max(0.0);
+    }
+
+    pub(crate) fn land(&mut self, velocity: f64) {
+        self.dip += velocity.abs();
+    }
+
+    pub(crate) fn offset(&self, effects: &CameraEffects) -> CameraOffset {
+        if !effects.enabled {
+            return CameraOffset::default();
+        }
+        let bob = effects.head_bob * self.bob_speed * (self.bob_phase * PI_2).sin();
+        let shake = effects.shake * self.shake;
+        CameraOffset {
+            eye_offset: bob - effects.landing_dip * self.dip,
+            // incommensurable frequencies make the shake look random
+            pitch: shake * (self.time * 31.0).sin(),
+            roll: shake * (self.time * 23.0).cos() + effects.strafe_roll * self.sideways_speed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    #[test]
+    fn head_bob() {
+        let effects = CameraEffects::default();
+        let mut state = CameraState::default();
+
+        // quarter of the step is the top of the bob
+        state.update(&effects, 0.1, 20.0, 0.0, true);
+        let offset = state.offset(&effects);
+        assert!(approx_eq!(
+            f64,
+            offset.eye_offset,
+            effects.head_bob * 20.0,
+            epsilon = 1e-9
+        ));
+        assert_eq!(offset.pitch, 0.0);
+
+        // no bob in the air
+        state.update(&effects, 0.1, 20.0, 0.0, false);
+        assert_eq!(state.offset(&effects).eye_offset, 0.0);
+    }
+
+    #[test]
+    fn shake_and_landing_dip() {
+        let effects = CameraEffects::default();
+        let mut state = CameraState::default();
+        state.shake(1.0);
+        state.shake(-1.0);
+        state.land(-4.0);
+        state.update(&effects, 0.05, 0.0, 0.0, true);
+        let offset = state.offset(&effects);
+        let shake = effects.shake * (-effects.shake_decay * 0.05).exp();
+        assert!(approx_eq!(
+            f64,
+            offset.pitch,
+            shake * (0.05_f64 * 31.0).sin(),
+            epsilon = 1e-9
+        ));
+        assert!(approx_eq!(
+            f64,
+            offset.roll,
+            shake * (0.05_f64 * 23.0).cos(),
+            epsilon = 1e-9
+        ));
+        assert!(approx_eq!(
+            f64,
+            offset.eye_offset,
+            -effects.landing_dip * 4.0 * (-effects.landing_recovery * 0.05).exp(),
+            epsilon = 1e-9
+        ));
+
+        // both fade out
+        state.update(&effects, 10.0, 0.0, 0.0, true);
+        let offset = state.offset(&effects);
+        assert!(offset.pitch.abs() < 1e-9);
+        assert!(offset.eye_offset.abs() < 1e-9);
+    }
+
+    #[test]
+    fn strafe_roll_and_disabled() {
+        let mut effects = CameraEffects::default();
+        let mut state = CameraState::default();
+        state.update(&effects, 0.1, 10.0, 10.0, false);
+        assert!(approx_eq!(
+            f64,
+            state.offset(&effects).roll,
+            effects.strafe_roll * 10.0,
+            epsilon = 1e-9
+        ));
+
+        state.shake(1.0);
+        state.land(4.0);
+        effects.enabled = false;
+        assert_eq!(state.offset(&effects), CameraOffset::default());
+    }
+}
@@ -0,0 +1,164 @@
+// Analog axes, e.g. of the gamepad sticks, which drive the player
+// proportionally. Values are in the range [-1, 1].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Axis {
+    // positive moves forward
+    MoveForward,
+    // positive moves to the right
+    MoveRight,
+    // positive turns the same way as the mouse moved to the right
+    Turn,
+    // positive looks up
+    LookUp,
+}
+
+impl Axis {
+    pub(crate) const COUNT: usize = 4;
+
+    pub(crate) fn index(&self) -> usize {
+        match self {
+            Axis::MoveForward => 0,
+            Axis::MoveRight => 1,
+            Axis::Turn => 2,
+            Axis::LookUp => 3,
+        }
+    }
+}
+
+// Settings of turning and looking with the mouse, the keyboard and the axes
+#[derive(Clone, PartialEq, Debug)]
+pub struct InputSettings {
+    // radians per pixel of the mouse move
+    pub mouse_sensitivity: f64,
+    // invert the mouse and the Turn axis
+    pub invert_x: bool,
+    // invert the mouse and the LookUp axis
+    pub invert_y: bool,
+    // radians per second of the arrow keys and the full Turn axis
+    pub turn_speed: f64,
+    // radians per second of the full LookUp axis
+    pub look_speed: f64,
+    // axis values closer to zero are ignored, e.g. of the worn stick
+    pub dead_zone: f64,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        Self {
+            mouse_sensitivity: 0.001,
+            invert_x: false,
+            invert_y: false,
+            turn_speed: std::f64::consts::PI,
+            look_speed: std::f64::consts::PI / 2.0,
+            dead_zone: 0.15,
+        }
+    }
+}
+
+impl InputSettings {
+    // negative values are replaced by zero, dead zone is lower than 1
+    pub(crate) fn valid(&self) -> Self {
+        Self {
+            mouse_sensitivity: self.mouse_sensitivity.max(0.0),
+            turn_speed: self.turn_speed.max(0.0),
+            look_speed: self.look_speed.max(0.0),
+            dead_zone: self.dead_zone.clamp(0.0, 0.99),
+            ..*self
+        }
+    }
+
+    // Clamps the value into [-1, 1] and removes the dead zone, the rest of
+    // the range is stretched back to [-1, 1]. Inverted axes change the sign.
+    pub(crate) fn axis_value(&self, axis: Axis, value: f64) -> f64 {
+        let value = value.clamp(-1.0, 1.0);
+        let value = if value.abs() < self.dead_zone {
+            0.0
+        } else {
+            value.signum() * (value.abs() - self.dead_zone) / (1.0 - self.dead_zone)
+        };
+        let is_inverted = match axis {
+            Axis::Turn => self.invert_x,
+            Axis::LookUp => self.invert_y,
+            _ => false,
+        };
+        if is_inverted {
+            -value
+        } else {
+            value
+        }
+    }
+
+    // turn and look up angles of the mouse move
+    pub(crate) fn mouse_look(&self, dx: f64, dy: f64) -> (f64, f64) {
+        let sign = |is_inverted: bool| if is_inverted { -1.0 } else { 1.0 };
+        // mouse moved up gives negative value
+        (
+            dx * self.mouse_sensitivity * sign(self.invert_x),
+            -dy * self.mouse_sensitivity * sign(self.invert_y),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    #[test]
+    fn axis_value() {
+        let settings = InputSettings {
+            dead_zone: 0.2,
+            ..InputSettings::default()
+        };
+        assert_eq!(settings.axis_value(Axis::MoveForward, 0.1), 0.0);
+        assert_eq!(settings.axis_value(Axis::MoveForward, -0.19), 0.0);
+        assert!(approx_eq!(
+            f64,
+            settings.axis_value(Axis::MoveForward, 0.6),
+            0.5,
+            epsilon = 1e-9
+        ));
+        assert_eq!(settings.axis_value(Axis::MoveRight, -3.0), -1.0);
+
+        let settings = InputSettings {
+            invert_x: true,
+            invert_y: true,
+            dead_zone: 0.0,
+            ..InputSettings::default()
+        };
+        assert_eq!(settings.axis_value(Axis::Turn, 0.5), -0.5);
+        assert_eq!(settings.axis_value(Axis::LookUp, 0.5), -0.5);
+        assert_eq!(settings.axis_value(Axis::MoveForward, 0.5), 0.5);
+    }
+
+    #[test]
+    fn mouse_look() {
+        let settings = InputSettings {
+            mouse_sensitivity: 0.5,
+            ..InputSettings::default()
+        };
+        assert_eq!(settings.mouse_look(3.0, 5.0), (1.5, -2.5));
+
+        let settings = InputSettings {
+            invert_y: true,
+            ..settings
+        };
+        assert_eq!(settings.mouse_look(3.0, 5.0), (1.5, 2.5));
+    }
+
+    #[test]
+    fn valid() {
+        let settings = InputSettings {
+            mouse_sensitivity: -1.0,
+            turn_speed: -1.0,
+            dead_zone: 2.0,
+            invert_x: true,
+            ..InputSettings::default()
+        }
+        .valid();
+        assert_eq!(settings.mouse_sensitivity, 0.0);
+        assert_eq!(settings.turn_speed, 0.0);
+        assert_eq!(settings.dead_zone, 0.99);
+        assert!(settings.invert_x);
+    }
+}
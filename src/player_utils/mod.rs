@@ -1,8 +1,16 @@
 mod angle;
+mod camera_effects;
+mod input;
 mod move_handler;
+mod movement;
 mod player;
+mod player_handle;
 mod radians;
 
 pub use angle::Angle;
+pub use camera_effects::{CameraEffects, CameraOffset};
+pub use input::{Axis, InputSettings};
+pub use movement::{Movement, SpeedMultiplierId};
 pub use player::*;
+pub use player_handle::PlayerHandle;
 pub use radians::{Radians, PI_2};
@@ -1,5 +1,5 @@
-use crate::graph;
-use std::time::SystemTime;
+use super::input::Axis;
+use crate::graph::Coordinate;
 
 #[cfg(test)]
 use mockall::{automock, predicate::*};
@@ -10,11 +10,9 @@ pub struct MoveHandler {
     move_right: bool,
     move_forward: bool,
     move_backward: bool,
-    move_left_last_time: SystemTime,
-    move_right_last_time: SystemTime,
-    move_forward_last_time: SystemTime,
-    move_backward_last_time: SystemTime,
-    position: graph::Coordinate,
+    turn_left: bool,
+    turn_right: bool,
+    axes: [f64; Axis::COUNT],
 }
 
 #[cfg_attr(test, automock)]
@@ -25,110 +23,98 @@ impl MoveHandler {
             move_right: false,
             move_forward: false,
             move_backward: false,
-            move_left_last_time: SystemTime::now(),
-            move_right_last_time: SystemTime::now(),
-            move_forward_last_time: SystemTime::now(),
-            move_backward_last_time: SystemTime::now(),
-            position: graph::Coordinate { x: 0.0, y: 0.0 },
-        }
-    }
-
-    fn _move(is_move: bool, move_flag: &mut bool, move_last_time: &mut SystemTime) {
-        if is_move == true {
-            if *move_flag == false {
-                *move_last_time = SystemTime::now();
-            }
-            *move_flag = true;
-        } else {
-            *move_flag = false;
+            turn_left: false,
+            turn_right: false,
+            axes: [0.0; Axis::COUNT],
         }
     }
 
     pub fn move_left(&mut self, is_move: bool) {
-        Self::_move(is_move, &mut self.move_left, &mut self.move_left_last_time);
+        self.move_left = is_move;
     }
 
     pub fn move_right(&mut self, is_move: bool) {
-        Self::_move(
-            is_move,
-            &mut self.move_right,
-            &mut self.move_right_last_time,
-        );
+        self.move_right = is_move;
     }
 
     pub fn move_forward(&mut self, is_move: bool) {
-        Self::_move(
-            is_move,
-            &mut self.move_forward,
-            &mut self.move_forward_last_time,
-        );
+        self.move_forward = is_move;
     }
 
     pub fn move_backward(&mut self, is_move: bool) {
-        Self::_move(
-            is_move,
-            &mut self.move_backward,
-            &mut self.move_backward_last_time,
-        );
+        self.move_backward = is_move;
     }
 
-    pub fn get_move_forward_backward_value(&mut self) -> Option<f64> {
-        if self.move_forward == true && self.move_backward == true {
-            self.move_forward_last_time = SystemTime::now();
-            self.move_backward_last_time = SystemTime::now();
-            return None;
-        }
-        if self.move_forward == true && self.move_backward == false {
-            let move_distance =
-                self.move_forward_last_time.elapsed().unwrap().as_nanos() as f64 / 50000000.0;
-            self.move_forward_last_time = SystemTime::now();
-            return Some(move_distance);
-        }
-        if self.move_backward == true && self.move_forward == false {
-            let move_distance =
-                -(self.move_backward_last_time.elapsed().unwrap().as_nanos() as f64 / 50000000.0);
-            self.move_backward_last_time = SystemTime::now();
-            return Some(move_distance);
-        }
-        return None;
+    pub fn turn_left(&mut self, is_turn: bool) {
+        self.turn_left = is_turn;
     }
 
-    pub fn get_move_left_right_value(&mut self) -> Option<f64> {
-        if self.move_right == true && self.move_left == true {
-            self.move_right_last_time = SystemTime::now();
-            self.move_left_last_time = SystemTime::now();
-            return None;
-        }
-        if self.move_right == true && self.move_left == false {
-            let move_distance =
-                -(self.move_right_last_time.elapsed().unwrap().as_nanos() as f64 / 50000000.0);
-            self.move_right_last_time = SystemTime::now();
-            return Some(move_distance);
+    pub fn turn_right(&mut self, is_turn: bool) {
+        self.turn_right = is_turn;
+    }
+
+    pub fn set_axis(&mut self, axis: Axis, value: f64) {
+        self.axes[axis.index()] = value;
+    }
+
+    fn key_value(positive: bool, negative: bool) -> f64 {
+        match (positive, negative) {
+            (true, false) => 1.0,
+            (false, true) => -1.0,
+            _ => 0.0,
         }
-        if self.move_left == true && self.move_right == false {
-            let move_distance =
-                self.move_left_last_time.elapsed().unwrap().as_nanos() as f64 / 50000000.0;
-            self.move_left_last_time = SystemTime::now();
-            return Some(move_distance);
+    }
+
+    // Direction of the pressed keys and the move axes relative to the player,
+    // x is forward and y is left. Direction longer than 1 is shortened, so the
+    // diagonal move isn't faster than the straight one. Opposite keys cancel
+    // each other.
+    pub fn get_move_direction(&self) -> Coordinate {
+        let forward = Self::key_value(self.move_forward, self.move_backward)
+            + self.axes[Axis::MoveForward.index()];
+        let left =
+            Self::key_value(self.move_left, self.move_right) - self.axes[Axis::MoveRight.index()];
+        let length = forward.hypot(left).max(1.0);
+        Coordinate {
+            x: forward / length,
+            y: left / length,
         }
-        return None;
+    }
+
+    // turn of the arrow keys and the Turn axis in [-1, 1], positive turns
+    // to the right
+    pub fn get_turn_value(&self) -> f64 {
+        (Self::key_value(self.turn_right, self.turn_left) + self.axes[Axis::Turn.index()])
+            .clamp(-1.0, 1.0)
+    }
+
+    pub fn get_look_up_value(&self) -> f64 {
+        self.axes[Axis::LookUp.index()]
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::{thread, time};
-
-    type AssertValue = fn(Option<f64>);
+    use float_cmp::approx_eq;
+
+    fn check_direction(move_handler: &MoveHandler, direction: Coordinate) {
+        let move_direction = move_handler.get_move_direction();
+        assert!(
+            approx_eq!(f64, move_direction.x, direction.x, epsilon = 1e-9)
+                && approx_eq!(f64, move_direction.y, direction.y, epsilon = 1e-9),
+            "{:?} != {:?}",
+            move_direction,
+            direction
+        );
+    }
 
     fn check_move(
         move_left: bool,
         move_right: bool,
         move_forward: bool,
         move_backward: bool,
-        assert_left_right: AssertValue,
-        assert_forward_backward: AssertValue,
+        direction: Coordinate,
     ) {
         let mut move_handler = MoveHandler::new();
 
@@ -136,42 +122,81 @@ mod tests {
         move_handler.move_right(move_right);
         move_handler.move_forward(move_forward);
         move_handler.move_backward(move_backward);
-        thread::sleep(time::Duration::from_millis(50));
 
-        assert_left_right(move_handler.get_move_left_right_value());
-        assert_forward_backward(move_handler.get_move_forward_backward_value());
+        check_direction(&move_handler, direction);
     }
 
     #[test]
     fn handle_move() {
-        let assert_greater = |value_option| {
-            if let Some(value) = value_option {
-                assert!(value > 0.0);
-            } else {
-                panic!("assert_greater value contains None");
-            }
-        };
-
-        let assert_less = |value_option| {
-            if let Some(value) = value_option {
-                assert!(value < 0.0);
-            } else {
-                panic!("assert_less value contains None");
-            }
-        };
-
-        let assert_none = |option_forward_backward_value| {
-            assert_eq!(option_forward_backward_value, None);
-        };
-
-        check_move(true, false, false, false, assert_greater, assert_none);
-        check_move(false, true, false, false, assert_less, assert_none);
-        check_move(true, true, false, false, assert_none, assert_none);
-
-        check_move(false, false, true, false, assert_none, assert_greater);
-        check_move(false, false, false, true, assert_none, assert_less);
-        check_move(false, false, true, true, assert_none, assert_none);
-
-        check_move(true, true, true, true, assert_none, assert_none);
+        check_move(true, false, false, false, Coordinate { x: 0.0, y: 1.0 });
+        check_move(false, true, false, false, Coordinate { x: 0.0, y: -1.0 });
+        check_move(true, true, false, false, Coordinate::ZERO);
+
+        check_move(false, false, true, false, Coordinate { x: 1.0, y: 0.0 });
+        check_move(false, false, false, true, Coordinate { x: -1.0, y: 0.0 });
+        check_move(false, false, true, true, Coordinate::ZERO);
+
+        check_move(true, true, true, true, Coordinate::ZERO);
+
+        // diagonal move has the same speed as the straight one
+        let diagonal = std::f64::consts::FRAC_1_SQRT_2;
+        check_move(
+            true,
+            false,
+            true,
+            false,
+            Coordinate {
+                x: diagonal,
+                y: diagonal,
+            },
+        );
+        check_move(
+            false,
+            true,
+            false,
+            true,
+            Coordinate {
+                x: -diagonal,
+                y: -diagonal,
+            },
+        );
+    }
+
+    #[test]
+    fn release_move() {
+        let mut move_handler = MoveHandler::new();
+        move_handler.move_forward(true);
+        move_handler.move_forward(false);
+        assert_eq!(move_handler.get_move_direction(), Coordinate::ZERO);
+    }
+
+    #[test]
+    fn handle_axes() {
+        let mut move_handler = MoveHandler::new();
+        move_handler.set_axis(Axis::MoveForward, 0.5);
+        check_direction(&move_handler, Coordinate { x: 0.5, y: 0.0 });
+        move_handler.set_axis(Axis::MoveRight, 0.5);
+        check_direction(&move_handler, Coordinate { x: 0.5, y: -0.5 });
+
+        // keys and axes are added, too long direction is shortened
+        move_handler.move_forward(true);
+        check_direction(
+            &move_handler,
+            Coordinate {
+                x: 1.5 / 2.5_f64.sqrt(),
+                y: -0.5 / 2.5_f64.sqrt(),
+            },
+        );
+
+        move_handler.set_axis(Axis::Turn, -0.25);
+        assert_eq!(move_handler.get_turn_value(), -0.25);
+        move_handler.turn_left(true);
+        assert_eq!(move_handler.get_turn_value(), -1.0);
+        move_handler.turn_left(false);
+        move_handler.turn_right(true);
+        assert_eq!(move_handler.get_turn_value(), 0.75);
+
+        move_handler.set_axis(Axis::LookUp, -0.5);
+        assert_eq!(move_handler.get_look_up_value(), -0.5);
     }
 }
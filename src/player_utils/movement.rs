@@ -0,0 +1,260 @@
+use crate::graph::Coordinate;
+
+// Velocity model of the walking player. Speeds are in map units per second,
+// acceleration and friction in map units per second squared. Friction slows
+// the player down all the time, so the acceleration has to be higher than the
+// friction to reach the max speed.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Movement {
+    pub acceleration: f64,
+    pub friction: f64,
+    pub max_walk_speed: f64,
+    pub max_run_speed: f64,
+    pub max_slow_walk_speed: f64,
+}
+
+impl Default for Movement {
+    fn default() -> Self {
+        Self {
+            acceleration: 150.0,
+            friction: 60.0,
+            max_walk_speed: 20.0,
+            max_run_speed: 35.0,
+            max_slow_walk_speed: 8.0,
+        }
+    }
+}
+
+impl Movement {
+    // negative values are replaced by zero
+    pub(crate) fn non_negative(&self) -> Self {
+        Self {
+            acceleration: self.acceleration.max(0.0),
+            friction: self.friction.max(0.0),
+            max_walk_speed: self.max_walk_speed.max(0.0),
+            max_run_speed: self.max_run_speed.max(0.0),
+            max_slow_walk_speed: self.max_slow_walk_speed.max(0.0),
+        }
+    }
+
+    // slow walk wins when the player both runs and walks slowly
+    pub(crate) fn max_speed(&self, is_running: bool, is_walking_slowly: bool) -> f64 {
+        if is_walking_slowly {
+            self.max_slow_walk_speed
+        } else if is_running {
+            self.max_run_speed
+        } else {
+            self.max_walk_speed
+        }
+    }
+
+    // Velocity after dt seconds. Direction is the wanted move direction with
+    // the length up to 1, shorter direction of the analog axis lowers the max
+    // speed by its length. Friction only stops the player, it never turns the
+    // velocity back.
+    pub(crate) fn update_velocity(
+        &self,
+        velocity: &Coordinate,
+        direction: &Coordinate,
+        max_speed: f64,
+        dt: f64,
+    ) -> Coordinate {
+        let speed = velocity.x.hypot(velocity.y);
+        let slowed = if speed > 0.0 {
+            (speed - self.friction * dt).max(0.0) / speed
+        } else {
+            0.0
+        };
+        let x = velocity.x * slowed + direction.x * self.acceleration * dt;
+        let y = velocity.y * slowed + direction.y * self.acceleration * dt;
+        let length = direction.x.hypot(direction.y);
+        let max_speed = if length > 0.0 {
+            max_speed * length.min(1.0)
+        } else {
+            max_speed
+        };
+        let speed = x.hypot(y);
+        let limited = if speed > max_speed && speed > 0.0 {
+            max_speed / speed
+        } else {
+            1.0
+        };
+        Coordinate {
+            x: x * limited,
+            y: y * limited,
+        }
+    }
+}
+
+// Identifies the multiplier added to the player
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SpeedMultiplierId(usize);
+
+#[derive(Clone, Debug)]
+struct SpeedMultiplier {
+    id: SpeedMultiplierId,
+    multiplier: f64,
+    // seconds left, multiplier without the duration stays until it is removed
+    duration: Option<f64>,
+}
+
+// Temporary multipliers of the max speed from the game effects, e.g. slowing
+// zones or power-ups. All multipliers are applied together.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct SpeedMultipliers {
+    multipliers: Vec<SpeedMultiplier>,
+    next_id: usize,
+}
+
+impl SpeedMultipliers {
+    // negative multiplier is replaced by zero
+    pub(crate) fn add(&mut self, multiplier: f64, duration: Option<f64>) -> SpeedMultiplierId {
+        let id = SpeedMultiplierId(self.next_id);
+        self.next_id += 1;
+        self.multipliers.push(SpeedMultiplier {
+            id,
+            multiplier: multiplier.max(0.0),
+            duration,
+        });
+        id
+    }
+
+    // returns false when the multiplier was already removed or expired
+    pub(crate) fn remove(&mut self, id: SpeedMultiplierId) -> bool {
+        let count = self.multipliers.len();
+        self.multipliers.retain(|multiplier| multiplier.id != id);
+        self.multipliers.len() != count
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.multipliers.clear();
+    }
+
+    pub(crate) fn value(&self) -> f64 {
+        self.multipliers
+            .iter()
+            .map(|multiplier| multiplier.multiplier)
+            .product()
+    }
+
+    // removes multipliers which expired during dt
+    pub(crate) fn update(&mut self, dt: f64) {
+        for multiplier in self.multipliers.iter_mut() {
+            if let Some(duration) = multiplier.duration.as_mut() {
+                *duration -= dt;
+            }
+        }
+        self.multipliers
+            .retain(|multiplier| match multiplier.duration {
+                Some(duration) => duration > 0.0,
+                None => true,
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    fn movement() -> Movement {
+        Movement {
+            acceleration: 10.0,
+            friction: 4.0,
+            max_walk_speed: 5.0,
+            max_run_speed: 8.0,
+            max_slow_walk_speed: 2.0,
+        }
+    }
+
+    fn assert_velocity(velocity: Coordinate, x: f64, y: f64) {
+        assert!(
+            approx_eq!(f64, velocity.x, x, epsilon = 1e-9)
+                && approx_eq!(f64, velocity.y, y, epsilon = 1e-9),
+            "{:?} != ({}, {})",
+            velocity,
+            x,
+            y
+        );
+    }
+
+    #[test]
+    fn update_velocity() {
+        let movement = movement();
+        let direction = Coordinate { x: 1.0, y: 0.0 };
+
+        // player starts slowly
+        let velocity = movement.update_velocity(&Coordinate::ZERO, &direction, 5.0, 0.1);
+        assert_velocity(velocity.clone(), 1.0, 0.0);
+        // friction is applied to the current velocity before the acceleration
+        let velocity = movement.update_velocity(&velocity, &direction, 5.0, 0.1);
+        assert_velocity(velocity.clone(), 1.6, 0.0);
+        // speed is limited
+        let velocity = movement.update_velocity(&velocity, &direction, 5.0, 10.0);
+        assert_velocity(velocity.clone(), 5.0, 0.0);
+
+        // without the input the friction stops the player
+        let velocity = movement.update_velocity(&velocity, &Coordinate::ZERO, 5.0, 0.5);
+        assert_velocity(velocity.clone(), 3.0, 0.0);
+        let velocity = movement.update_velocity(&velocity, &Coordinate::ZERO, 5.0, 10.0);
+        assert_velocity(velocity, 0.0, 0.0);
+
+        // diagonal speed is limited by the same max speed
+        let velocity = movement.update_velocity(
+            &Coordinate { x: 5.0, y: 5.0 },
+            &Coordinate { x: 0.6, y: 0.8 },
+            5.0,
+            0.0,
+        );
+        assert_velocity(velocity, 5.0 / 2.0_f64.sqrt(), 5.0 / 2.0_f64.sqrt());
+
+        // half tilted stick walks at the half speed
+        let velocity =
+            movement.update_velocity(&Coordinate::ZERO, &Coordinate { x: 0.0, y: 0.5 }, 5.0, 10.0);
+        assert_velocity(velocity, 0.0, 2.5);
+    }
+
+    #[test]
+    fn max_speed_and_non_negative() {
+        let movement = movement();
+        assert_eq!(movement.max_speed(false, false), 5.0);
+        assert_eq!(movement.max_speed(true, false), 8.0);
+        assert_eq!(movement.max_speed(false, true), 2.0);
+        assert_eq!(movement.max_speed(true, true), 2.0);
+
+        let movement = Movement {
+            acceleration: -1.0,
+            friction: -2.0,
+            ..movement
+        }
+        .non_negative();
+        assert_eq!(movement.acceleration, 0.0);
+        assert_eq!(movement.friction, 0.0);
+        assert_eq!(movement.max_walk_speed, 5.0);
+    }
+
+    #[test]
+    fn speed_multipliers() {
+        let mut multipliers = SpeedMultipliers::default();
+        assert_eq!(multipliers.value(), 1.0);
+
+        let zone = multipliers.add(0.5, None);
+        let power_up = multipliers.add(3.0, Some(1.0));
+        assert_ne!(zone, power_up);
+        assert_eq!(multipliers.value(), 1.5);
+
+        // power-up expires, the zone stays until it is removed
+        multipliers.update(0.5);
+        assert_eq!(multipliers.value(), 1.5);
+        multipliers.update(0.5);
+        assert_eq!(multipliers.value(), 0.5);
+        assert!(!multipliers.remove(power_up));
+        assert!(multipliers.remove(zone));
+        assert_eq!(multipliers.value(), 1.0);
+
+        multipliers.add(-1.0, None);
+        assert_eq!(multipliers.value(), 0.0);
+        multipliers.clear();
+        assert_eq!(multipliers.value(), 1.0);
+    }
+}
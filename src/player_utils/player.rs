@@ -1,6 +1,10 @@
 use super::angle::Angle;
+use super::camera_effects::{CameraEffects, CameraOffset, CameraState};
+use super::input::{Axis, InputSettings};
+use super::movement::{Movement, SpeedMultiplierId, SpeedMultipliers};
 use super::radians::Radians;
 use crate::graph::{Coordinate, LinearGraph, Rays};
+use crate::map_element::MapElements;
 use mockall_double::double;
 
 #[cfg(test)]
@@ -15,12 +19,64 @@ pub struct Player {
     pub position: Coordinate,
     number_of_rays: usize,
     move_handler: MoveHandler,
+    // horizontal field of view without the zoom
+    field_of_view: Radians,
+    zoom: f64,
+    // vertical look angle, positive when the player looks up
+    pitch: f64,
+    max_pitch: f64,
+    // height of the floor under the player, e.g. stair steps
+    floor_height: f64,
+    // height above the floor and the vertical velocity during the jump
+    jump_height: f64,
+    vertical_velocity: f64,
+    is_crouching: bool,
+    // highest floor difference the player can walk up
+    step_height: f64,
+    // horizontal velocity in the map coordinates
+    velocity: Coordinate,
+    movement: Movement,
+    is_running: bool,
+    is_walking_slowly: bool,
+    speed_multipliers: SpeedMultipliers,
+    camera_effects: CameraEffects,
+    camera: CameraState,
+    input_settings: InputSettings,
 }
 
+const JUMP_VELOCITY: f64 = 4.0;
+const GRAVITY: f64 = 10.0;
+// crouching moves the eye down by this value
+const CROUCH_DEPTH: f64 = 1.0;
+const DEFAULT_STEP_HEIGHT: f64 = 0.5;
+// shorter slides along the step are rounding errors of the blocked move
+const MIN_SLIDE: f64 = 1e-9;
+
+const DEFAULT_MAX_PITCH: f64 = std::f64::consts::PI / 6.0;
+// looking straight up or down can't be rendered with the y-shearing
+const MAX_PITCH_LIMIT: f64 = std::f64::consts::PI * 0.45;
+
 #[cfg_attr(test, automock)]
 impl Player {
     pub fn new(angle: Angle, position: Coordinate, number_of_rays: usize) -> Player {
         Player {
+            field_of_view: angle.value(),
+            zoom: 1.0,
+            pitch: 0.0,
+            max_pitch: DEFAULT_MAX_PITCH,
+            floor_height: 0.0,
+            jump_height: 0.0,
+            vertical_velocity: 0.0,
+            is_crouching: false,
+            step_height: DEFAULT_STEP_HEIGHT,
+            velocity: Coordinate::ZERO,
+            movement: Movement::default(),
+            is_running: false,
+            is_walking_slowly: false,
+            speed_multipliers: SpeedMultipliers::default(),
+            camera_effects: CameraEffects::default(),
+            camera: CameraState::default(),
+            input_settings: InputSettings::default(),
             angle,
             position,
             number_of_rays,
@@ -39,20 +95,258 @@ impl Player {
         &self.position
     }
 
-    fn move_forward_backward(&mut self, distance: f64) {
-        let direction = LinearGraph::from_radians(self.angle.get_direction());
-        self.position = direction.get_next_from_distance(&self.position, distance);
+    // turns the direction relative to the player, x is forward and y is
+    // left, into the map coordinates
+    fn world_direction(&self, direction: &Coordinate) -> Coordinate {
+        let (sin, cos) = self.angle.get_direction().to_f64().sin_cos();
+        Coordinate {
+            x: direction.x * cos + direction.y * sin,
+            y: direction.x * sin - direction.y * cos,
+        }
+    }
+
+    pub fn velocity(&self) -> &Coordinate {
+        &self.velocity
+    }
+
+    pub fn movement(&self) -> &Movement {
+        &self.movement
     }
 
-    fn move_left_right(&mut self, distance: f64) {
-        let direction = LinearGraph::from_radians(self.angle.get_direction() - Radians::PI / 2.0);
-        self.position = direction.get_next_from_distance(&self.position, distance);
+    // negative values are replaced by zero
+    pub fn set_movement(&mut self, movement: Movement) {
+        self.movement = movement.non_negative();
     }
 
     pub fn get_angle_value(&self) -> Radians {
         self.angle.value()
     }
 
+    pub fn field_of_view(&self) -> Radians {
+        self.field_of_view
+    }
+
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    // field of view has to be in range (0, pi), other values are ignored
+    pub fn set_field_of_view(&mut self, field_of_view: Radians) {
+        if field_of_view > Radians::ZERO && field_of_view < Radians::PI {
+            self.field_of_view = field_of_view;
+            self.update_angle_value();
+        }
+    }
+
+    // zoom 2.0 makes everything twice as big, zoom has to be positive
+    pub fn set_zoom(&mut self, zoom: f64) {
+        if zoom > 0.0 {
+            self.zoom = zoom;
+            self.update_angle_value();
+        }
+    }
+
+    pub fn pitch(&self) -> f64 {
+        self.pitch
+    }
+
+    // pitch is clamped to the [-max_pitch, max_pitch] range, pitch which is
+    // not finite is ignored
+    pub fn set_pitch(&mut self, pitch: f64) {
+        if !pitch.is_finite() {
+            return;
+        }
+        self.pitch = pitch.clamp(-self.max_pitch, self.max_pitch);
+    }
+
+    // max pitch which is not finite is ignored
+    pub fn set_max_pitch(&mut self, max_pitch: f64) {
+        if !max_pitch.is_finite() {
+            return;
+        }
+        self.max_pitch = max_pitch.clamp(0.0, MAX_PITCH_LIMIT);
+        self.set_pitch(self.pitch);
+    }
+
+    pub(crate) fn look_up(&mut self, pitch_delta: f64) {
+        self.set_pitch(self.pitch + pitch_delta);
+    }
+
+    // positive angle turns the same way as the mouse moved to the right
+    fn turn(&mut self, angle: f64) {
+        if angle > 0.0 {
+            self.rotate_left(Radians::new(angle));
+        } else if angle < 0.0 {
+            self.rotate_right(Radians::new(-angle));
+        }
+    }
+
+    // turns and looks up by the mouse move in pixels
+    pub(crate) fn mouse_look(&mut self, dx: f64, dy: f64) {
+        let (turn, pitch_delta) = self.input_settings.mouse_look(dx, dy);
+        self.turn(turn);
+        if pitch_delta != 0.0 {
+            self.look_up(pitch_delta);
+        }
+    }
+
+    pub fn input_settings(&self) -> &InputSettings {
+        &self.input_settings
+    }
+
+    // negative sensitivity and speeds are replaced by zero
+    pub fn set_input_settings(&mut self, input_settings: InputSettings) {
+        self.input_settings = input_settings.valid();
+    }
+
+    // Value of the analog axis, e.g. of the gamepad stick, in [-1, 1]. Dead
+    // zone and inversion of the input settings are applied. Axis keeps the
+    // value until it is set again.
+    pub fn set_axis(&mut self, axis: Axis, value: f64) {
+        let value = self.input_settings.axis_value(axis, value);
+        self.move_handler.set_axis(axis, value);
+    }
+
+    // offset of the eye from the standing eye height, which is in the middle
+    // of the wall
+    pub fn eye_offset(&self) -> f64 {
+        let crouch_offset = if self.is_crouching { CROUCH_DEPTH } else { 0.0 };
+        self.floor_height + self.jump_height - crouch_offset
+    }
+
+    // eye offset with the camera effects, which is used by the rendering
+    pub fn view_eye_offset(&self) -> f64 {
+        self.eye_offset() + self.camera_offset().eye_offset
+    }
+
+    // pitch with the camera effects, which is used by the rendering
+    pub fn view_pitch(&self) -> f64 {
+        (self.pitch + self.camera_offset().pitch).clamp(-MAX_PITCH_LIMIT, MAX_PITCH_LIMIT)
+    }
+
+    // roll of the view in radians, counterclockwise on the screen
+    pub fn view_roll(&self) -> f64 {
+        self.camera_offset().roll
+    }
+
+    pub fn camera_offset(&self) -> CameraOffset {
+        self.camera.offset(&self.camera_effects)
+    }
+
+    pub fn camera_effects(&self) -> &CameraEffects {
+        &self.camera_effects
+    }
+
+    pub fn set_camera_effects(&mut self, camera_effects: CameraEffects) {
+        self.camera_effects = camera_effects;
+    }
+
+    // Shakes the view, e.g. after the explosion. Intensity 1 is the full shake
+    // of the camera effects, it fades out by itself.
+    pub fn shake_camera(&mut self, intensity: f64) {
+        self.camera.shake(intensity);
+    }
+
+    // floor is changed to the sector floor when the player moves
+    pub fn set_floor_height(&mut self, floor_height: f64) {
+        self.floor_height = floor_height;
+    }
+
+    pub fn set_step_height(&mut self, step_height: f64) {
+        self.step_height = step_height.max(0.0);
+    }
+
+    // Moves the player back to the position when the floor is higher than the
+    // step height, otherwise the player stands on the new floor.
+    fn step(&mut self, position: Coordinate, map_elements: &MapElements) -> bool {
+        let floor_height = map_elements.floor_height_at(&self.position);
+        if floor_height - self.floor_height > self.step_height {
+            self.position = position;
+            return false;
+        }
+        self.floor_height = floor_height;
+        self.pass_portal(&position, map_elements);
+        true
+    }
+
+    // moves the player who walked into the portal out of its other end,
+    // mirrors only reflect the rays
+    fn pass_portal(&mut self, position: &Coordinate, map_elements: &MapElements) {
+        let Some(crossing) = map_elements.portal_crossing(position, &self.position) else {
+            return;
+        };
+        if !crossing.transform.is_mirrored() {
+            self.position = crossing.transform.apply(&self.position);
+            self.angle.rotate_left(crossing.transform.angle());
+            self.velocity = crossing.transform.rotate(&self.velocity);
+            self.floor_height = map_elements.floor_height_at(&self.position);
+        }
+    }
+
+    // player can't jump again before landing
+    pub(crate) fn jump(&mut self) {
+        if self.jump_height == 0.0 && self.vertical_velocity == 0.0 {
+            self.vertical_velocity = JUMP_VELOCITY;
+        }
+    }
+
+    pub(crate) fn crouch(&mut self, is_crouching: bool) {
+        self.is_crouching = is_crouching;
+    }
+
+    pub(crate) fn run(&mut self, is_running: bool) {
+        self.is_running = is_running;
+    }
+
+    pub(crate) fn walk_slowly(&mut self, is_walking_slowly: bool) {
+        self.is_walking_slowly = is_walking_slowly;
+    }
+
+    // Multiplies the max speed, e.g. 0.5 in the slowing zone. Multiplier with
+    // the duration in seconds is removed when it expires, the other one when
+    // it is removed by the id.
+    pub fn add_speed_multiplier(
+        &mut self,
+        multiplier: f64,
+        duration: Option<f64>,
+    ) -> SpeedMultiplierId {
+        self.speed_multipliers.add(multiplier, duration)
+    }
+
+    // returns false when the multiplier was already removed or expired
+    pub fn remove_speed_multiplier(&mut self, id: SpeedMultiplierId) -> bool {
+        self.speed_multipliers.remove(id)
+    }
+
+    pub fn clear_speed_multipliers(&mut self) {
+        self.speed_multipliers.clear();
+    }
+
+    // product of all active multipliers
+    pub fn speed_multiplier(&self) -> f64 {
+        self.speed_multipliers.value()
+    }
+
+    // moves the player along the jump arc, dt is the time since the last update
+    pub(crate) fn update_height(&mut self, dt: f64) {
+        if self.jump_height == 0.0 && self.vertical_velocity == 0.0 {
+            return;
+        }
+        self.jump_height += self.vertical_velocity * dt - GRAVITY * dt * dt / 2.0;
+        self.vertical_velocity -= GRAVITY * dt;
+        if self.jump_height <= 0.0 {
+            self.camera.land(self.vertical_velocity);
+            self.jump_height = 0.0;
+            self.vertical_velocity = 0.0;
+        }
+    }
+
+    fn update_angle_value(&mut self) {
+        let half_tangens = (self.field_of_view / 2.0).tan() / self.zoom;
+        self.angle
+            .set_value(Radians::new(half_tangens.atan() * 2.0));
+    }
+
     pub(crate) fn get_all_rays(&self) -> Rays {
         LinearGraph::get_all_rays(self.number_of_rays)
     }
@@ -81,18 +375,73 @@ impl Player {
         self.move_handler.move_backward(is_move)
     }
 
-    pub(crate) fn update(&mut self) -> bool {
-        let mut is_updated = false;
-        if let Some(forward_backward_value) = self.move_handler.get_move_forward_backward_value() {
-            is_updated = true;
-            self.move_forward_backward(forward_backward_value)
+    pub(crate) fn turn_left(&mut self, is_turn: bool) {
+        self.move_handler.turn_left(is_turn)
+    }
+
+    pub(crate) fn turn_right(&mut self, is_turn: bool) {
+        self.move_handler.turn_right(is_turn)
+    }
+
+    // sideways speed is positive when the player moves to the left
+    fn update_camera(&mut self, dt: f64) {
+        let left = self.world_direction(&Coordinate { x: 0.0, y: 1.0 });
+        let speed = self.velocity.x.hypot(self.velocity.y);
+        let sideways_speed = self.velocity.x * left.x + self.velocity.y * left.y;
+        let is_on_ground = self.jump_height == 0.0;
+        self.camera.update(
+            &self.camera_effects,
+            dt,
+            speed,
+            sideways_speed,
+            is_on_ground,
+        );
+    }
+
+    fn move_by(&mut self, x: f64, y: f64, map_elements: &MapElements) -> bool {
+        let position = self.position.clone();
+        self.position += &Coordinate { x, y };
+        self.step(position, map_elements)
+    }
+
+    // Turns the player by the arrow keys and the axes, accelerates them in
+    // the direction of the pressed keys and moves them by the velocity, dt is
+    // the time since the last update. Move blocked by the step is tried along
+    // each axis, so the player slides along the step.
+    pub(crate) fn update(&mut self, map_elements: &MapElements, dt: f64) -> bool {
+        self.turn(self.move_handler.get_turn_value() * self.input_settings.turn_speed * dt);
+        let look_up = self.move_handler.get_look_up_value();
+        if look_up != 0.0 {
+            self.look_up(look_up * self.input_settings.look_speed * dt);
+        }
+        let direction = self.world_direction(&self.move_handler.get_move_direction());
+        self.speed_multipliers.update(dt);
+        let max_speed = self
+            .movement
+            .max_speed(self.is_running, self.is_walking_slowly)
+            * self.speed_multipliers.value();
+        self.velocity = self
+            .movement
+            .update_velocity(&self.velocity, &direction, max_speed, dt);
+        self.update_camera(dt);
+        if self.velocity == Coordinate::ZERO {
+            return false;
         }
 
-        if let Some(right_left_value) = self.move_handler.get_move_left_right_value() {
-            is_updated = true;
-            self.move_left_right(right_left_value)
+        let (x, y) = (self.velocity.x * dt, self.velocity.y * dt);
+        if self.move_by(x, y, map_elements) {
+            return true;
+        }
+        if x.abs() > MIN_SLIDE && self.move_by(x, 0.0, map_elements) {
+            self.velocity.y = 0.0;
+            return true;
+        }
+        if y.abs() > MIN_SLIDE && self.move_by(0.0, y, map_elements) {
+            self.velocity.x = 0.0;
+            return true;
         }
-        return is_updated;
+        self.velocity = Coordinate::ZERO;
+        false
     }
 
     #[cfg(test)]
@@ -104,10 +453,36 @@ impl Player {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::map_element::{MapElement, Point, Portal, Rectangle, Sector, Segment};
+    use float_cmp::approx_eq;
+    use std::sync::{Arc, Mutex};
+
+    // reaches the speed of 10 in the first 0.1 seconds and moves by 1
+    fn fast_movement() -> Movement {
+        Movement {
+            acceleration: 100.0,
+            ..Movement::default()
+        }
+    }
+
+    fn expect_no_turn(move_handler: &mut MoveHandler) {
+        move_handler.expect_get_turn_value().return_const(0.0);
+        move_handler.expect_get_look_up_value().return_const(0.0);
+    }
+
+    fn assert_coordinate(coordinate: &Coordinate, x: f64, y: f64) {
+        assert!(
+            approx_eq!(f64, coordinate.x, x, epsilon = 1e-9)
+                && approx_eq!(f64, coordinate.y, y, epsilon = 1e-9),
+            "{:?} != ({}, {})",
+            coordinate,
+            x,
+            y
+        );
+    }
 
     fn check_update(
-        forward_backward_value: Option<f64>,
-        right_left_value: Option<f64>,
+        direction: Coordinate,
         start_position: Coordinate,
         updated_postion: Coordinate,
     ) {
@@ -118,23 +493,124 @@ mod tests {
         let mut move_handler = MoveHandler::default();
 
         move_handler
-            .expect_get_move_forward_backward_value()
+            .expect_get_move_direction()
             .times(1)
-            .return_const(forward_backward_value);
-        move_handler
-            .expect_get_move_left_right_value()
-            .times(1)
-            .return_const(right_left_value);
+            .return_const(direction);
+        expect_no_turn(&mut move_handler);
 
         let mut player = Player {
             angle,
             position: start_position,
             number_of_rays: 0,
             move_handler,
+            field_of_view: Default::default(),
+            zoom: 1.0,
+            pitch: 0.0,
+            max_pitch: DEFAULT_MAX_PITCH,
+            floor_height: 0.0,
+            jump_height: 0.0,
+            vertical_velocity: 0.0,
+            is_crouching: false,
+            step_height: DEFAULT_STEP_HEIGHT,
+            velocity: Coordinate::ZERO,
+            movement: fast_movement(),
+            is_running: false,
+            is_walking_slowly: false,
+            speed_multipliers: SpeedMultipliers::default(),
+            camera_effects: CameraEffects::default(),
+            camera: CameraState::default(),
+            input_settings: InputSettings::default(),
+        };
+        player.update(&MapElements::new(), 0.1);
+
+        assert_coordinate(&player.position, updated_postion.x, updated_postion.y);
+    }
+
+    #[test]
+    fn player_update_step() {
+        let check_step = |sector_floor_height: f64, updated_postion: Coordinate, is_updated| {
+            let mut move_handler = MoveHandler::default();
+            move_handler
+                .expect_get_move_direction()
+                .return_const(Coordinate { x: 1.0, y: 0.0 });
+            let mut player = Player::new(
+                Angle {
+                    start: Radians::PI / 4.0,
+                    end: Radians::new(std::f64::consts::PI * 3.0 / 4.0),
+                },
+                Coordinate { x: 10.5, y: -0.5 },
+                0,
+            );
+            expect_no_turn(&mut move_handler);
+            player.move_handler = move_handler;
+            player.set_movement(fast_movement());
+            let map_elements = MapElements::from(vec![Box::new(Sector::new(
+                Rectangle {
+                    point_a: Point { x: 10, y: 0 },
+                    point_b: Point { x: 11, y: 1 },
+                },
+                sector_floor_height,
+                None,
+                None,
+                None,
+            )) as Box<dyn MapElement>]);
+
+            assert_eq!(player.update(&map_elements, 0.1), is_updated);
+            assert_coordinate(&player.position, updated_postion.x, updated_postion.y);
+            // blocked player stops
+            assert_eq!(*player.velocity() == Coordinate::ZERO, !is_updated);
+            player.floor_height
         };
-        player.update();
 
-        assert_eq!(player.position, updated_postion);
+        // step lower than the step height is climbed
+        assert_eq!(check_step(0.25, Coordinate { x: 10.5, y: 0.5 }, true), 0.25);
+        // higher step blocks the player
+        assert_eq!(check_step(1.0, Coordinate { x: 10.5, y: -0.5 }, false), 0.0);
+        // player steps down into the pit of any depth
+        assert_eq!(check_step(-3.0, Coordinate { x: 10.5, y: 0.5 }, true), -3.0);
+    }
+
+    #[test]
+    fn player_update_portal() {
+        let mut move_handler = MoveHandler::default();
+        move_handler
+            .expect_get_move_direction()
+            .return_const(Coordinate { x: 1.0, y: 0.0 });
+        // player looks along y axis
+        let mut player = Player::new(
+            Angle {
+                start: Radians::PI / 4.0,
+                end: Radians::new(std::f64::consts::PI * 3.0 / 4.0),
+            },
+            Coordinate { x: 5.5, y: 1.5 },
+            0,
+        );
+        expect_no_turn(&mut move_handler);
+        player.move_handler = move_handler;
+        player.set_movement(fast_movement());
+        let segment = |start: (f64, f64), end: (f64, f64)| Segment {
+            start: Coordinate {
+                x: start.0,
+                y: start.1,
+            },
+            end: Coordinate { x: end.0, y: end.1 },
+        };
+        let map_elements = MapElements::from(vec![Box::new(Portal::new(
+            segment((5.0, 2.0), (6.0, 2.0)),
+            segment((20.0, 10.0), (20.0, 11.0)),
+        )) as Box<dyn MapElement>]);
+
+        assert!(player.update(&map_elements, 0.1));
+        // the other end is turned by a right angle, player looks and moves
+        // along x axis
+        assert_coordinate(&player.position, 20.5, 10.5);
+        assert_coordinate(player.velocity(), 10.0, 0.0);
+        assert!(approx_eq!(
+            f64,
+            player.angle.get_direction().to_f64().cos(),
+            1.0,
+            epsilon = 1e-9
+        ));
     }
 
     #[test]
@@ -216,33 +692,37 @@ mod tests {
     }
 
     #[test]
-    fn move_player() {
-        let angle = Angle {
-            start: Radians::ZERO,
-            end: Radians::PI / 2.0,
-        };
-        let first_position = Coordinate { x: 5.0, y: 8.0 };
-        let second_position = Coordinate { x: 6.0, y: 9.0 };
-        let third_position = Coordinate { x: 6.0, y: 7.0 };
+    fn world_direction() {
+        let player = Player::new(
+            Angle {
+                start: Radians::ZERO,
+                end: Radians::PI / 2.0,
+            },
+            Coordinate { x: 5.0, y: 8.0 },
+            0,
+        );
         let distance = 2.0_f64.sqrt();
 
-        let mut player = Player {
-            angle,
-            position: first_position.clone(),
-            number_of_rays: 0,
-            move_handler: MoveHandler::default(),
-        };
-        player.move_forward_backward(distance);
-        assert_eq!(player.position, second_position);
-
-        player.move_forward_backward(-distance);
-        assert_eq!(player.position, first_position);
-
-        player.move_left_right(distance);
-        assert_eq!(player.position, third_position);
-
-        player.move_left_right(-distance);
-        assert_eq!(player.position, first_position);
+        let forward = player.world_direction(&Coordinate {
+            x: distance,
+            y: 0.0,
+        });
+        assert_coordinate(&forward, 1.0, 1.0);
+        let backward = player.world_direction(&Coordinate {
+            x: -distance,
+            y: 0.0,
+        });
+        assert_coordinate(&backward, -1.0, -1.0);
+        let left = player.world_direction(&Coordinate {
+            x: 0.0,
+            y: distance,
+        });
+        assert_coordinate(&left, 1.0, -1.0);
+        let right = player.world_direction(&Coordinate {
+            x: 0.0,
+            y: -distance,
+        });
+        assert_coordinate(&right, -1.0, 1.0);
     }
 
     #[test]
@@ -300,6 +780,23 @@ mod tests {
             position: position.clone(),
             number_of_rays: 0,
             move_handler,
+            field_of_view: Default::default(),
+            zoom: 1.0,
+            pitch: 0.0,
+            max_pitch: DEFAULT_MAX_PITCH,
+            floor_height: 0.0,
+            jump_height: 0.0,
+            vertical_velocity: 0.0,
+            is_crouching: false,
+            step_height: DEFAULT_STEP_HEIGHT,
+            velocity: Coordinate::ZERO,
+            movement: Movement::default(),
+            is_running: false,
+            is_walking_slowly: false,
+            speed_multipliers: SpeedMultipliers::default(),
+            camera_effects: CameraEffects::default(),
+            camera: CameraState::default(),
+            input_settings: InputSettings::default(),
         };
 
         player.move_right(true);
@@ -315,31 +812,274 @@ mod tests {
     #[test]
     fn update_postion() {
         check_update(
-            Some(1.0),
-            None,
+            Coordinate { x: 1.0, y: 0.0 },
             Coordinate { x: 5.0, y: 8.0 },
             Coordinate { x: 5.0, y: 9.0 },
         );
         check_update(
-            Some(-1.0),
-            None,
+            Coordinate { x: -1.0, y: 0.0 },
             Coordinate { x: 5.0, y: 8.0 },
             Coordinate { x: 5.0, y: 7.0 },
         );
         check_update(
-            None,
-            Some(1.0),
+            Coordinate { x: 0.0, y: 1.0 },
             Coordinate { x: 5.0, y: 8.0 },
             Coordinate { x: 6.0, y: 8.0 },
         );
         check_update(
-            None,
-            Some(-1.0),
+            Coordinate { x: 0.0, y: -1.0 },
             Coordinate { x: 5.0, y: 8.0 },
             Coordinate { x: 4.0, y: 8.0 },
         );
     }
 
+    #[test]
+    fn update_velocity() {
+        let mut move_handler = MoveHandler::default();
+        let mut seq = mockall::Sequence::new();
+        move_handler
+            .expect_get_move_direction()
+            .times(3)
+            .return_const(Coordinate { x: 1.0, y: 0.0 })
+            .in_sequence(&mut seq);
+        move_handler
+            .expect_get_move_direction()
+            .times(2)
+            .return_const(Coordinate::ZERO)
+            .in_sequence(&mut seq);
+        // player looks along y axis
+        let mut player = Player::new(
+            Angle {
+                start: Radians::PI / 4.0,
+                end: Radians::new(std::f64::consts::PI * 3.0 / 4.0),
+            },
+            Coordinate { x: 5.0, y: 8.0 },
+            0,
+        );
+        expect_no_turn(&mut move_handler);
+        player.move_handler = move_handler;
+        let map_elements = MapElements::new();
+        let movement = player.movement().clone();
+
+        // player speeds up to the max walk speed
+        assert!(player.update(&map_elements, 0.1));
+        assert_coordinate(player.velocity(), 0.0, movement.acceleration * 0.1);
+        assert!(player.update(&map_elements, 1.0));
+        assert_coordinate(player.velocity(), 0.0, movement.max_walk_speed);
+        player.run(true);
+        assert!(player.update(&map_elements, 1.0));
+        assert_coordinate(player.velocity(), 0.0, movement.max_run_speed);
+
+        // and slows down when the keys are released
+        assert!(player.update(&map_elements, 0.1));
+        assert_coordinate(
+            player.velocity(),
+            0.0,
+            movement.max_run_speed - movement.friction * 0.1,
+        );
+        assert!(!player.update(&map_elements, 10.0));
+        assert_eq!(*player.velocity(), Coordinate::ZERO);
+    }
+
+    #[test]
+    fn update_speed_modifiers() {
+        let mut move_handler = MoveHandler::default();
+        move_handler
+            .expect_get_move_direction()
+            .return_const(Coordinate { x: 1.0, y: 0.0 });
+        // player looks along y axis
+        let mut player = Player::new(
+            Angle {
+                start: Radians::PI / 4.0,
+                end: Radians::new(std::f64::consts::PI * 3.0 / 4.0),
+            },
+            Coordinate { x: 5.0, y: 8.0 },
+            0,
+        );
+        expect_no_turn(&mut move_handler);
+        player.move_handler = move_handler;
+        let map_elements = MapElements::new();
+        let movement = player.movement().clone();
+
+        // slow walk wins over the run
+        player.run(true);
+        player.walk_slowly(true);
+        player.update(&map_elements, 1.0);
+        assert_coordinate(player.velocity(), 0.0, movement.max_slow_walk_speed);
+        player.walk_slowly(false);
+
+        let zone = player.add_speed_multiplier(0.5, None);
+        let power_up = player.add_speed_multiplier(3.0, Some(1.5));
+        assert_eq!(player.speed_multiplier(), 1.5);
+        player.update(&map_elements, 1.0);
+        assert_coordinate(player.velocity(), 0.0, movement.max_run_speed * 1.5);
+
+        // power-up expires during the update
+        player.update(&map_elements, 1.0);
+        assert_coordinate(player.velocity(), 0.0, movement.max_run_speed * 0.5);
+        assert!(!player.remove_speed_multiplier(power_up));
+        assert!(player.remove_speed_multiplier(zone));
+
+        player.add_speed_multiplier(0.0, None);
+        player.clear_speed_multipliers();
+        assert_eq!(player.speed_multiplier(), 1.0);
+    }
+
+    #[test]
+    fn camera_effects() {
+        let mut move_handler = MoveHandler::default();
+        move_handler
+            .expect_get_move_direction()
+            .return_const(Coordinate::ZERO);
+        let mut player = Player::new(Default::default(), Coordinate { x: 0.0, y: 0.0 }, 100);
+        expect_no_turn(&mut move_handler);
+        player.move_handler = move_handler;
+        assert_eq!(player.camera_offset(), CameraOffset::default());
+
+        // landing moves the eye down and it comes back
+        player.jump();
+        player.update_height(10.0);
+        assert_eq!(player.eye_offset(), 0.0);
+        assert!(player.view_eye_offset() < 0.0);
+        player.update(&MapElements::new(), 10.0);
+        assert!(player.view_eye_offset().abs() < 1e-9);
+
+        player.set_pitch(DEFAULT_MAX_PITCH);
+        player.shake_camera(1000.0);
+        player.update(&MapElements::new(), 0.01);
+        assert_eq!(player.view_pitch().abs(), MAX_PITCH_LIMIT);
+
+        // disabled effects don't change the view
+        player.set_camera_effects(CameraEffects {
+            enabled: false,
+            ..CameraEffects::default()
+        });
+        assert_eq!(player.view_pitch(), DEFAULT_MAX_PITCH);
+        assert_eq!(player.view_roll(), 0.0);
+        assert_eq!(player.view_eye_offset(), 0.0);
+    }
+
+    #[test]
+    fn turn_with_keys() {
+        let mut move_handler = MoveHandler::default();
+        move_handler
+            .expect_turn_left()
+            .times(1)
+            .return_const(())
+            .withf(|is_turn| *is_turn);
+        move_handler
+            .expect_turn_right()
+            .times(1)
+            .return_const(())
+            .withf(|is_turn| !*is_turn);
+        let mut player = Player::new(Default::default(), Coordinate { x: 0.0, y: 0.0 }, 100);
+        player.move_handler = move_handler;
+
+        player.turn_left(true);
+        player.turn_right(false);
+    }
+
+    #[test]
+    fn turn_and_look() {
+        // mocked move handler keeps the axes set by the player
+        let axes = Arc::new(Mutex::new([0.0; Axis::COUNT]));
+        let mut move_handler = MoveHandler::default();
+        move_handler
+            .expect_get_move_direction()
+            .return_const(Coordinate::ZERO);
+        let turn_axes = Arc::clone(&axes);
+        move_handler
+            .expect_get_turn_value()
+            .returning(move || turn_axes.lock().unwrap()[Axis::Turn.index()]);
+        let look_up_axes = Arc::clone(&axes);
+        move_handler
+            .expect_get_look_up_value()
+            .returning(move || look_up_axes.lock().unwrap()[Axis::LookUp.index()]);
+        let set_axes = Arc::clone(&axes);
+        move_handler
+            .expect_set_axis()
+            .returning(move |axis, value| set_axes.lock().unwrap()[axis.index()] = value);
+        let mut player = Player::new(
+            Angle {
+                start: Radians::new(1.0),
+                end: Radians::new(2.0),
+            },
+            Coordinate { x: 0.0, y: 0.0 },
+            100,
+        );
+        player.move_handler = move_handler;
+        player.set_input_settings(InputSettings {
+            mouse_sensitivity: 0.5,
+            invert_x: true,
+            turn_speed: 2.0,
+            look_speed: 0.25,
+            dead_zone: 0.2,
+            ..InputSettings::default()
+        });
+        let assert_view = |player: &Player, angle: f64, pitch: f64| {
+            assert!(
+                approx_eq!(f64, player.angle.start.to_f64(), angle, epsilon = 1e-9)
+                    && approx_eq!(f64, player.pitch(), pitch, epsilon = 1e-9),
+                "({}, {}) != ({}, {})",
+                player.angle.start.to_f64(),
+                player.pitch(),
+                angle,
+                pitch
+            );
+        };
+
+        // half turned stick (after the dead zone) turns by the half of the
+        // turn speed, inverted axis turns the other way
+        player.set_axis(Axis::Turn, 0.6);
+        player.update(&MapElements::new(), 0.1);
+        assert_view(&player, 0.9, 0.0);
+
+        player.set_axis(Axis::LookUp, 0.6);
+        player.update(&MapElements::new(), 0.1);
+        assert_view(&player, 0.8, 0.0125);
+
+        // stick in the dead zone doesn't turn
+        player.set_axis(Axis::Turn, 0.15);
+        player.update(&MapElements::new(), 0.1);
+        assert_view(&player, 0.8, 0.025);
+
+        // too high value is clamped to the full turn
+        player.set_axis(Axis::Turn, -3.0);
+        player.set_axis(Axis::LookUp, -0.1);
+        player.update(&MapElements::new(), 0.1);
+        assert_view(&player, 1.0, 0.025);
+
+        // pitch stops at the max pitch
+        player.set_axis(Axis::Turn, 0.0);
+        player.set_axis(Axis::LookUp, 3.0);
+        player.update(&MapElements::new(), 10.0);
+        assert_view(&player, 1.0, DEFAULT_MAX_PITCH);
+
+        // inverted mouse move to the right turns the other way
+        player.set_axis(Axis::LookUp, 0.0);
+        player.mouse_look(0.2, 0.0);
+        assert_view(&player, 0.9, DEFAULT_MAX_PITCH);
+        player.mouse_look(0.0, 0.025);
+        assert_view(&player, 0.9, DEFAULT_MAX_PITCH - 0.0125);
+    }
+
+    #[test]
+    fn set_movement() {
+        let mut player = Player::new(Default::default(), Coordinate { x: 0.0, y: 0.0 }, 100);
+        assert_eq!(*player.movement(), Movement::default());
+        player.set_movement(Movement {
+            friction: -1.0,
+            ..fast_movement()
+        });
+        assert_eq!(
+            *player.movement(),
+            Movement {
+                friction: 0.0,
+                ..fast_movement()
+            }
+        );
+    }
+
     #[test]
     fn angle() {
         let angle = Angle {
@@ -351,6 +1091,23 @@ mod tests {
             position: Default::default(),
             number_of_rays: Default::default(),
             move_handler: Default::default(),
+            field_of_view: Default::default(),
+            zoom: 1.0,
+            pitch: 0.0,
+            max_pitch: DEFAULT_MAX_PITCH,
+            floor_height: 0.0,
+            jump_height: 0.0,
+            vertical_velocity: 0.0,
+            is_crouching: false,
+            step_height: DEFAULT_STEP_HEIGHT,
+            velocity: Coordinate::ZERO,
+            movement: Movement::default(),
+            is_running: false,
+            is_walking_slowly: false,
+            speed_multipliers: SpeedMultipliers::default(),
+            camera_effects: CameraEffects::default(),
+            camera: CameraState::default(),
+            input_settings: InputSettings::default(),
         };
         assert_eq!(*player.angle(), angle);
     }
@@ -363,7 +1120,135 @@ mod tests {
             position: position.clone(),
             number_of_rays: Default::default(),
             move_handler: Default::default(),
+            field_of_view: Default::default(),
+            zoom: 1.0,
+            pitch: 0.0,
+            max_pitch: DEFAULT_MAX_PITCH,
+            floor_height: 0.0,
+            jump_height: 0.0,
+            vertical_velocity: 0.0,
+            is_crouching: false,
+            step_height: DEFAULT_STEP_HEIGHT,
+            velocity: Coordinate::ZERO,
+            movement: Movement::default(),
+            is_running: false,
+            is_walking_slowly: false,
+            speed_multipliers: SpeedMultipliers::default(),
+            camera_effects: CameraEffects::default(),
+            camera: CameraState::default(),
+            input_settings: InputSettings::default(),
         };
         assert_eq!(*player.position(), position);
     }
+
+    #[test]
+    fn field_of_view_and_zoom() {
+        let mut player = Player::new(
+            Angle {
+                start: Radians::new(std::f64::consts::PI / 4.0),
+                end: Radians::new(std::f64::consts::PI * 3.0 / 4.0),
+            },
+            Coordinate { x: 0.0, y: 0.0 },
+            100,
+        );
+        assert_eq!(player.field_of_view(), Radians::PI / 2.0);
+        assert_eq!(player.zoom(), 1.0);
+
+        player.set_zoom(2.0);
+        assert!(approx_eq!(
+            f64,
+            player.get_angle_value().to_f64(),
+            0.5_f64.atan() * 2.0,
+            epsilon = 1e-9
+        ));
+        assert!(approx_eq!(
+            f64,
+            player.angle.get_direction().to_f64(),
+            std::f64::consts::PI / 2.0,
+            epsilon = 1e-9
+        ));
+
+        player.set_field_of_view(Radians::new(std::f64::consts::PI * 2.0 / 3.0));
+        assert!(approx_eq!(
+            f64,
+            player.get_angle_value().to_f64(),
+            (3.0_f64.sqrt() / 2.0).atan() * 2.0,
+            epsilon = 1e-9
+        ));
+
+        // invalid values are ignored
+        player.set_zoom(0.0);
+        player.set_field_of_view(Radians::PI);
+        assert_eq!(player.zoom(), 2.0);
+        assert_eq!(
+            player.field_of_view(),
+            Radians::new(std::f64::consts::PI * 2.0 / 3.0)
+        );
+    }
+
+    #[test]
+    fn pitch() {
+        let mut player = Player::new(Default::default(), Coordinate { x: 0.0, y: 0.0 }, 100);
+        assert_eq!(player.pitch(), 0.0);
+
+        player.look_up(0.2);
+        player.look_up(0.1);
+        assert!(approx_eq!(f64, player.pitch(), 0.3, epsilon = 1e-9));
+        player.look_up(-1.0);
+        assert_eq!(player.pitch(), -DEFAULT_MAX_PITCH);
+
+        player.set_max_pitch(0.1);
+        assert_eq!(player.pitch(), -0.1);
+        player.set_pitch(0.5);
+        assert_eq!(player.pitch(), 0.1);
+
+        player.set_max_pitch(10.0);
+        player.set_pitch(10.0);
+        assert_eq!(player.pitch(), MAX_PITCH_LIMIT);
+
+        // values which are not finite are ignored
+        player.set_max_pitch(f64::NAN);
+        player.set_max_pitch(f64::INFINITY);
+        player.set_pitch(f64::NAN);
+        assert_eq!(player.pitch(), MAX_PITCH_LIMIT);
+        player.set_pitch(-1.0);
+        assert_eq!(player.pitch(), -1.0);
+        player.look_up(-10.0);
+        assert_eq!(player.pitch(), -MAX_PITCH_LIMIT);
+    }
+
+    #[test]
+    fn jump_and_crouch() {
+        let mut player = Player::new(Default::default(), Coordinate { x: 0.0, y: 0.0 }, 100);
+        assert_eq!(player.eye_offset(), 0.0);
+
+        player.jump();
+        player.update_height(0.1);
+        let expected_height = JUMP_VELOCITY * 0.1 - GRAVITY * 0.01 / 2.0;
+        assert!(approx_eq!(
+            f64,
+            player.eye_offset(),
+            expected_height,
+            epsilon = 1e-9
+        ));
+
+        // second jump in the air is ignored
+        player.jump();
+        player.update_height(0.1);
+        assert!(approx_eq!(
+            f64,
+            player.eye_offset(),
+            JUMP_VELOCITY * 0.2 - GRAVITY * 0.04 / 2.0,
+            epsilon = 1e-9
+        ));
+
+        player.update_height(10.0);
+        assert_eq!(player.eye_offset(), 0.0);
+
+        player.crouch(true);
+        player.set_floor_height(0.25);
+        assert_eq!(player.eye_offset(), 0.25 - CROUCH_DEPTH);
+        player.crouch(false);
+        assert_eq!(player.eye_offset(), 0.25);
+    }
 }
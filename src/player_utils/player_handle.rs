@@ -0,0 +1,316 @@
+use super::{Axis, CameraEffects, InputSettings, Movement, Radians, SpeedMultiplierId};
+use mockall_double::double;
+use std::sync::{Arc, RwLock};
+
+#[double]
+use super::player::Player;
+
+// Changes the player while the engine is running. Changes are visible in the
+// next frame.
+pub struct PlayerHandle {
+    player: Arc<RwLock<Player>>,
+}
+
+impl PlayerHandle {
+    pub(crate) fn new(player: Arc<RwLock<Player>>) -> Self {
+        Self { player }
+    }
+
+    pub fn field_of_view(&self) -> Radians {
+        self.player.read().unwrap().field_of_view()
+    }
+
+    // Horizontal field of view without the zoom. Vertical one is changed
+    // together with it, so the aspect ratio stays the same.
+    pub fn set_field_of_view(&self, field_of_view: Radians) {
+        self.player
+            .write()
+            .unwrap()
+            .set_field_of_view(field_of_view)
+    }
+
+    pub fn zoom(&self) -> f64 {
+        self.player.read().unwrap().zoom()
+    }
+
+    pub fn set_zoom(&self, zoom: f64) {
+        self.player.write().unwrap().set_zoom(zoom)
+    }
+
+    pub fn pitch(&self) -> f64 {
+        self.player.read().unwrap().pitch()
+    }
+
+    pub fn set_pitch(&self, pitch: f64) {
+        self.player.write().unwrap().set_pitch(pitch)
+    }
+
+    // pitch is clamped to the [-max_pitch, max_pitch] range
+    pub fn set_max_pitch(&self, max_pitch: f64) {
+        self.player.write().unwrap().set_max_pitch(max_pitch)
+    }
+
+    // floor under the player is moved up, e.g. on the stairs
+    pub fn set_floor_height(&self, floor_height: f64) {
+        self.player.write().unwrap().set_floor_height(floor_height)
+    }
+
+    // sectors with the floor higher by more than the step height block the player
+    pub fn set_step_height(&self, step_height: f64) {
+        self.player.write().unwrap().set_step_height(step_height)
+    }
+
+    pub fn movement(&self) -> Movement {
+        self.player.read().unwrap().movement().clone()
+    }
+
+    // acceleration, friction and max speeds of the walking and running player
+    pub fn set_movement(&self, movement: Movement) {
+        self.player.write().unwrap().set_movement(movement)
+    }
+
+    // Multiplies the max speed, e.g. in the slowing zone or after the
+    // power-up. Multiplier with the duration in seconds expires, the other
+    // one stays until it is removed.
+    pub fn add_speed_multiplier(
+        &self,
+        multiplier: f64,
+        duration: Option<f64>,
+    ) -> SpeedMultiplierId {
+        self.player
+            .write()
+            .unwrap()
+            .add_speed_multiplier(multiplier, duration)
+    }
+
+    pub fn remove_speed_multiplier(&self, id: SpeedMultiplierId) -> bool {
+        self.player.write().unwrap().remove_speed_multiplier(id)
+    }
+
+    pub fn clear_speed_multipliers(&self) {
+        self.player.write().unwrap().clear_speed_multipliers()
+    }
+
+    pub fn speed_multiplier(&self) -> f64 {
+        self.player.read().unwrap().speed_multiplier()
+    }
+
+    pub fn camera_effects(&self) -> CameraEffects {
+        self.player.read().unwrap().camera_effects().clone()
+    }
+
+    // head bob, screen shake, landing dip and strafe roll, enabled false
+    // turns all of them off
+    pub fn set_camera_effects(&self, camera_effects: CameraEffects) {
+        self.player
+            .write()
+            .unwrap()
+            .set_camera_effects(camera_effects)
+    }
+
+    // intensity 1 is the full shake, it fades out by itself
+    pub fn shake_camera(&self, intensity: f64) {
+        self.player.write().unwrap().shake_camera(intensity)
+    }
+
+    pub fn input_settings(&self) -> InputSettings {
+        self.player.read().unwrap().input_settings().clone()
+    }
+
+    // mouse sensitivity and inversion, turn speed of the arrow keys and the
+    // dead zone of the axes
+    pub fn set_input_settings(&self, input_settings: InputSettings) {
+        self.player
+            .write()
+            .unwrap()
+            .set_input_settings(input_settings)
+    }
+
+    // Drives the player by the analog axis, e.g. of the gamepad stick, with
+    // the value in [-1, 1]. Axis keeps the value until it is set again.
+    pub fn set_axis(&self, axis: Axis, value: f64) {
+        self.player.write().unwrap().set_axis(axis, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player_utils::movement::SpeedMultipliers;
+    use crate::player_utils::MockPlayer;
+
+    #[test]
+    fn field_of_view_and_zoom() {
+        let mut player = MockPlayer::default();
+        player
+            .expect_set_field_of_view()
+            .times(1)
+            .withf(|field_of_view| *field_of_view == Radians::PI / 3.0)
+            .return_const(());
+        player
+            .expect_field_of_view()
+            .times(1)
+            .return_const(Radians::PI / 3.0);
+        player
+            .expect_set_zoom()
+            .times(1)
+            .withf(|zoom| *zoom == 4.0)
+            .return_const(());
+        player.expect_zoom().times(1).return_const(4.0);
+
+        let handle = PlayerHandle::new(Arc::new(RwLock::new(player)));
+        handle.set_field_of_view(Radians::PI / 3.0);
+        assert_eq!(handle.field_of_view(), Radians::PI / 3.0);
+        handle.set_zoom(4.0);
+        assert_eq!(handle.zoom(), 4.0);
+    }
+
+    #[test]
+    fn pitch() {
+        let mut player = MockPlayer::default();
+        player
+            .expect_set_max_pitch()
+            .times(1)
+            .withf(|max_pitch| *max_pitch == 0.5)
+            .return_const(());
+        player
+            .expect_set_pitch()
+            .times(1)
+            .withf(|pitch| *pitch == -0.2)
+            .return_const(());
+        player.expect_pitch().times(1).return_const(-0.2);
+
+        let handle = PlayerHandle::new(Arc::new(RwLock::new(player)));
+        handle.set_max_pitch(0.5);
+        handle.set_pitch(-0.2);
+        assert_eq!(handle.pitch(), -0.2);
+    }
+
+    #[test]
+    fn set_floor_height() {
+        let mut player = MockPlayer::default();
+        player
+            .expect_set_floor_height()
+            .times(1)
+            .withf(|floor_height| *floor_height == 0.5)
+            .return_const(());
+
+        PlayerHandle::new(Arc::new(RwLock::new(player))).set_floor_height(0.5);
+    }
+
+    #[test]
+    fn movement() {
+        let movement = Movement {
+            max_run_speed: 50.0,
+            ..Movement::default()
+        };
+        let mut player = MockPlayer::default();
+        let expected = movement.clone();
+        player
+            .expect_set_movement()
+            .times(1)
+            .withf(move |movement| *movement == expected)
+            .return_const(());
+        player
+            .expect_movement()
+            .times(1)
+            .return_const(movement.clone());
+
+        let handle = PlayerHandle::new(Arc::new(RwLock::new(player)));
+        handle.set_movement(movement.clone());
+        assert_eq!(handle.movement(), movement);
+    }
+
+    #[test]
+    fn speed_multipliers() {
+        let mut seq = mockall::Sequence::new();
+        let mut player = MockPlayer::default();
+        let id = SpeedMultipliers::default().add(0.5, None);
+        player
+            .expect_add_speed_multiplier()
+            .times(1)
+            .withf(|multiplier, duration| *multiplier == 0.5 && *duration == Some(2.0))
+            .return_const(id)
+            .in_sequence(&mut seq);
+        player
+            .expect_speed_multiplier()
+            .times(1)
+            .return_const(0.5)
+            .in_sequence(&mut seq);
+        player
+            .expect_remove_speed_multiplier()
+            .times(1)
+            .withf(move |id_| *id_ == id)
+            .return_const(true)
+            .in_sequence(&mut seq);
+        player
+            .expect_clear_speed_multipliers()
+            .times(1)
+            .return_const(())
+            .in_sequence(&mut seq);
+
+        let handle = PlayerHandle::new(Arc::new(RwLock::new(player)));
+        assert_eq!(handle.add_speed_multiplier(0.5, Some(2.0)), id);
+        assert_eq!(handle.speed_multiplier(), 0.5);
+        assert!(handle.remove_speed_multiplier(id));
+        handle.clear_speed_multipliers();
+    }
+
+    #[test]
+    fn camera_effects() {
+        let camera_effects = CameraEffects {
+            enabled: false,
+            ..CameraEffects::default()
+        };
+        let mut player = MockPlayer::default();
+        let expected = camera_effects.clone();
+        player
+            .expect_set_camera_effects()
+            .times(1)
+            .withf(move |camera_effects| *camera_effects == expected)
+            .return_const(());
+        player
+            .expect_camera_effects()
+            .times(1)
+            .return_const(camera_effects.clone());
+        player
+            .expect_shake_camera()
+            .times(1)
+            .withf(|intensity| *intensity == 0.5)
+            .return_const(());
+
+        let handle = PlayerHandle::new(Arc::new(RwLock::new(player)));
+        handle.set_camera_effects(camera_effects.clone());
+        assert_eq!(handle.camera_effects(), camera_effects);
+        handle.shake_camera(0.5);
+    }
+
+    #[test]
+    fn input_settings_and_axis() {
+        let input_settings = InputSettings {
+            invert_y: true,
+            ..InputSettings::default()
+        };
+        let mut player = MockPlayer::default();
+        let expected = input_settings.clone();
+        player
+            .expect_set_input_settings()
+            .times(1)
+            .withf(move |input_settings| *input_settings == expected)
+            .return_const(());
+        player
+            .expect_input_settings()
+            .times(1)
+            .return_const(input_settings.clone());
+        player
+            .expect_set_axis()
+            .times(1)
+            .withf(|axis, value| *axis == Axis::LookUp && *value == 0.5)
+            .return_const(());
+
+        let handle = PlayerHandle::new(Arc::new(RwLock::new(player)));
+        handle.set_input_settings(input_settings.clone());
+        assert_eq!(handle.input_settings(), input_settings);
+        handle.set_axis(Axis::LookUp, 0.5);
+    }
+}
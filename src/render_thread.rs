@@ -1,5 +1,7 @@
-use crate::graph::{Coordinate, LinearGraph, Walls};
-use crate::map_element::MapElement;
+use crate::graph::{Column, Coordinate, LinearGraph, Walls};
+use crate::map::Layer;
+use crate::map_element::MapElements;
+use crate::player_utils::Angle;
 use mockall_double::double;
 use std::sync::mpsc::{Receiver, Sender};
 use std::sync::{Arc, RwLock};
@@ -14,15 +16,67 @@ use crate::map::Map;
 #[double]
 use crate::player_utils::Player;
 
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum RenderMode {
+    // hits are merged into walls, every wall is drawn as one polygon
+    #[default]
+    Walls,
+    // one ray is cast for every screen column, every column is drawn separately
+    Columns,
+}
+
+// Result of the render thread, depends on the render mode. Walls are kept
+// for every layer, columns of the following layers are appended.
+#[derive(PartialEq, Debug)]
+pub enum InSight {
+    Walls(Vec<Walls>),
+    Columns(Vec<Column>),
+}
+
+impl InSight {
+    pub fn merge(&mut self, in_sight: InSight) {
+        match (self, in_sight) {
+            // all threads render the same layers
+            (InSight::Walls(layers), InSight::Walls(layers_to_merge)) => {
+                for (walls, walls_to_merge) in layers.iter_mut().zip(layers_to_merge) {
+                    walls.merge(walls_to_merge);
+                }
+            }
+            (InSight::Columns(columns), InSight::Columns(columns_to_merge)) => {
+                columns.extend(columns_to_merge)
+            }
+            // all threads use the same render mode
+            _ => {}
+        }
+    }
+}
+
 pub struct RenderThread {
-    pub map_elements: Arc<RwLock<Vec<Box<dyn MapElement>>>>,
+    pub map_elements: Arc<RwLock<MapElements>>,
     pub player: Arc<RwLock<Player>>,
     pub map: Arc<Map>,
     pub rays: Arc<Rays>,
     pub start_render_receiver: Receiver<bool>,
-    pub sender_walls: Sender<(Walls, usize)>,
+    pub sender_walls: Sender<(InSight, usize)>,
     pub thread_index: usize,
     pub threads_amount: usize,
+    pub render_mode: Arc<RwLock<RenderMode>>,
+    // amount of the screen columns used in the columns render mode
+    pub columns: usize,
+    // height of the walls which don't have their own height
+    pub wall_height: f64,
+}
+
+// Returns the ray which goes through the middle of the column on the screen
+// plane, so columns are evenly spaced on the screen instead of in the angle.
+fn column_ray(angle: &Angle, index: usize, columns: usize) -> LinearGraph {
+    let direction = LinearGraph::from_radians(angle.get_direction()).direction;
+    let half_width = (angle.get_direction() - angle.start).tan();
+    let plane_x = half_width * ((index as f64 + 0.5) / columns as f64 * 2.0 - 1.0);
+    LinearGraph::from_direction(&Coordinate {
+        x: direction.x - direction.y * plane_x,
+        y: direction.y + direction.x * plane_x,
+    })
 }
 
 macro_rules! check_next_ray {
@@ -45,21 +99,70 @@ impl RenderThread {
         while let Ok(true) = self.start_render_receiver.recv() {
             let map_elements = self.map_elements.read().unwrap();
             let player = self.player.read().unwrap();
-            let walls = self.get_walls_in_sight(
-                player.position(),
-                self.rays
-                    .iter(player.angle(), self.thread_index, self.threads_amount),
-                &map_elements,
-            );
-            self.sender_walls.send((walls, self.thread_index)).unwrap();
+            let position = player.position();
+            let angle = player.angle();
+            let layers = map_elements.layers(self.wall_height);
+            let render_mode = *self.render_mode.read().unwrap();
+            let in_sight = match render_mode {
+                RenderMode::Walls => InSight::Walls(
+                    layers
+                        .iter()
+                        .map(|layer| {
+                            self.get_walls_in_sight(
+                                position,
+                                self.rays
+                                    .iter(angle, self.thread_index, self.threads_amount),
+                                &map_elements,
+                                layer,
+                            )
+                        })
+                        .collect(),
+                ),
+                RenderMode::Columns => InSight::Columns(
+                    layers
+                        .iter()
+                        .flat_map(|layer| {
+                            self.get_columns_in_sight(position, angle, &map_elements, layer)
+                        })
+                        .collect(),
+                ),
+            };
+            self.sender_walls
+                .send((in_sight, self.thread_index))
+                .unwrap();
         }
     }
 
+    // every thread casts rays for every threads_amount-th column
+    fn get_columns_in_sight(
+        &self,
+        position: &Coordinate,
+        angle: &Angle,
+        map_elements: &MapElements,
+        layer: &Layer,
+    ) -> Vec<Column> {
+        (self.thread_index..self.columns)
+            .step_by(self.threads_amount)
+            .filter_map(|index| {
+                let ray = column_ray(angle, index, self.columns);
+                self.map
+                    .cast_ray(position, &ray, map_elements, layer)
+                    .map(|(wall, _)| Column {
+                        index,
+                        hit_point: wall.cross_point(position, &ray),
+                        color: wall.primary_object_color,
+                        height: wall.height,
+                    })
+            })
+            .collect()
+    }
+
     fn get_walls_in_sight<'a>(
         &self,
         position: &Coordinate,
         mut rays_iter: impl Iterator<Item = &'a LinearGraph>,
-        map_elements: &Vec<Box<dyn MapElement>>,
+        map_elements: &MapElements,
+        layer: &Layer,
     ) -> Walls {
         let mut walls_in_sight = Walls(vec![]);
         let mut next_ray: Option<&LinearGraph> = None;
@@ -72,7 +175,8 @@ impl RenderThread {
             next_ray = None;
             loop {
                 if let Some((wall, ray_ret)) =
-                    self.map.cast_ray(position, &current_ray, map_elements)
+                    self.map
+                        .cast_ray(position, &current_ray, map_elements, layer)
                 {
                     if walls_in_sight.is_wall_in_object(&wall) {
                         break;
@@ -105,8 +209,9 @@ mod tests {
     use crate::graph::MockGraphMethods;
     use crate::graph::{MockRays, MockRaysIterator, Wall};
     use crate::map::MockMap;
-    use crate::map_element::{Color, Point};
-    use crate::player_utils::{Angle, MockPlayer, Radians};
+    use crate::map_element::Color;
+    use crate::player_utils::{MockPlayer, Radians};
+    use float_cmp::approx_eq;
     use mockall::*;
     use std::sync::mpsc::channel;
 
@@ -114,7 +219,7 @@ mod tests {
     fn render_thread_start() {
         let mut seq = Sequence::new();
 
-        let map_elements: Arc<RwLock<Vec<Box<dyn MapElement>>>> = Arc::new(RwLock::new(vec![]));
+        let map_elements = Arc::new(RwLock::new(MapElements::new()));
         let player = Arc::new(RwLock::new(MockPlayer::default()));
         let mut map = MockMap::default();
         let mut rays = MockRays::new();
@@ -135,7 +240,7 @@ mod tests {
         let less_than_context = MockGraphMethods::less_than_context();
 
         let (start_render_sender, start_render_receiver) = channel::<bool>();
-        let (sender_walls, receiver_walls) = channel::<(Walls, usize)>();
+        let (sender_walls, receiver_walls) = channel::<(InSight, usize)>();
         static thread_index: usize = 3;
         static threads_amount: usize = 4;
 
@@ -146,40 +251,80 @@ mod tests {
         };
         let walls_rays = vec![
             Some((
-                Wall::new(Point { x: 2, y: 4 }, Point { x: 3, y: 4 }, Color::Red),
+                Wall::new(
+                    Coordinate { x: 2.0, y: 4.0 },
+                    Coordinate { x: 3.0, y: 4.0 },
+                    Color::Red,
+                ),
                 LinearGraph::default(),
             )),
             Some((
-                Wall::new(Point { x: 3, y: 4 }, Point { x: 4, y: 4 }, Color::Red),
+                Wall::new(
+                    Coordinate { x: 3.0, y: 4.0 },
+                    Coordinate { x: 4.0, y: 4.0 },
+                    Color::Red,
+                ),
                 LinearGraph::default(),
             )),
             Some((
-                Wall::new(Point { x: 4, y: 2 }, Point { x: 5, y: 2 }, Color::Red),
+                Wall::new(
+                    Coordinate { x: 4.0, y: 2.0 },
+                    Coordinate { x: 5.0, y: 2.0 },
+                    Color::Red,
+                ),
                 LinearGraph::default(),
             )),
             Some((
-                Wall::new(Point { x: 3, y: 4 }, Point { x: 4, y: 4 }, Color::Red),
+                Wall::new(
+                    Coordinate { x: 3.0, y: 4.0 },
+                    Coordinate { x: 4.0, y: 4.0 },
+                    Color::Red,
+                ),
                 LinearGraph::default(),
             )),
             Some((
-                Wall::new(Point { x: 4, y: 2 }, Point { x: 5, y: 2 }, Color::Red),
+                Wall::new(
+                    Coordinate { x: 4.0, y: 2.0 },
+                    Coordinate { x: 5.0, y: 2.0 },
+                    Color::Red,
+                ),
                 LinearGraph::default(),
             )),
             Some((
-                Wall::new(Point { x: 5, y: 2 }, Point { x: 5, y: 1 }, Color::Green),
+                Wall::new(
+                    Coordinate { x: 5.0, y: 2.0 },
+                    Coordinate { x: 5.0, y: 1.0 },
+                    Color::Green,
+                ),
                 LinearGraph::default(),
             )),
             None,
             Some((
-                Wall::new(Point { x: 5, y: 1 }, Point { x: 5, y: 0 }, Color::Green),
+                Wall::new(
+                    Coordinate { x: 5.0, y: 1.0 },
+                    Coordinate { x: 5.0, y: 0.0 },
+                    Color::Green,
+                ),
                 LinearGraph::default(),
             )),
         ];
 
         let walls_in_sight = Walls(vec![
-            Wall::new(Point { x: 2, y: 4 }, Point { x: 4, y: 4 }, Color::Red),
-            Wall::new(Point { x: 4, y: 2 }, Point { x: 5, y: 2 }, Color::Red),
-            Wall::new(Point { x: 5, y: 2 }, Point { x: 5, y: 0 }, Color::Green),
+            Wall::new(
+                Coordinate { x: 2.0, y: 4.0 },
+                Coordinate { x: 4.0, y: 4.0 },
+                Color::Red,
+            ),
+            Wall::new(
+                Coordinate { x: 4.0, y: 2.0 },
+                Coordinate { x: 5.0, y: 2.0 },
+                Color::Red,
+            ),
+            Wall::new(
+                Coordinate { x: 5.0, y: 2.0 },
+                Coordinate { x: 5.0, y: 0.0 },
+                Color::Green,
+            ),
         ]);
 
         {
@@ -234,6 +379,9 @@ mod tests {
             sender_walls,
             thread_index,
             threads_amount,
+            render_mode: Arc::new(RwLock::new(RenderMode::Walls)),
+            columns: 0,
+            wall_height: 1.0,
         };
         start_render_sender.send(true).unwrap();
         start_render_sender.send(false).unwrap();
@@ -242,7 +390,117 @@ mod tests {
 
         assert_eq!(
             receiver_walls.recv().unwrap(),
-            (walls_in_sight, thread_index)
+            (InSight::Walls(vec![walls_in_sight]), thread_index)
+        );
+    }
+
+    #[test]
+    fn render_thread_columns() {
+        let player = Arc::new(RwLock::new(MockPlayer::default()));
+        let mut map = MockMap::default();
+        let (start_render_sender, start_render_receiver) = channel::<bool>();
+        let (sender_walls, receiver_walls) = channel::<(InSight, usize)>();
+
+        {
+            let mut player_write = player.write().unwrap();
+            player_write
+                .expect_position()
+                .times(1)
+                .return_const(Coordinate { x: 0.0, y: 0.0 });
+            player_write.expect_angle().times(1).return_const(Angle {
+                start: Radians::ZERO,
+                end: Radians::PI / 2.0,
+            });
+        }
+
+        // thread 1 of 4 casts rays for the columns 1 and 5 of 8
+        map.expect_cast_ray()
+            .once()
+            .withf(|_, ray, _, _| {
+                approx_eq!(
+                    f64,
+                    ray.direction.y / ray.direction.x,
+                    0.375 / 1.625,
+                    ulps = 4
+                )
+            })
+            .return_const(Some((
+                Wall::new(
+                    Coordinate { x: 4.0, y: -10.0 },
+                    Coordinate { x: 4.0, y: 10.0 },
+                    Color::Red,
+                ),
+                LinearGraph::default(),
+            )));
+        map.expect_cast_ray().once().return_const(None);
+
+        let render_thread = RenderThread {
+            map_elements: Arc::new(RwLock::new(MapElements::new())),
+            player,
+            map: Arc::new(map),
+            rays: Arc::new(MockRays::new()),
+            start_render_receiver,
+            sender_walls,
+            thread_index: 1,
+            threads_amount: 4,
+            render_mode: Arc::new(RwLock::new(RenderMode::Columns)),
+            columns: 8,
+            wall_height: 1.0,
+        };
+        start_render_sender.send(true).unwrap();
+        start_render_sender.send(false).unwrap();
+
+        render_thread.start();
+
+        if let (InSight::Columns(columns), 1) = receiver_walls.recv().unwrap() {
+            assert_eq!(columns.len(), 1);
+            assert_eq!(columns[0].index, 1);
+            assert_eq!(columns[0].color, Color::Red);
+            assert!(approx_eq!(f64, columns[0].hit_point.x, 4.0, ulps = 2));
+            assert!(approx_eq!(
+                f64,
+                columns[0].hit_point.y,
+                4.0 * 0.375 / 1.625,
+                ulps = 4
+            ));
+        } else {
+            panic!("render_thread_columns test failed");
+        }
+    }
+
+    #[test]
+    fn in_sight_merge_layers() {
+        let walls = |x| {
+            Walls(vec![Wall::new(
+                Coordinate { x, y: 0.0 },
+                Coordinate { x, y: 1.0 },
+                Color::Red,
+            )])
+        };
+        let mut in_sight = InSight::Walls(vec![walls(1.0), walls(2.0)]);
+        in_sight.merge(InSight::Walls(vec![walls(3.0), walls(4.0)]));
+        assert_eq!(
+            in_sight,
+            InSight::Walls(vec![
+                Walls(vec![walls(1.0).0[0].clone(), walls(3.0).0[0].clone()]),
+                Walls(vec![walls(2.0).0[0].clone(), walls(4.0).0[0].clone()]),
+            ])
+        );
+    }
+
+    #[test]
+    fn in_sight_merge() {
+        let column = |index| Column {
+            index,
+            hit_point: Coordinate::default(),
+            color: Color::Red,
+            height: Default::default(),
+        };
+        let mut in_sight = InSight::Columns(vec![column(0), column(2)]);
+        in_sight.merge(InSight::Columns(vec![column(1)]));
+        assert_eq!(
+            in_sight,
+            InSight::Columns(vec![column(0), column(2), column(1)])
         );
     }
 }
@@ -0,0 +1,545 @@
+use crate::graph::Coordinate;
+use crate::map_element::{DoorArea, DoorType, Point, Rectangle, WallMap};
+use std::collections::VecDeque;
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum ValidationError {
+    SpawnOutsideMap,
+    SpawnInsideWall,
+    DoorOutsideMap {
+        door: usize,
+    },
+    DoorOverlapsWall {
+        door: usize,
+        point: Point,
+    },
+    DoorsOverlap {
+        first_door: usize,
+        second_door: usize,
+    },
+    OpenAreaOutsideMap {
+        door: usize,
+    },
+    OpenAreaMissesDoor {
+        door: usize,
+    },
+    // player can open the door only from one side
+    OpenAreaOneSided {
+        door: usize,
+    },
+    // player can reach the map border so rays can leave the map
+    OpenBorder {
+        point: Point,
+    },
+}
+
+// Free area which can't be reached from the spawn.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Region {
+    pub point: Point,
+    pub size: usize,
+    pub touches_border: bool,
+}
+
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct ValidationReport {
+    pub errors: Vec<ValidationError>,
+    pub reachable_area: usize,
+    pub dead_ends: Vec<Point>,
+    pub disconnected_regions: Vec<Region>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+// returns (min_x, min_y, max_x, max_y) of the rectangle
+fn bounds(rectangle: &Rectangle) -> (i64, i64, i64, i64) {
+    (
+        rectangle.point_a.x.min(rectangle.point_b.x),
+        rectangle.point_a.y.min(rectangle.point_b.y),
+        rectangle.point_a.x.max(rectangle.point_b.x),
+        rectangle.point_a.y.max(rectangle.point_b.y),
+    )
+}
+
+fn rectangles_overlap(first: &Rectangle, second: &Rectangle) -> bool {
+    let (first_min_x, first_min_y, first_max_x, first_max_y) = bounds(first);
+    let (second_min_x, second_min_y, second_max_x, second_max_y) = bounds(second);
+    first_min_x <= second_max_x
+        && second_min_x <= first_max_x
+        && first_min_y <= second_max_y
+        && second_min_y <= first_max_y
+}
+
+// Checks the level made of the WallMap, doors and the spawn position and analyses
+// the area reachable from the spawn. Doors are treated as passable.
+// Dead ends are searched on the grid of cell_size x cell_size pixels cells,
+// cell is free when its middle pixel is free. Use cell size equal to the corridor width.
+pub struct MapValidator {
+    cell_size: i64,
+}
+
+impl MapValidator {
+    pub fn new(cell_size: u32) -> Self {
+        Self {
+            cell_size: cell_size.max(1) as i64,
+        }
+    }
+
+    pub fn validate(
+        &self,
+        wall_map: &WallMap,
+        doors: &[DoorArea],
+        spawn: &Coordinate,
+    ) -> ValidationReport {
+        let map = wall_map.get_map();
+        let mut validation = Validation {
+            width: map.width,
+            height: map.height,
+            walls: vec![false; (map.width * map.height) as usize],
+            report: Default::default(),
+        };
+        for y in 0..map.height {
+            for x in 0..map.width {
+                validation.walls[(y * map.width + x) as usize] =
+                    wall_map.is_wall_pixel(x as u32, y as u32);
+            }
+        }
+        validation.validate_doors(doors);
+        for door in doors {
+            validation.clear_area(&door.area);
+        }
+        if let Some(spawn_point) = validation.validate_spawn(spawn) {
+            let reachable = validation.analyse_reachable_area(&spawn_point);
+            validation.find_dead_ends(&reachable, self.cell_size);
+            validation.find_disconnected_regions(&reachable);
+        }
+        validation.report
+    }
+}
+
+struct Validation {
+    width: i64,
+    height: i64,
+    walls: Vec<bool>,
+    report: ValidationReport,
+}
+
+impl Validation {
+    fn is_inside(&self, x: i64, y: i64) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height
+    }
+
+    fn is_rectangle_inside(&self, rectangle: &Rectangle) -> bool {
+        let (min_x, min_y, max_x, max_y) = bounds(rectangle);
+        self.is_inside(min_x, min_y) && self.is_inside(max_x, max_y)
+    }
+
+    fn is_border(&self, x: i64, y: i64) -> bool {
+        x == 0 || y == 0 || x == self.width - 1 || y == self.height - 1
+    }
+
+    fn is_wall(&self, x: i64, y: i64) -> bool {
+        self.walls[(y * self.width + x) as usize]
+    }
+
+    fn clear_area(&mut self, rectangle: &Rectangle) {
+        let (min_x, min_y, max_x, max_y) = bounds(rectangle);
+        for y in min_y.max(0)..=max_y.min(self.height - 1) {
+            for x in min_x.max(0)..=max_x.min(self.width - 1) {
+                self.walls[(y * self.width + x) as usize] = false;
+            }
+        }
+    }
+
+    fn first_wall_in_area(&self, rectangle: &Rectangle) -> Option<Point> {
+        let (min_x, min_y, max_x, max_y) = bounds(rectangle);
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                if self.is_wall(x, y) {
+                    return Some(Point { x, y });
+                }
+            }
+        }
+        None
+    }
+
+    fn is_open_area_two_sided(door: &DoorArea, open_area: &Rectangle) -> bool {
+        let (door_min_x, door_min_y, door_max_x, door_max_y) = bounds(&door.area);
+        let (open_min_x, open_min_y, open_max_x, open_max_y) = bounds(open_area);
+        if door.door_type == DoorType::Vertical {
+            return open_min_x < door_min_x && open_max_x > door_max_x;
+        }
+        open_min_y < door_min_y && open_max_y > door_max_y
+    }
+
+    fn validate_doors(&mut self, doors: &[DoorArea]) {
+        for (index, door) in doors.iter().enumerate() {
+            if !self.is_rectangle_inside(&door.area) {
+                self.report
+                    .errors
+                    .push(ValidationError::DoorOutsideMap { door: index });
+            } else if let Some(point) = self.first_wall_in_area(&door.area) {
+                self.report
+                    .errors
+                    .push(ValidationError::DoorOverlapsWall { door: index, point });
+            }
+
+            for (other_index, other_door) in doors.iter().enumerate().skip(index + 1) {
+                if rectangles_overlap(&door.area, &other_door.area) {
+                    self.report.errors.push(ValidationError::DoorsOverlap {
+                        first_door: index,
+                        second_door: other_index,
+                    });
+                }
+            }
+
+            let open_area = door.open_door_area();
+            if !self.is_rectangle_inside(&open_area) {
+                self.report
+                    .errors
+                    .push(ValidationError::OpenAreaOutsideMap { door: index });
+            }
+            let (door_min_x, door_min_y, door_max_x, door_max_y) = bounds(&door.area);
+            let (open_min_x, open_min_y, open_max_x, open_max_y) = bounds(&open_area);
+            if open_min_x > door_min_x
+                || open_min_y > door_min_y
+                || open_max_x < door_max_x
+                || open_max_y < door_max_y
+            {
+                self.report
+                    .errors
+                    .push(ValidationError::OpenAreaMissesDoor { door: index });
+            } else if !Self::is_open_area_two_sided(door, &open_area) {
+                self.report
+                    .errors
+                    .push(ValidationError::OpenAreaOneSided { door: index });
+            }
+        }
+    }
+
+    fn validate_spawn(&mut self, spawn: &Coordinate) -> Option<Point> {
+        if spawn.x < 0.0 || spawn.y < 0.0 {
+            self.report.errors.push(ValidationError::SpawnOutsideMap);
+            return None;
+        }
+        let point = Point::new(spawn.x, spawn.y);
+        if !self.is_inside(point.x, point.y) {
+            self.report.errors.push(ValidationError::SpawnOutsideMap);
+            return None;
+        }
+        if self.is_wall(point.x, point.y) {
+            self.report.errors.push(ValidationError::SpawnInsideWall);
+            return None;
+        }
+        Some(point)
+    }
+
+    // returns free points connected with the start point, indexed by y * width + x
+    fn flood_fill(&self, start: &Point, visited: &mut [bool]) -> Vec<Point> {
+        let mut region = vec![];
+        let mut queue = VecDeque::new();
+        visited[(start.y * self.width + start.x) as usize] = true;
+        queue.push_back(start.clone());
+        while let Some(point) = queue.pop_front() {
+            for (x, y) in [
+                (point.x - 1, point.y),
+                (point.x + 1, point.y),
+                (point.x, point.y - 1),
+                (point.x, point.y + 1),
+            ]
+            .iter()
+            {
+                let index = (y * self.width + x) as usize;
+                if self.is_inside(*x, *y) && !self.is_wall(*x, *y) && !visited[index] {
+                    visited[index] = true;
+                    queue.push_back(Point { x: *x, y: *y });
+                }
+            }
+            region.push(point);
+        }
+        region
+    }
+
+    fn analyse_reachable_area(&mut self, spawn_point: &Point) -> Vec<bool> {
+        let mut reachable = vec![false; self.walls.len()];
+        let region = self.flood_fill(spawn_point, &mut reachable);
+        if let Some(point) = region.iter().find(|point| self.is_border(point.x, point.y)) {
+            self.report.errors.push(ValidationError::OpenBorder {
+                point: point.clone(),
+            });
+        }
+        self.report.reachable_area = region.len();
+        reachable
+    }
+
+    fn find_dead_ends(&mut self, reachable: &[bool], cell_size: i64) {
+        let mut dead_ends = vec![];
+        let is_cell_reachable = |cell_x: i64, cell_y: i64| {
+            let x = cell_x * cell_size + cell_size / 2;
+            let y = cell_y * cell_size + cell_size / 2;
+            self.is_inside(x, y) && reachable[(y * self.width + x) as usize]
+        };
+        for cell_y in 0..(self.height + cell_size - 1) / cell_size {
+            for cell_x in 0..(self.width + cell_size - 1) / cell_size {
+                if !is_cell_reachable(cell_x, cell_y) {
+                    continue;
+                }
+                let reachable_neighbours = [
+                    is_cell_reachable(cell_x - 1, cell_y),
+                    is_cell_reachable(cell_x + 1, cell_y),
+                    is_cell_reachable(cell_x, cell_y - 1),
+                    is_cell_reachable(cell_x, cell_y + 1),
+                ]
+                .iter()
+                .filter(|is_reachable| **is_reachable)
+                .count();
+                if reachable_neighbours == 1 {
+                    dead_ends.push(Point {
+                        x: cell_x * cell_size + cell_size / 2,
+                        y: cell_y * cell_size + cell_size / 2,
+                    });
+                }
+            }
+        }
+        self.report.dead_ends = dead_ends;
+    }
+
+    fn find_disconnected_regions(&mut self, reachable: &[bool]) {
+        let mut visited = reachable.to_vec();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if visited[(y * self.width + x) as usize] || self.is_wall(x, y) {
+                    continue;
+                }
+                let region = self.flood_fill(&Point { x, y }, &mut visited);
+                self.report.disconnected_regions.push(Region {
+                    point: Point { x, y },
+                    size: region.len(),
+                    touches_border: region.iter().any(|point| self.is_border(point.x, point.y)),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // '#' - wall, '.' - free area
+    fn wall_map(rows: &[&str]) -> WallMap {
+        let mut image = image::RgbaImage::from_pixel(
+            rows[0].len() as u32,
+            rows.len() as u32,
+            image::Rgba([255, 255, 255, 255]),
+        );
+        for (y, row) in rows.iter().enumerate() {
+            for (x, tile) in row.chars().enumerate() {
+                if tile == '#' {
+                    image.put_pixel(x as u32, y as u32, image::Rgba([0, 0, 0, 255]));
+                }
+            }
+        }
+        WallMap::from_image(image, None)
+    }
+
+    fn door(point_a: Point, point_b: Point, door_type: DoorType, open_area: Rectangle) -> DoorArea {
+        DoorArea {
+            area: Rectangle { point_a, point_b },
+            door_type,
+            open_area: Some(open_area),
+        }
+    }
+
+    fn rectangle(x1: i64, y1: i64, x2: i64, y2: i64) -> Rectangle {
+        Rectangle {
+            point_a: Point { x: x1, y: y1 },
+            point_b: Point { x: x2, y: y2 },
+        }
+    }
+
+    #[test]
+    fn valid_level() {
+        let wall_map = wall_map(&[
+            "#########", //
+            "#...#...#", //
+            "#...#...#", //
+            "#.......#", //
+            "#########", //
+        ]);
+        let doors = vec![door(
+            Point { x: 4, y: 3 },
+            Point { x: 4, y: 3 },
+            DoorType::Vertical,
+            rectangle(2, 1, 6, 3),
+        )];
+        let report =
+            MapValidator::new(1).validate(&wall_map, &doors, &Coordinate { x: 1.5, y: 1.5 });
+
+        assert!(report.is_valid());
+        assert_eq!(report.errors, vec![]);
+        assert_eq!(report.reachable_area, 19);
+        assert_eq!(report.disconnected_regions, vec![]);
+    }
+
+    #[test]
+    fn spawn_errors() {
+        let wall_map = wall_map(&["###", "#.#", "###"]);
+        let validator = MapValidator::new(1);
+
+        assert_eq!(
+            validator
+                .validate(&wall_map, &[], &Coordinate { x: 0.5, y: 0.5 })
+                .errors,
+            vec![ValidationError::SpawnInsideWall]
+        );
+        assert_eq!(
+            validator
+                .validate(&wall_map, &[], &Coordinate { x: 3.5, y: 0.5 })
+                .errors,
+            vec![ValidationError::SpawnOutsideMap]
+        );
+        assert_eq!(
+            validator
+                .validate(&wall_map, &[], &Coordinate { x: -0.5, y: 1.0 })
+                .errors,
+            vec![ValidationError::SpawnOutsideMap]
+        );
+    }
+
+    #[test]
+    fn door_errors() {
+        let wall_map = wall_map(&[
+            "##########", //
+            "#........#", //
+            "#........#", //
+            "#........#", //
+            "##########", //
+        ]);
+        let doors = vec![
+            door(
+                Point { x: 4, y: 0 },
+                Point { x: 4, y: 2 },
+                DoorType::Vertical,
+                rectangle(2, 0, 6, 3),
+            ),
+            door(
+                Point { x: 3, y: 2 },
+                Point { x: 5, y: 2 },
+                DoorType::Horizontal,
+                rectangle(7, 1, 8, 3),
+            ),
+            door(
+                Point { x: 7, y: 1 },
+                Point { x: 7, y: 3 },
+                DoorType::Vertical,
+                rectangle(7, 0, 8, 4),
+            ),
+            door(
+                Point { x: 8, y: 1 },
+                Point { x: 11, y: 3 },
+                DoorType::Vertical,
+                rectangle(5, 0, 12, 4),
+            ),
+        ];
+        let report =
+            MapValidator::new(1).validate(&wall_map, &doors, &Coordinate { x: 1.5, y: 1.5 });
+
+        assert_eq!(
+            report.errors,
+            vec![
+                ValidationError::DoorOverlapsWall {
+                    door: 0,
+                    point: Point { x: 4, y: 0 }
+                },
+                ValidationError::DoorsOverlap {
+                    first_door: 0,
+                    second_door: 1
+                },
+                ValidationError::OpenAreaMissesDoor { door: 1 },
+                ValidationError::OpenAreaOneSided { door: 2 },
+                ValidationError::DoorOutsideMap { door: 3 },
+                ValidationError::OpenAreaOutsideMap { door: 3 },
+                ValidationError::OpenBorder {
+                    point: Point { x: 4, y: 0 }
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn open_border() {
+        let wall_map = wall_map(&[
+            "#####", //
+            "#....", //
+            "#####", //
+        ]);
+        let report = MapValidator::new(1).validate(&wall_map, &[], &Coordinate { x: 1.5, y: 1.5 });
+        assert_eq!(
+            report.errors,
+            vec![ValidationError::OpenBorder {
+                point: Point { x: 4, y: 1 }
+            }]
+        );
+    }
+
+    #[test]
+    fn dead_ends_and_disconnected_regions() {
+        let wall_map = wall_map(&[
+            "#########", //
+            "#.#.....#", //
+            "#.#.###.#", //
+            "#...#.#.#", //
+            "#########", //
+            "........#", //
+        ]);
+        let report = MapValidator::new(1).validate(&wall_map, &[], &Coordinate { x: 1.5, y: 1.5 });
+
+        assert!(report.is_valid());
+        assert_eq!(report.reachable_area, 13);
+        assert_eq!(
+            report.dead_ends,
+            vec![Point { x: 1, y: 1 }, Point { x: 7, y: 3 }]
+        );
+        assert_eq!(
+            report.disconnected_regions,
+            vec![
+                Region {
+                    point: Point { x: 5, y: 3 },
+                    size: 1,
+                    touches_border: false
+                },
+                Region {
+                    point: Point { x: 0, y: 5 },
+                    size: 8,
+                    touches_border: true
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn dead_ends_cell_size() {
+        let wall_map = wall_map(&[
+            "########", //
+            "########", //
+            "##....##", //
+            "##....##", //
+            "######..", //
+            "######..", //
+            "########", //
+            "########", //
+        ]);
+        let report = MapValidator::new(2).validate(&wall_map, &[], &Coordinate { x: 2.5, y: 2.5 });
+
+        assert_eq!(report.reachable_area, 8);
+        assert_eq!(
+            report.dead_ends,
+            vec![Point { x: 3, y: 3 }, Point { x: 5, y: 3 }]
+        );
+    }
+}